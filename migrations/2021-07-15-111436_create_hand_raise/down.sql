@@ -0,0 +1,2 @@
+ALTER TABLE agent DROP COLUMN speak_granted;
+DROP TABLE hand_raise;
@@ -0,0 +1,12 @@
+CREATE TABLE hand_raise (
+    room_id UUID NOT NULL,
+    agent_id AGENT_ID NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    FOREIGN KEY (room_id) REFERENCES room (id) ON DELETE CASCADE,
+    PRIMARY KEY (room_id, agent_id)
+);
+
+CREATE INDEX hand_raise_room_id_created_at_idx ON hand_raise (room_id, created_at);
+
+ALTER TABLE agent ADD COLUMN speak_granted BOOLEAN NOT NULL DEFAULT FALSE;
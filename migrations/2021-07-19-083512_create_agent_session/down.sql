@@ -0,0 +1,2 @@
+DROP TABLE agent_session;
+DROP TYPE agent_session_leave_reason;
@@ -0,0 +1,15 @@
+CREATE TYPE agent_session_leave_reason AS ENUM ('request', 'subscription_delete', 'kick', 'ban');
+
+CREATE TABLE agent_session (
+    id UUID DEFAULT gen_random_uuid(),
+    room_id UUID NOT NULL,
+    agent_id agent_id NOT NULL,
+    entered_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    left_at TIMESTAMPTZ,
+    leave_reason agent_session_leave_reason,
+
+    FOREIGN KEY (room_id) REFERENCES room (id) ON DELETE CASCADE,
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX agent_session_room_id_entered_at_idx ON agent_session (room_id, entered_at);
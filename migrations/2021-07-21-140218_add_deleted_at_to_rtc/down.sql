@@ -0,0 +1 @@
+ALTER TABLE rtc DROP COLUMN deleted_at;
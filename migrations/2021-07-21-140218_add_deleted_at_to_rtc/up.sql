@@ -0,0 +1 @@
+ALTER TABLE rtc ADD COLUMN deleted_at TIMESTAMPTZ;
@@ -0,0 +1,13 @@
+DELETE FROM rtc_writer_config WHERE kind <> 'camera';
+ALTER TABLE rtc_writer_config DROP CONSTRAINT rtc_writer_config_pkey;
+ALTER TABLE rtc_writer_config ADD PRIMARY KEY (rtc_id);
+ALTER TABLE rtc_writer_config DROP COLUMN kind;
+
+DELETE FROM agent_connection WHERE kind <> 'camera';
+ALTER TABLE agent_connection DROP CONSTRAINT agent_connection_pkey;
+ALTER TABLE agent_connection ADD PRIMARY KEY (agent_id, rtc_id);
+ALTER TABLE agent_connection DROP COLUMN kind;
+
+ALTER TABLE janus_rtc_stream DROP COLUMN kind;
+
+DROP TYPE rtc_stream_kind;
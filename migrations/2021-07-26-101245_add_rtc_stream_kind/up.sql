@@ -0,0 +1,11 @@
+CREATE TYPE rtc_stream_kind AS ENUM ('camera', 'screen', 'audio_only');
+
+ALTER TABLE janus_rtc_stream ADD COLUMN kind rtc_stream_kind NOT NULL DEFAULT 'camera';
+
+ALTER TABLE agent_connection ADD COLUMN kind rtc_stream_kind NOT NULL DEFAULT 'camera';
+ALTER TABLE agent_connection DROP CONSTRAINT agent_connection_pkey;
+ALTER TABLE agent_connection ADD PRIMARY KEY (agent_id, rtc_id, kind);
+
+ALTER TABLE rtc_writer_config ADD COLUMN kind rtc_stream_kind NOT NULL DEFAULT 'camera';
+ALTER TABLE rtc_writer_config DROP CONSTRAINT rtc_writer_config_pkey;
+ALTER TABLE rtc_writer_config ADD PRIMARY KEY (rtc_id, kind);
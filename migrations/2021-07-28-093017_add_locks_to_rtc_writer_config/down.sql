@@ -0,0 +1,2 @@
+ALTER TABLE rtc_writer_config DROP COLUMN send_video_locked;
+ALTER TABLE rtc_writer_config DROP COLUMN send_audio_locked;
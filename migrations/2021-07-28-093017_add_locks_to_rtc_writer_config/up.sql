@@ -0,0 +1,2 @@
+ALTER TABLE rtc_writer_config ADD COLUMN send_audio_locked BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE rtc_writer_config ADD COLUMN send_video_locked BOOLEAN NOT NULL DEFAULT FALSE;
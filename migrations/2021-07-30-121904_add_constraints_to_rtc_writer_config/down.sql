@@ -0,0 +1,5 @@
+ALTER TABLE rtc_writer_config
+    DROP COLUMN max_framerate,
+    DROP COLUMN max_width,
+    DROP COLUMN max_height,
+    DROP COLUMN audio_remb;
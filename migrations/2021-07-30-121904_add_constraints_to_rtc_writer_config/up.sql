@@ -0,0 +1,5 @@
+ALTER TABLE rtc_writer_config
+    ADD COLUMN audio_remb BIGINT CHECK (audio_remb IS NULL OR audio_remb > 0),
+    ADD COLUMN max_height INT CHECK (max_height IS NULL OR max_height > 0),
+    ADD COLUMN max_width INT CHECK (max_width IS NULL OR max_width > 0),
+    ADD COLUMN max_framerate INT CHECK (max_framerate IS NULL OR max_framerate > 0);
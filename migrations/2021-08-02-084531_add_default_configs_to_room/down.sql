@@ -0,0 +1,3 @@
+ALTER TABLE room
+    DROP COLUMN default_reader_config,
+    DROP COLUMN default_writer_config;
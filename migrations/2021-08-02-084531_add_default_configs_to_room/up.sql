@@ -0,0 +1,3 @@
+ALTER TABLE room
+    ADD COLUMN default_writer_config JSONB,
+    ADD COLUMN default_reader_config JSONB;
@@ -0,0 +1,2 @@
+DROP TABLE message;
+ALTER TABLE room DROP COLUMN persistent_message_labels;
@@ -0,0 +1,2 @@
+ALTER TABLE room DROP COLUMN region;
+ALTER TABLE janus_backend DROP COLUMN region;
@@ -0,0 +1,2 @@
+ALTER TABLE janus_backend ADD COLUMN region TEXT;
+ALTER TABLE room ADD COLUMN region TEXT;
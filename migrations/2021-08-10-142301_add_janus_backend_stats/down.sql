@@ -0,0 +1,3 @@
+ALTER TABLE janus_backend DROP COLUMN stats_updated_at;
+ALTER TABLE janus_backend DROP COLUMN cpu_usage;
+ALTER TABLE janus_backend DROP COLUMN egress_bitrate;
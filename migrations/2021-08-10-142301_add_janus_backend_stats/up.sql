@@ -0,0 +1,3 @@
+ALTER TABLE janus_backend ADD COLUMN egress_bitrate BIGINT;
+ALTER TABLE janus_backend ADD COLUMN cpu_usage DOUBLE PRECISION;
+ALTER TABLE janus_backend ADD COLUMN stats_updated_at TIMESTAMPTZ;
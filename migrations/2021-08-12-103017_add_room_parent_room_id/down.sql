@@ -0,0 +1,2 @@
+DROP INDEX room_parent_room_id_idx;
+ALTER TABLE room DROP COLUMN parent_room_id;
@@ -0,0 +1,2 @@
+ALTER TABLE room ADD COLUMN parent_room_id UUID REFERENCES room (id) ON DELETE CASCADE;
+CREATE INDEX room_parent_room_id_idx ON room (parent_room_id);
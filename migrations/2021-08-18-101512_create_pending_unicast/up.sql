@@ -0,0 +1,14 @@
+CREATE TABLE pending_unicast (
+    id UUID NOT NULL,
+    room_id UUID NOT NULL,
+    target AGENT_ID NOT NULL,
+    reqp JSONB NOT NULL,
+    deadline TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    FOREIGN KEY (room_id) REFERENCES room (id) ON DELETE CASCADE,
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX pending_unicast_deadline_idx ON pending_unicast (deadline);
+CREATE INDEX pending_unicast_room_id_target_idx ON pending_unicast (room_id, target);
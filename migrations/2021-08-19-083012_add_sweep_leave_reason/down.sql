@@ -0,0 +1,6 @@
+-- Treat swept agents as disconnected and drop `sweep` leave reason.
+UPDATE agent_session SET leave_reason = 'subscription_delete' WHERE leave_reason = 'sweep';
+ALTER TYPE agent_session_leave_reason RENAME TO agent_session_leave_reason_old;
+CREATE TYPE agent_session_leave_reason AS ENUM ('request', 'subscription_delete', 'kick', 'ban');
+ALTER TABLE agent_session ALTER COLUMN leave_reason TYPE agent_session_leave_reason USING leave_reason::text::agent_session_leave_reason;
+DROP TYPE agent_session_leave_reason_old;
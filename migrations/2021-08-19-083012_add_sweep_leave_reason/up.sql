@@ -0,0 +1,5 @@
+-- Add `sweep` leave reason for agents removed by `system.sweep`.
+ALTER TYPE agent_session_leave_reason RENAME TO agent_session_leave_reason_old;
+CREATE TYPE agent_session_leave_reason AS ENUM ('request', 'subscription_delete', 'kick', 'ban', 'sweep');
+ALTER TABLE agent_session ALTER COLUMN leave_reason TYPE agent_session_leave_reason USING leave_reason::text::agent_session_leave_reason;
+DROP TYPE agent_session_leave_reason_old;
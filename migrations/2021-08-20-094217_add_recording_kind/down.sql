@@ -0,0 +1,4 @@
+DELETE FROM recording WHERE kind <> 'camera';
+ALTER TABLE recording DROP CONSTRAINT recording_pkey;
+ALTER TABLE recording ADD PRIMARY KEY (rtc_id);
+ALTER TABLE recording DROP COLUMN kind;
@@ -0,0 +1,4 @@
+-- Streams of each kind are recorded and uploaded separately.
+ALTER TABLE recording ADD COLUMN kind rtc_stream_kind NOT NULL DEFAULT 'camera';
+ALTER TABLE recording DROP CONSTRAINT recording_pkey;
+ALTER TABLE recording ADD PRIMARY KEY (rtc_id, kind);
@@ -0,0 +1,532 @@
+use std::{
+    cmp::Ordering,
+    sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        Arc,
+    },
+};
+
+use chrono::{DateTime, Utc};
+use rand::{distributions::WeightedIndex, prelude::*};
+use svc_agent::AgentId;
+
+use crate::{
+    config::{BalancerStatsConfig, BalancerStrategyKind},
+    db,
+};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Load of a backend at the moment of balancing.
+#[derive(Clone, Debug)]
+pub struct BackendLoad {
+    id: AgentId,
+    capacity: i32,
+    load: f64,
+    taken: f64,
+    region: Option<String>,
+    stats: Option<BackendStats>,
+}
+
+impl BackendLoad {
+    /// `load` is the sum of rooms' reserves or actual loads whichever is greater
+    /// and `taken` is the sum of actual loads only. Actual loads are writers' bitrates in Mbps
+    /// falling back to `default_video_remb!` or `default_audio_remb!` from `db::janus_backend`.
+    pub fn new(id: AgentId, capacity: i32, load: f64, taken: f64) -> Self {
+        Self {
+            id,
+            capacity,
+            load,
+            taken,
+            region: None,
+            stats: None,
+        }
+    }
+
+    pub fn region(self, region: &str) -> Self {
+        Self {
+            region: Some(region.to_owned()),
+            ..self
+        }
+    }
+
+    pub fn stats(self, stats: BackendStats) -> Self {
+        Self {
+            stats: Some(stats),
+            ..self
+        }
+    }
+
+    /// Raises the estimated load and taken capacity up to the measured load if it's greater.
+    /// The measured load is the greater of the weighted egress bitrate in Mbps
+    /// and the weighted CPU usage share of the capacity. Outdated stats are ignored.
+    pub fn blend_stats(self, config: &BalancerStatsConfig, now: DateTime<Utc>) -> Self {
+        let stats = match self.stats {
+            Some(ref stats) => stats,
+            None => return self,
+        };
+
+        let age = (now - stats.reported_at).to_std().unwrap_or_default();
+
+        if age > config.max_age {
+            return self;
+        }
+
+        let bitrate_load =
+            stats.egress_bitrate.unwrap_or(0) as f64 / 1_000_000.0 * config.bitrate_weight;
+
+        // CPU usage can't be converted to load units for backends with unlimited capacity.
+        let cpu_load = if self.capacity < i32::MAX {
+            stats.cpu_usage.unwrap_or(0.0) * self.capacity as f64 * config.cpu_weight
+        } else {
+            0.0
+        };
+
+        let measured = bitrate_load.max(cpu_load);
+
+        Self {
+            load: self.load.max(measured),
+            taken: self.taken.max(measured),
+            ..self
+        }
+    }
+
+    pub fn id(&self) -> &AgentId {
+        &self.id
+    }
+
+    /// Capacity left considering rooms' reserves.
+    fn free(&self) -> f64 {
+        self.capacity as f64 - self.load
+    }
+
+    pub fn is_capable(&self, reserve: i32) -> bool {
+        self.free() >= reserve as f64
+    }
+}
+
+impl From<db::janus_backend::BalancerLoadRow> for BackendLoad {
+    fn from(row: db::janus_backend::BalancerLoadRow) -> Self {
+        let stats = row.stats_updated_at.map(|reported_at| BackendStats {
+            egress_bitrate: row.egress_bitrate,
+            cpu_usage: row.cpu_usage,
+            reported_at,
+        });
+
+        Self {
+            region: row.region,
+            stats,
+            ..Self::new(row.backend_id, row.capacity, row.load, row.taken)
+        }
+    }
+}
+
+/// Usage measured and reported by the backend itself.
+#[derive(Clone, Debug)]
+pub struct BackendStats {
+    /// Egress bitrate in bits per second.
+    pub egress_bitrate: Option<i64>,
+    /// CPU usage from 0.0 to 1.0.
+    pub cpu_usage: Option<f64>,
+    pub reported_at: DateTime<Utc>,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+pub trait BalancerStrategy: Send + Sync {
+    /// Chooses one of the backends capable to host the room's reserve.
+    fn select<'a>(&self, candidates: &[&'a BackendLoad]) -> Option<&'a BackendLoad>;
+}
+
+/// Packs rooms densely by choosing the most loaded backend.
+#[derive(Debug)]
+pub struct BinPacking;
+
+impl BalancerStrategy for BinPacking {
+    fn select<'a>(&self, candidates: &[&'a BackendLoad]) -> Option<&'a BackendLoad> {
+        choose_max_by_key(candidates, |backend| backend.load)
+    }
+}
+
+/// Spreads rooms by choosing the backend with the most free capacity.
+#[derive(Debug)]
+pub struct LeastLoaded;
+
+impl BalancerStrategy for LeastLoaded {
+    fn select<'a>(&self, candidates: &[&'a BackendLoad]) -> Option<&'a BackendLoad> {
+        choose_max_by_key(candidates, |backend| backend.free())
+    }
+}
+
+/// Chooses backends in turn ordered by id.
+#[derive(Clone, Debug, Default)]
+pub struct RoundRobin {
+    counter: Arc<AtomicUsize>,
+}
+
+impl BalancerStrategy for RoundRobin {
+    fn select<'a>(&self, candidates: &[&'a BackendLoad]) -> Option<&'a BackendLoad> {
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let mut candidates = candidates.to_vec();
+        candidates.sort_by_key(|backend| backend.id.to_string());
+
+        let idx = self.counter.fetch_add(1, AtomicOrdering::Relaxed) % candidates.len();
+        Some(candidates[idx])
+    }
+}
+
+/// Chooses a random backend with chances proportional to its free capacity.
+#[derive(Debug)]
+pub struct WeightedRandom;
+
+impl BalancerStrategy for WeightedRandom {
+    fn select<'a>(&self, candidates: &[&'a BackendLoad]) -> Option<&'a BackendLoad> {
+        let weights = candidates
+            .iter()
+            .map(|backend| backend.free().max(0.0))
+            .collect::<Vec<_>>();
+
+        let mut rng = rand::thread_rng();
+
+        match WeightedIndex::new(&weights) {
+            Ok(distribution) => Some(candidates[distribution.sample(&mut rng)]),
+            // All weights are zero, e.g. for rooms with zero reserve on full backends.
+            Err(_) => candidates.choose(&mut rng).copied(),
+        }
+    }
+}
+
+/// Picks the element with the maximum key choosing randomly among equal ones.
+fn choose_max_by_key<'a, F>(candidates: &[&'a BackendLoad], key: F) -> Option<&'a BackendLoad>
+where
+    F: Fn(&BackendLoad) -> f64,
+{
+    let max_key = candidates
+        .iter()
+        .map(|backend| key(backend))
+        .max_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))?;
+
+    let best = candidates
+        .iter()
+        .filter(|backend| key(backend) >= max_key)
+        .copied()
+        .collect::<Vec<_>>();
+
+    best.choose(&mut rand::thread_rng()).copied()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug)]
+pub enum Selection<'a> {
+    /// The backend is capable to host the room's reserve.
+    Capable(&'a BackendLoad),
+    /// The backend is capable to host the room's reserve but it's not in the preferred region
+    /// because there are no capable backends there.
+    OutOfRegion(&'a BackendLoad),
+    /// There are no capable backends so the least loaded one has been chosen
+    /// without considering reserves.
+    Fallback(&'a BackendLoad),
+}
+
+/// Strategies available for balancing. Stateful ones keep their state across requests.
+#[derive(Clone, Debug, Default)]
+pub struct Balancer {
+    round_robin: RoundRobin,
+}
+
+impl Balancer {
+    pub fn strategy(&self, kind: BalancerStrategyKind) -> &dyn BalancerStrategy {
+        match kind {
+            BalancerStrategyKind::BinPacking => &BinPacking,
+            BalancerStrategyKind::LeastLoaded => &LeastLoaded,
+            BalancerStrategyKind::RoundRobin => &self.round_robin,
+            BalancerStrategyKind::WeightedRandom => &WeightedRandom,
+        }
+    }
+
+    /// Selects a backend to host a room with the `reserve` using the strategy
+    /// preferring the `region` if specified. Returns `None` only if there are no backends at all.
+    pub fn select<'a>(
+        &self,
+        kind: BalancerStrategyKind,
+        backends: &'a [BackendLoad],
+        reserve: i32,
+        region: Option<&str>,
+    ) -> Option<Selection<'a>> {
+        let strategy = self.strategy(kind);
+
+        let candidates = backends
+            .iter()
+            .filter(|backend| backend.is_capable(reserve))
+            .collect::<Vec<_>>();
+
+        match region {
+            Some(region) => {
+                let regional_candidates = candidates
+                    .iter()
+                    .filter(|backend| backend.region.as_deref() == Some(region))
+                    .copied()
+                    .collect::<Vec<_>>();
+
+                if let Some(backend) = strategy.select(&regional_candidates) {
+                    return Some(Selection::Capable(backend));
+                }
+
+                if let Some(backend) = strategy.select(&candidates) {
+                    return Some(Selection::OutOfRegion(backend));
+                }
+            }
+            None => {
+                if let Some(backend) = strategy.select(&candidates) {
+                    return Some(Selection::Capable(backend));
+                }
+            }
+        }
+
+        let backends = backends.iter().collect::<Vec<_>>();
+
+        choose_max_by_key(&backends, |backend| backend.capacity as f64 - backend.taken)
+            .map(Selection::Fallback)
+    }
+
+    /// Same as `select` but chooses one of the `preferred` backends regardless of the region
+    /// if any of them is capable to host the room's `reserve`.
+    pub fn select_preferred<'a>(
+        &self,
+        kind: BalancerStrategyKind,
+        backends: &'a [BackendLoad],
+        reserve: i32,
+        region: Option<&str>,
+        preferred: &[AgentId],
+    ) -> Option<Selection<'a>> {
+        let candidates = backends
+            .iter()
+            .filter(|backend| preferred.contains(&backend.id) && backend.is_capable(reserve))
+            .collect::<Vec<_>>();
+
+        match self.strategy(kind).select(&candidates) {
+            Some(backend) => Some(Selection::Capable(backend)),
+            None => self.select(kind, backends, reserve, region),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use svc_agent::AccountId;
+
+    use super::*;
+
+    fn backend(label: &str, capacity: i32, load: f64, taken: f64) -> BackendLoad {
+        let id = AgentId::new(label, AccountId::new("janus-gateway", "svc.example.org"));
+        BackendLoad::new(id, capacity, load, taken)
+    }
+
+    fn select_capable(
+        balancer: &Balancer,
+        kind: BalancerStrategyKind,
+        backends: &[BackendLoad],
+        reserve: i32,
+    ) -> String {
+        match balancer.select(kind, backends, reserve, None) {
+            Some(Selection::Capable(backend)) => backend.id().label().to_owned(),
+            other => panic!("Expected a capable backend, got {:?}", other),
+        }
+    }
+
+    fn snapshot() -> Vec<BackendLoad> {
+        vec![
+            backend("full", 100, 95.0, 50.0),
+            backend("busy", 100, 60.0, 60.0),
+            backend("idle", 100, 10.0, 10.0),
+        ]
+    }
+
+    #[test]
+    fn bin_packing() {
+        let balancer = Balancer::default();
+        let kind = BalancerStrategyKind::BinPacking;
+        assert_eq!(select_capable(&balancer, kind, &snapshot(), 20), "busy");
+        assert_eq!(select_capable(&balancer, kind, &snapshot(), 5), "full");
+    }
+
+    #[test]
+    fn least_loaded() {
+        let balancer = Balancer::default();
+        let kind = BalancerStrategyKind::LeastLoaded;
+        assert_eq!(select_capable(&balancer, kind, &snapshot(), 5), "idle");
+    }
+
+    #[test]
+    fn round_robin() {
+        let balancer = Balancer::default();
+        let kind = BalancerStrategyKind::RoundRobin;
+
+        let labels = (0..4)
+            .map(|_| select_capable(&balancer, kind, &snapshot(), 20))
+            .collect::<Vec<_>>();
+
+        assert_eq!(labels, vec!["busy", "idle", "busy", "idle"]);
+    }
+
+    #[test]
+    fn weighted_random() {
+        let balancer = Balancer::default();
+        let kind = BalancerStrategyKind::WeightedRandom;
+        let mut counts = HashMap::new();
+
+        for _ in 0..1000 {
+            let label = select_capable(&balancer, kind, &snapshot(), 20);
+            *counts.entry(label).or_insert(0) += 1;
+        }
+
+        // `full` can't host the reserve and `idle` has more free capacity than `busy`.
+        assert_eq!(counts.get("full"), None);
+        assert!(counts["idle"] > counts["busy"]);
+    }
+
+    #[test]
+    fn fallback_to_least_taken() {
+        let balancer = Balancer::default();
+
+        for kind in &[
+            BalancerStrategyKind::BinPacking,
+            BalancerStrategyKind::LeastLoaded,
+            BalancerStrategyKind::RoundRobin,
+            BalancerStrategyKind::WeightedRandom,
+        ] {
+            match balancer.select(*kind, &snapshot(), 1000, None) {
+                Some(Selection::Fallback(backend)) => assert_eq!(backend.id().label(), "idle"),
+                other => panic!("Expected fallback backend, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn region() {
+        let balancer = Balancer::default();
+        let kind = BalancerStrategyKind::BinPacking;
+
+        let backends = vec![
+            backend("eu-busy", 100, 60.0, 60.0).region("eu"),
+            backend("eu-idle", 100, 10.0, 10.0).region("eu"),
+            backend("us-full", 100, 95.0, 95.0).region("us"),
+        ];
+
+        // The most loaded backend in the region.
+        match balancer.select(kind, &backends, 10, Some("eu")) {
+            Some(Selection::Capable(backend)) => assert_eq!(backend.id().label(), "eu-busy"),
+            other => panic!("Expected a capable backend, got {:?}", other),
+        }
+
+        // No capable backends in the region.
+        match balancer.select(kind, &backends, 10, Some("us")) {
+            Some(Selection::OutOfRegion(backend)) => assert_eq!(backend.id().label(), "eu-busy"),
+            other => panic!("Expected a backend out of the region, got {:?}", other),
+        }
+
+        // Unknown region.
+        match balancer.select(kind, &backends, 10, Some("asia")) {
+            Some(Selection::OutOfRegion(backend)) => assert_eq!(backend.id().label(), "eu-busy"),
+            other => panic!("Expected a backend out of the region, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn blend_stats() {
+        let config = BalancerStatsConfig {
+            bitrate_weight: 1.0,
+            cpu_weight: 1.0,
+            max_age: std::time::Duration::from_secs(60),
+        };
+
+        let now = Utc::now();
+
+        let stats = |egress_bitrate, cpu_usage, age| BackendStats {
+            egress_bitrate: Some(egress_bitrate),
+            cpu_usage: Some(cpu_usage),
+            reported_at: now - chrono::Duration::seconds(age),
+        };
+
+        // Hot by bitrate.
+        let blended = backend("hot", 100, 10.0, 10.0)
+            .stats(stats(50_000_000, 0.1, 0))
+            .blend_stats(&config, now);
+
+        assert_eq!((blended.load, blended.taken), (50.0, 50.0));
+
+        // Hot by CPU.
+        let blended = backend("hot", 100, 10.0, 10.0)
+            .stats(stats(1_000_000, 0.8, 0))
+            .blend_stats(&config, now);
+
+        assert_eq!((blended.load, blended.taken), (80.0, 80.0));
+
+        // Measured load is less than the reserves.
+        let blended = backend("reserved", 100, 60.0, 10.0)
+            .stats(stats(20_000_000, 0.1, 0))
+            .blend_stats(&config, now);
+
+        assert_eq!((blended.load, blended.taken), (60.0, 20.0));
+
+        // Outdated stats.
+        let blended = backend("outdated", 100, 10.0, 10.0)
+            .stats(stats(50_000_000, 0.8, 120))
+            .blend_stats(&config, now);
+
+        assert_eq!((blended.load, blended.taken), (10.0, 10.0));
+
+        // The hot backend doesn't get the room anymore.
+        let backends = vec![
+            backend("hot", 100, 10.0, 10.0)
+                .stats(stats(80_000_000, 0.9, 0))
+                .blend_stats(&config, now),
+            backend("cold", 100, 10.0, 10.0),
+        ];
+
+        let balancer = Balancer::default();
+        let kind = BalancerStrategyKind::BinPacking;
+        assert_eq!(select_capable(&balancer, kind, &backends, 20), "cold");
+    }
+
+    #[test]
+    fn preferred() {
+        let balancer = Balancer::default();
+        let kind = BalancerStrategyKind::LeastLoaded;
+
+        let backends = vec![
+            backend("eu-busy", 100, 60.0, 60.0).region("eu"),
+            backend("eu-idle", 100, 10.0, 10.0).region("eu"),
+            backend("us-busy", 100, 70.0, 70.0).region("us"),
+        ];
+
+        let preferred = vec![backends[0].id().to_owned(), backends[2].id().to_owned()];
+
+        // The least loaded among the preferred ones even out of the region.
+        match balancer.select_preferred(kind, &backends, 20, Some("us"), &preferred) {
+            Some(Selection::Capable(backend)) => assert_eq!(backend.id().label(), "eu-busy"),
+            other => panic!("Expected a capable backend, got {:?}", other),
+        }
+
+        // The preferred backends can't host the reserve.
+        match balancer.select_preferred(kind, &backends, 50, Some("eu"), &preferred) {
+            Some(Selection::Capable(backend)) => assert_eq!(backend.id().label(), "eu-idle"),
+            other => panic!("Expected a capable backend, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn no_backends() {
+        let balancer = Balancer::default();
+        let result = balancer.select(BalancerStrategyKind::BinPacking, &[], 1, None);
+        assert!(result.is_none());
+    }
+}
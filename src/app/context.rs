@@ -17,7 +17,7 @@ use crate::{
     db::ConnectionPool as Db,
 };
 
-use super::metrics::Metrics;
+use super::{balancer::Balancer, metrics::Metrics, rate_limiter::RateLimiter};
 
 ///////////////////////////////////////////////////////////////////////////////
 
@@ -32,6 +32,8 @@ pub trait GlobalContext: Sync {
     fn janus_topics(&self) -> &JanusTopics;
     fn redis_pool(&self) -> &Option<RedisConnectionPool>;
     fn metrics(&self) -> Arc<Metrics>;
+    fn rate_limiter(&self) -> &RateLimiter;
+    fn balancer(&self) -> &Balancer;
 
     fn get_conn(
         &self,
@@ -66,6 +68,8 @@ pub struct AppContext {
     redis_pool: Option<RedisConnectionPool>,
     clients: Clients,
     metrics: Arc<Metrics>,
+    rate_limiter: RateLimiter,
+    balancer: Balancer,
 }
 
 impl AppContext {
@@ -88,6 +92,8 @@ impl AppContext {
             redis_pool: None,
             clients,
             metrics,
+            rate_limiter: RateLimiter::default(),
+            balancer: Balancer::default(),
         }
     }
 
@@ -131,6 +137,14 @@ impl GlobalContext for AppContext {
     fn metrics(&self) -> Arc<Metrics> {
         self.metrics.clone()
     }
+
+    fn rate_limiter(&self) -> &RateLimiter {
+        &self.rate_limiter
+    }
+
+    fn balancer(&self) -> &Balancer {
+        &self.balancer
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -183,6 +197,14 @@ impl<'a, C: GlobalContext> GlobalContext for AppMessageContext<'a, C> {
     fn metrics(&self) -> Arc<Metrics> {
         self.global_context.metrics()
     }
+
+    fn rate_limiter(&self) -> &RateLimiter {
+        self.global_context.rate_limiter()
+    }
+
+    fn balancer(&self) -> &Balancer {
+        self.global_context.balancer()
+    }
 }
 
 impl<'a, C: GlobalContext> MessageContext for AppMessageContext<'a, C> {
@@ -1,88 +1,1444 @@
+use anyhow::{anyhow, Context as AnyhowContext};
 use async_std::{stream, task};
 use async_trait::async_trait;
-use serde::Deserialize;
-use svc_agent::mqtt::{IncomingRequestProperties, ResponseStatus};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{collections::HashMap, ops::Bound, result::Result as StdResult};
+use svc_agent::{
+    mqtt::{
+        IncomingRequestProperties, IntoPublishableMessage, OutgoingRequest, ResponseStatus,
+        ShortTermTimingProperties, SubscriptionTopic,
+    },
+    AccountId, Addressable, AgentId, Authenticable, Subscription,
+};
+
+use crate::{
+    app::{
+        context::Context,
+        endpoint::{
+            prelude::*,
+            room::SubscriptionRequest,
+            subscription::{self, CorrelationDataPayload, LeaveReason, RoomEnterLeaveEvent},
+        },
+        metrics::HistogramExt,
+        API_VERSION,
+    },
+    db,
+    db::room_role::Role as RoomRole,
+};
+
+///////////////////////////////////////////////////////////////////////////////
+
+const MAX_LIMIT: i64 = 25;
+
+#[derive(Debug, Deserialize)]
+pub struct ListRequest {
+    room_id: db::room::Id,
+    offset: Option<i64>,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListResponseItem {
+    #[serde(flatten)]
+    agent: db::agent::Object,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<RoomRole>,
+}
+
+pub struct ListHandler;
+
+#[async_trait]
+impl RequestHandler for ListHandler {
+    type Payload = ListRequest;
+    const ERROR_TITLE: &'static str = "Failed to list agents";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let conn = context.get_conn().await?;
+        let room = task::spawn_blocking({
+            let room_id = payload.room_id;
+            move || helpers::find_room_by_id(room_id, helpers::RoomTimeRequirement::Open, &conn)
+        })
+        .await?;
+        helpers::add_room_logger_tags(context, &room);
+
+        // Authorize agents listing in the room.
+        let room_id = room.id().to_string();
+        let object = vec!["rooms", &room_id];
+
+        let authz_time = context
+            .authz()
+            .authorize(room.audience(), reqp, object, "read")
+            .await?;
+        context.metrics().observe_auth(authz_time);
+
+        // Get agents list in the room including those waiting in the lobby.
+        let conn = context.get_conn().await?;
+        let agents = task::spawn_blocking(move || {
+            let agents = db::agent::ListQuery::new()
+                .room_id(payload.room_id)
+                .statuses(&[db::agent::Status::Ready, db::agent::Status::Waiting])
+                .offset(payload.offset.unwrap_or(0))
+                .limit(std::cmp::min(payload.limit.unwrap_or(MAX_LIMIT), MAX_LIMIT))
+                .execute(&conn)?;
+
+            // Supply agents with their roles in the room.
+            let roles = db::room_role::ListQuery::new(payload.room_id)
+                .execute(&conn)?
+                .into_iter()
+                .map(|room_role| (room_role.account_id().to_owned(), room_role.role()))
+                .collect::<HashMap<_, _>>();
+
+            let items = agents
+                .into_iter()
+                .map(|agent| {
+                    let role = roles.get(agent.agent_id().as_account_id()).copied();
+                    ListResponseItem { agent, role }
+                })
+                .collect::<Vec<_>>();
+
+            Ok::<_, AppError>(items)
+        })
+        .await?;
+        context
+            .metrics()
+            .request_duration
+            .agent_list
+            .observe_timestamp(context.start_timestamp());
+
+        // Respond with agents list.
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            agents,
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub struct KickRequest {
+    room_id: db::room::Id,
+    agent_id: AgentId,
+}
+
+pub struct KickHandler;
+
+#[async_trait]
+impl RequestHandler for KickHandler {
+    type Payload = KickRequest;
+    const ERROR_TITLE: &'static str = "Failed to kick agent";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let (room, authz_time) = authorize_moderation(context, payload.room_id, reqp).await?;
+
+        let maybe_messages =
+            kick(context, &room, &payload.agent_id, LeaveReason::Kicked, reqp).await?;
+
+        let mut messages = match maybe_messages {
+            Some(messages) => messages,
+            None => {
+                return Err(anyhow!("The agent is not found"))
+                    .error(AppErrorKind::AgentNotEnteredTheRoom)
+            }
+        };
+
+        let response = helpers::build_response(
+            ResponseStatus::OK,
+            json!({}),
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        );
+
+        messages.insert(0, response);
+        context
+            .metrics()
+            .request_duration
+            .agent_kick
+            .observe_timestamp(context.start_timestamp());
+
+        Ok(Box::new(stream::from_iter(messages)))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+pub type BanRequest = KickRequest;
+pub struct BanHandler;
+
+#[async_trait]
+impl RequestHandler for BanHandler {
+    type Payload = BanRequest;
+    const ERROR_TITLE: &'static str = "Failed to ban agent";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let (room, authz_time) = authorize_moderation(context, payload.room_id, reqp).await?;
+
+        // Persist the ban so the account couldn't enter the room again
+        // and find all its agents that are currently in the room.
+        let conn = context.get_conn().await?;
+        let banned_agent_ids = task::spawn_blocking({
+            let room_id = room.id();
+            let account_id = payload.agent_id.as_account_id().to_owned();
+            let banned_by = reqp.as_agent_id().to_owned();
+
+            move || {
+                db::room_ban::InsertQuery::new(room_id, &account_id, &banned_by).execute(&conn)?;
+
+                let agent_ids = db::agent::ListQuery::new()
+                    .room_id(room_id)
+                    .execute(&conn)?
+                    .into_iter()
+                    .map(|agent| agent.agent_id().to_owned())
+                    .filter(|agent_id| agent_id.as_account_id() == &account_id)
+                    .collect::<Vec<_>>();
+
+                Ok::<_, AppError>(agent_ids)
+            }
+        })
+        .await?;
+
+        // Kick every agent of the banned account from the room.
+        let mut messages = vec![];
+
+        for agent_id in banned_agent_ids {
+            if let Some(kick_messages) =
+                kick(context, &room, &agent_id, LeaveReason::Banned, reqp).await?
+            {
+                messages.extend(kick_messages);
+            }
+        }
+
+        let response = helpers::build_response(
+            ResponseStatus::OK,
+            json!({}),
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        );
+
+        messages.insert(0, response);
+        context
+            .metrics()
+            .request_duration
+            .agent_ban
+            .observe_timestamp(context.start_timestamp());
+
+        Ok(Box::new(stream::from_iter(messages)))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SetRoleRequest {
+    room_id: db::room::Id,
+    account_id: AccountId,
+    role: Option<RoomRole>,
+}
+
+pub struct SetRoleHandler;
+
+#[async_trait]
+impl RequestHandler for SetRoleHandler {
+    type Payload = SetRoleRequest;
+    const ERROR_TITLE: &'static str = "Failed to set agent role";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let (room, authz_time) = authorize_moderation(context, payload.room_id, reqp).await?;
+
+        // Assign the role to the account or revoke it when `role` is null.
+        let conn = context.get_conn().await?;
+        let payload = task::spawn_blocking(move || {
+            match payload.role {
+                Some(role) => {
+                    db::room_role::UpsertQuery::new(room.id(), &payload.account_id, role)
+                        .execute(&conn)?;
+                }
+                None => {
+                    db::room_role::DeleteQuery::new(room.id(), &payload.account_id)
+                        .execute(&conn)?;
+                }
+            }
+
+            Ok::<_, AppError>(payload)
+        })
+        .await?;
+        context
+            .metrics()
+            .request_duration
+            .agent_set_role
+            .observe_timestamp(context.start_timestamp());
+
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            payload,
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, Serialize)]
+pub struct HandQueue {
+    room_id: db::room::Id,
+    agents: Vec<db::hand_raise::Object>,
+}
+
+impl HandQueue {
+    fn new(room_id: db::room::Id, agents: Vec<db::hand_raise::Object>) -> Self {
+        Self { room_id, agents }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RaiseHandRequest {
+    room_id: db::room::Id,
+}
+
+pub struct RaiseHandHandler;
+
+#[async_trait]
+impl RequestHandler for RaiseHandHandler {
+    type Payload = RaiseHandRequest;
+    const ERROR_TITLE: &'static str = "Failed to raise hand";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let conn = context.get_conn().await?;
+
+        let (room, queue, is_changed) = task::spawn_blocking({
+            let agent_id = reqp.as_agent_id().to_owned();
+
+            move || {
+                let room = helpers::find_room_by_id(
+                    payload.room_id,
+                    helpers::RoomTimeRequirement::Open,
+                    &conn,
+                )?;
+
+                helpers::check_room_presence(&room, &agent_id, &conn)?;
+                let inserted =
+                    db::hand_raise::InsertQuery::new(room.id(), &agent_id).execute(&conn)?;
+                let agents = db::hand_raise::ListQuery::new(room.id()).execute(&conn)?;
+                let queue = HandQueue::new(room.id(), agents);
+                Ok::<_, AppError>((room, queue, inserted > 0))
+            }
+        })
+        .await?;
+
+        helpers::add_room_logger_tags(context, &room);
+
+        let messages = build_hand_queue_messages(context, queue, is_changed, reqp, None);
+        context
+            .metrics()
+            .request_duration
+            .agent_raise_hand
+            .observe_timestamp(context.start_timestamp());
+
+        Ok(Box::new(stream::from_iter(messages)))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub struct LowerHandRequest {
+    room_id: db::room::Id,
+    agent_id: Option<AgentId>,
+}
+
+pub struct LowerHandHandler;
+
+#[async_trait]
+impl RequestHandler for LowerHandHandler {
+    type Payload = LowerHandRequest;
+    const ERROR_TITLE: &'static str = "Failed to lower hand";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let agent_id = payload
+            .agent_id
+            .unwrap_or_else(|| reqp.as_agent_id().to_owned());
+
+        let conn = context.get_conn().await?;
+        let room_id = payload.room_id;
+        let room = task::spawn_blocking(move || {
+            helpers::find_room_by_id(room_id, helpers::RoomTimeRequirement::Open, &conn)
+        })
+        .await?;
+
+        helpers::add_room_logger_tags(context, &room);
+
+        // Lowering somebody else's hand is up to moderators.
+        let maybe_authz_time = if &agent_id != reqp.as_agent_id() {
+            let room_id = room.id().to_string();
+            let object = vec!["rooms", &room_id];
+
+            let authz_time = context
+                .authz()
+                .authorize(room.audience(), reqp, object, "update")
+                .await?;
+
+            context.metrics().observe_auth(authz_time);
+            Some(authz_time)
+        } else {
+            None
+        };
+
+        let conn = context.get_conn().await?;
+
+        let (queue, is_changed) = task::spawn_blocking(move || {
+            let deleted = db::hand_raise::DeleteQuery::new(room.id(), &agent_id).execute(&conn)?;
+            let agents = db::hand_raise::ListQuery::new(room.id()).execute(&conn)?;
+            Ok::<_, AppError>((HandQueue::new(room.id(), agents), deleted > 0))
+        })
+        .await?;
+
+        let messages =
+            build_hand_queue_messages(context, queue, is_changed, reqp, maybe_authz_time);
+
+        context
+            .metrics()
+            .request_duration
+            .agent_lower_hand
+            .observe_timestamp(context.start_timestamp());
+
+        Ok(Box::new(stream::from_iter(messages)))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GrantSpeakRequest {
+    room_id: db::room::Id,
+    agent_id: AgentId,
+}
+
+pub struct GrantSpeakHandler;
+
+#[async_trait]
+impl RequestHandler for GrantSpeakHandler {
+    type Payload = GrantSpeakRequest;
+    const ERROR_TITLE: &'static str = "Failed to grant speak";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let conn = context.get_conn().await?;
+
+        let room = task::spawn_blocking({
+            let room_id = payload.room_id;
+            let moderator_id = reqp.as_agent_id().to_owned();
+
+            move || {
+                let room =
+                    helpers::find_room_by_id(room_id, helpers::RoomTimeRequirement::Open, &conn)?;
+
+                if room.rtc_sharing_policy() != db::rtc::SharingPolicy::Shared {
+                    let err = anyhow!(
+                        "Granting speak is not implemented for rtc_sharing_policy = '{}'",
+                        room.rtc_sharing_policy(),
+                    );
+
+                    return Err(err).error(AppErrorKind::NotImplemented);
+                }
+
+                helpers::check_room_role(&room, &moderator_id, &[RoomRole::Moderator], &conn)?;
+                Ok::<_, AppError>(room)
+            }
+        })
+        .await?;
+
+        helpers::add_room_logger_tags(context, &room);
+
+        let room_id = room.id().to_string();
+        let object = vec!["rooms", &room_id];
+
+        let authz_time = context
+            .authz()
+            .authorize(room.audience(), reqp, object, "update")
+            .await?;
+
+        context.metrics().observe_auth(authz_time);
+
+        // Let the agent publish to the room's RTC until it leaves and take it out of the queue.
+        let conn = context.get_conn().await?;
+
+        let (queue, is_changed) = task::spawn_blocking({
+            let agent_id = payload.agent_id.clone();
+
+            move || {
+                helpers::check_room_presence(&room, &agent_id, &conn)?;
+
+                db::agent::UpdateQuery::new(&agent_id, room.id())
+                    .speak_granted(true)
+                    .execute(&conn)?;
+
+                let deleted =
+                    db::hand_raise::DeleteQuery::new(room.id(), &agent_id).execute(&conn)?;
+
+                let agents = db::hand_raise::ListQuery::new(room.id()).execute(&conn)?;
+                Ok::<_, AppError>((HandQueue::new(room.id(), agents), deleted > 0))
+            }
+        })
+        .await?;
+
+        let notification = helpers::build_notification(
+            "agent.grant_speak",
+            &format!("rooms/{}/events", payload.room_id),
+            payload,
+            reqp,
+            context.start_timestamp(),
+        );
+
+        let mut messages =
+            build_hand_queue_messages(context, queue, is_changed, reqp, Some(authz_time));
+
+        messages.push(notification);
+
+        context
+            .metrics()
+            .request_duration
+            .agent_grant_speak
+            .observe_timestamp(context.start_timestamp());
+
+        Ok(Box::new(stream::from_iter(messages)))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub struct AttendanceRequest {
+    room_id: db::room::Id,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AttendanceResponseItem {
+    account_id: AccountId,
+    /// Total time in seconds the account has been present in the room with any of its agents.
+    presence: i64,
+}
+
+pub struct AttendanceHandler;
+
+#[async_trait]
+impl RequestHandler for AttendanceHandler {
+    type Payload = AttendanceRequest;
+    const ERROR_TITLE: &'static str = "Failed to get attendance";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let conn = context.get_conn().await?;
+        let room = task::spawn_blocking({
+            let room_id = payload.room_id;
+            move || helpers::find_room_by_id(room_id, helpers::RoomTimeRequirement::Any, &conn)
+        })
+        .await?;
+        helpers::add_room_logger_tags(context, &room);
+
+        // Attendance is personal data so it's available only to those who may update the room.
+        let room_id = room.id().to_string();
+        let object = vec!["rooms", &room_id];
+
+        let authz_time = context
+            .authz()
+            .authorize(room.audience(), reqp, object, "update")
+            .await?;
+        context.metrics().observe_auth(authz_time);
+
+        let conn = context.get_conn().await?;
+        let room_id = room.id();
+        let sessions =
+            task::spawn_blocking(move || db::agent_session::ListQuery::new(room_id).execute(&conn))
+                .await?;
+
+        // Sessions that haven't been closed explicitly last until the room closes.
+        let now = Utc::now();
+
+        let closed_at = match room.time().1 {
+            Bound::Included(time) | Bound::Excluded(time) => std::cmp::min(time, now),
+            Bound::Unbounded => now,
+        };
+
+        let items = summarize_attendance(&sessions, closed_at);
+        context
+            .metrics()
+            .request_duration
+            .agent_attendance
+            .observe_timestamp(context.start_timestamp());
+
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            items,
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
+    }
+}
+
+/// Sums up presence time per account. Overlapping sessions of the same account,
+/// e.g. from multiple devices, are counted once. `sessions` must be ordered by `entered_at`.
+fn summarize_attendance(
+    sessions: &[db::agent_session::Object],
+    closed_at: DateTime<Utc>,
+) -> Vec<AttendanceResponseItem> {
+    // Account id with its last merged interval and the presence accumulated before it.
+    let mut accounts: Vec<(AccountId, DateTime<Utc>, DateTime<Utc>, i64)> = vec![];
+    let mut indexes = HashMap::new();
+
+    for session in sessions {
+        let account_id = session.agent_id().as_account_id();
+        let start = session.entered_at();
+        let end = std::cmp::max(session.left_at().unwrap_or(closed_at), start);
+
+        match indexes.get(account_id).copied() {
+            None => {
+                indexes.insert(account_id.to_owned(), accounts.len());
+                accounts.push((account_id.to_owned(), start, end, 0));
+            }
+            Some(idx) => {
+                let (_, last_start, last_end, presence) = &mut accounts[idx];
+
+                if start <= *last_end {
+                    *last_end = std::cmp::max(*last_end, end);
+                } else {
+                    *presence += (*last_end - *last_start).num_seconds();
+                    *last_start = start;
+                    *last_end = end;
+                }
+            }
+        }
+    }
+
+    accounts
+        .into_iter()
+        .map(
+            |(account_id, last_start, last_end, presence)| AttendanceResponseItem {
+                account_id,
+                presence: presence + (last_end - last_start).num_seconds(),
+            },
+        )
+        .collect()
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+async fn authorize_moderation<C: Context>(
+    context: &mut C,
+    room_id: db::room::Id,
+    reqp: &IncomingRequestProperties,
+) -> StdResult<(db::room::Object, chrono::Duration), AppError> {
+    let conn = context.get_conn().await?;
+    let room = task::spawn_blocking(move || {
+        helpers::find_room_by_id(room_id, helpers::RoomTimeRequirement::NotClosed, &conn)
+    })
+    .await?;
+    helpers::add_room_logger_tags(context, &room);
+
+    // Only those who may update the room are allowed to remove agents from it.
+    let room_id = room.id().to_string();
+    let object = vec!["rooms", &room_id];
+
+    let authz_time = context
+        .authz()
+        .authorize(room.audience(), reqp, object, "update")
+        .await?;
+    context.metrics().observe_auth(authz_time);
+    Ok((room, authz_time))
+}
+
+/// Removes the agent from the room right away and asks the broker to drop its subscription
+/// to the room's events. Returns `None` if the agent is not in the room.
+async fn kick<C: Context>(
+    context: &mut C,
+    room: &db::room::Object,
+    agent_id: &AgentId,
+    reason: LeaveReason,
+    reqp: &IncomingRequestProperties,
+) -> StdResult<Option<Vec<Box<dyn IntoPublishableMessage + Send>>>, AppError> {
+    let leave_reason = match reason {
+        LeaveReason::Kicked => db::agent_session::LeaveReason::Kick,
+        LeaveReason::Banned => db::agent_session::LeaveReason::Ban,
+    };
+
+    let mut messages =
+        match subscription::leave_room(context, agent_id, room.id(), leave_reason).await? {
+            Some(messages) => messages,
+            None => return Ok(None),
+        };
+
+    // Send dynamic subscription deletion request to the broker.
+    let object = vec![
+        String::from("rooms"),
+        room.id().to_string(),
+        String::from("events"),
+    ];
+
+    let payload = SubscriptionRequest::new(agent_id.to_owned(), object.clone());
+    let broker_id = AgentId::new("nevermind", context.config().broker_id.to_owned());
+
+    let response_topic = Subscription::unicast_responses_from(&broker_id)
+        .subscription_topic(context.agent_id(), API_VERSION)
+        .context("Failed to build response topic")
+        .error(AppErrorKind::BrokerRequestFailed)?;
+
+    let corr_data_payload =
+        CorrelationDataPayload::new(reqp.to_owned(), agent_id.to_owned(), object);
+
+    let corr_data = CorrelationData::SubscriptionKick(corr_data_payload)
+        .dump()
+        .context("Failed to dump correlation data")
+        .error(AppErrorKind::BrokerRequestFailed)?;
+
+    let timing = ShortTermTimingProperties::until_now(context.start_timestamp());
+    let props = reqp.to_request("subscription.delete", &response_topic, &corr_data, timing);
+    let to = &context.config().broker_id;
+    let outgoing_request = OutgoingRequest::multicast(payload, props, to, API_VERSION);
+    let boxed_request = Box::new(outgoing_request) as Box<dyn IntoPublishableMessage + Send>;
+
+    // Notify the room that the agent has left it.
+    let notification = helpers::build_notification(
+        "room.leave",
+        &format!("rooms/{}/events", room.id()),
+        RoomEnterLeaveEvent::new(room.id(), agent_id.to_owned()).reason(reason),
+        reqp,
+        context.start_timestamp(),
+    );
+
+    messages.push(boxed_request);
+    messages.push(notification);
+    Ok(Some(messages))
+}
+
+/// Responds with the current hand raise queue and broadcasts it to the room if it has changed.
+fn build_hand_queue_messages<C: Context>(
+    context: &C,
+    queue: HandQueue,
+    is_changed: bool,
+    reqp: &IncomingRequestProperties,
+    maybe_authz_time: Option<chrono::Duration>,
+) -> Vec<Box<dyn IntoPublishableMessage + Send>> {
+    let mut messages = Vec::with_capacity(2);
+
+    if is_changed {
+        messages.push(helpers::build_notification(
+            "room.hand_queue.update",
+            &format!("rooms/{}/events", queue.room_id),
+            queue.clone(),
+            reqp,
+            context.start_timestamp(),
+        ));
+    }
+
+    let response = helpers::build_response(
+        ResponseStatus::OK,
+        queue,
+        reqp,
+        context.start_timestamp(),
+        maybe_authz_time,
+    );
+
+    messages.insert(0, response);
+    messages
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    mod list {
+        use serde::Deserialize;
+        use svc_agent::AgentId;
+
+        use crate::test_helpers::{prelude::*, test_deps::LocalDeps};
+
+        use super::super::*;
+
+        ///////////////////////////////////////////////////////////////////////////
+
+        #[derive(Deserialize)]
+        struct Agent {
+            agent_id: AgentId,
+            room_id: db::room::Id,
+        }
+
+        #[async_std::test]
+        async fn list_agents() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Create room and put the agent online.
+                let room = shared_helpers::insert_room(&conn);
+                shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                room
+            };
+
+            // Allow agent to list agents in the room.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+
+            // Make agent.list request.
+            let mut context = TestContext::new(db, authz);
+
+            let payload = ListRequest {
+                room_id: room.id(),
+                offset: None,
+                limit: None,
+            };
+
+            let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Agents listing failed");
+
+            // Assert response.
+            let (agents, respp, _) = find_response::<Vec<Agent>>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(agents.len(), 1);
+            assert_eq!(&agents[0].agent_id, agent.agent_id());
+            assert_eq!(agents[0].room_id, room.id());
+        }
+
+        #[async_std::test]
+        async fn list_agents_not_authorized() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                shared_helpers::insert_room(&conn)
+            };
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = ListRequest {
+                room_id: room.id(),
+                offset: None,
+                limit: None,
+            };
+
+            let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on agents listing");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
+        }
+
+        #[async_std::test]
+        async fn list_agents_closed_room() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Create closed room.
+                shared_helpers::insert_closed_room(&conn)
+            };
+
+            // Allow agent to list agents in the room.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+
+            // Make agent.list request.
+            let mut context = TestContext::new(db, authz);
+
+            let payload = ListRequest {
+                room_id: room.id(),
+                offset: None,
+                limit: None,
+            };
+
+            let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on agents listing");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "room_closed");
+        }
+
+        #[async_std::test]
+        async fn list_agents_missing_room() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = ListRequest {
+                room_id: db::room::Id::random(),
+                offset: None,
+                limit: None,
+            };
+
+            let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on agents listing");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "room_not_found");
+        }
+    }
+
+    mod kick {
+        use serde::Deserialize;
+        use serde_json::Value as JsonValue;
+        use svc_agent::AgentId;
+
+        use crate::{
+            app::endpoint::subscription::RoomEnterLeaveEvent,
+            db::agent::ListQuery as AgentListQuery,
+            test_helpers::{prelude::*, test_deps::LocalDeps},
+        };
+
+        use super::super::*;
+
+        ///////////////////////////////////////////////////////////////////////////
+
+        #[derive(Deserialize)]
+        struct DynSubRequest {
+            subject: AgentId,
+            object: Vec<String>,
+        }
+
+        #[async_std::test]
+        async fn kick_agent() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let moderator = TestAgent::new("web", "moderator", USR_AUDIENCE);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Create room and put the agent online.
+                let room = shared_helpers::insert_room(&conn);
+                shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                room
+            };
+
+            // Allow moderator to update the room.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            authz.allow(moderator.account_id(), vec!["rooms", &room_id], "update");
+
+            // Make agent.kick request.
+            let mut context = TestContext::new(db.clone(), authz);
+
+            let payload = KickRequest {
+                room_id: room.id(),
+                agent_id: agent.agent_id().to_owned(),
+            };
+
+            let messages = handle_request::<KickHandler>(&mut context, &moderator, payload)
+                .await
+                .expect("Agent kick failed");
+
+            // Assert response.
+            let (_, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+
+            // Assert dynamic subscription deletion request.
+            let (payload, reqp, _) = find_request::<DynSubRequest>(messages.as_slice());
+            assert_eq!(reqp.method(), "subscription.delete");
+            assert_eq!(&payload.subject, agent.agent_id());
+            assert_eq!(payload.object, vec!["rooms", &room_id, "events"]);
+
+            // Assert notification.
+            let (event, evp, topic) = find_event::<JsonValue>(messages.as_slice());
+            assert_eq!(evp.label(), "room.leave");
+            assert_eq!(topic, format!("rooms/{}/events", room.id()));
+            assert_eq!(event["reason"], "kicked");
+
+            serde_json::from_value::<RoomEnterLeaveEvent>(event)
+                .expect("Failed to parse room.leave event");
+
+            // Assert the agent has been removed from the room.
+            let conn = db
+                .connection_pool()
+                .get()
+                .expect("Failed to get DB connection");
+
+            let agents = AgentListQuery::new()
+                .agent_id(agent.agent_id())
+                .room_id(room.id())
+                .execute(&conn)
+                .expect("Failed to execute agent list query");
+
+            assert!(agents.is_empty());
+        }
+
+        #[async_std::test]
+        async fn kick_agent_not_authorized() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let moderator = TestAgent::new("web", "moderator", USR_AUDIENCE);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                let room = shared_helpers::insert_room(&conn);
+                shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                room
+            };
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = KickRequest {
+                room_id: room.id(),
+                agent_id: agent.agent_id().to_owned(),
+            };
+
+            let err = handle_request::<KickHandler>(&mut context, &moderator, payload)
+                .await
+                .expect_err("Unexpected success on agent kick");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
+        }
+
+        #[async_std::test]
+        async fn kick_agent_not_entered() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let moderator = TestAgent::new("web", "moderator", USR_AUDIENCE);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                shared_helpers::insert_room(&conn)
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            authz.allow(moderator.account_id(), vec!["rooms", &room_id], "update");
+
+            let mut context = TestContext::new(db, authz);
+
+            let payload = KickRequest {
+                room_id: room.id(),
+                agent_id: agent.agent_id().to_owned(),
+            };
+
+            let err = handle_request::<KickHandler>(&mut context, &moderator, payload)
+                .await
+                .expect_err("Unexpected success on agent kick");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "agent_not_entered_the_room");
+        }
+
+        #[async_std::test]
+        async fn kick_agent_missing_room() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let moderator = TestAgent::new("web", "moderator", USR_AUDIENCE);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = KickRequest {
+                room_id: db::room::Id::random(),
+                agent_id: agent.agent_id().to_owned(),
+            };
+
+            let err = handle_request::<KickHandler>(&mut context, &moderator, payload)
+                .await
+                .expect_err("Unexpected success on agent kick");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "room_not_found");
+        }
+    }
+
+    mod ban {
+        use serde_json::Value as JsonValue;
+
+        use crate::{
+            db::room_ban::FindQuery as RoomBanFindQuery,
+            test_helpers::{find_event_by_predicate, prelude::*, test_deps::LocalDeps},
+        };
+
+        use super::super::*;
+
+        ///////////////////////////////////////////////////////////////////////////
+
+        #[async_std::test]
+        async fn ban_agent() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let moderator = TestAgent::new("web", "moderator", USR_AUDIENCE);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let other_agent = TestAgent::new("mobile", "user123", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Put two agents of the same account in the room.
+                let room = shared_helpers::insert_room(&conn);
+                shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                shared_helpers::insert_agent(&conn, other_agent.agent_id(), room.id());
+                room
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            authz.allow(moderator.account_id(), vec!["rooms", &room_id], "update");
+
+            // Make agent.ban request.
+            let mut context = TestContext::new(db.clone(), authz);
+
+            let payload = BanRequest {
+                room_id: room.id(),
+                agent_id: agent.agent_id().to_owned(),
+            };
+
+            let messages = handle_request::<BanHandler>(&mut context, &moderator, payload)
+                .await
+                .expect("Agent ban failed");
+
+            // Assert response and notification.
+            let (_, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+
+            // Assert both agents of the account have been removed from the room.
+            for banned_agent in &[&agent, &other_agent] {
+                let (event, _, _) = find_event_by_predicate::<JsonValue, _>(
+                    messages.as_slice(),
+                    |evp, event: JsonValue, _| {
+                        evp.label() == "room.leave"
+                            && event["agent_id"] == banned_agent.agent_id().to_string()
+                    },
+                )
+                .expect("room.leave event not found");
+
+                assert_eq!(event["reason"], "banned");
+            }
+
+            let conn = db
+                .connection_pool()
+                .get()
+                .expect("Failed to get DB connection");
+
+            let agents = db::agent::ListQuery::new()
+                .room_id(room.id())
+                .execute(&conn)
+                .expect("Failed to list agents");
+
+            assert!(agents.is_empty());
+
+            // Assert the ban is persisted.
+
+            let ban = RoomBanFindQuery::new(room.id(), agent.account_id())
+                .execute(&conn)
+                .expect("Failed to find room ban")
+                .expect("Room ban not found");
+
+            assert_eq!(ban.created_by(), moderator.agent_id());
+        }
+
+        #[async_std::test]
+        async fn ban_agent_not_authorized() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let moderator = TestAgent::new("web", "moderator", USR_AUDIENCE);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                shared_helpers::insert_room(&conn)
+            };
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = BanRequest {
+                room_id: room.id(),
+                agent_id: agent.agent_id().to_owned(),
+            };
+
+            let err = handle_request::<BanHandler>(&mut context, &moderator, payload)
+                .await
+                .expect_err("Unexpected success on agent ban");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
+        }
+    }
+
+    mod set_role {
+        use serde_json::Value as JsonValue;
+
+        use crate::test_helpers::{prelude::*, test_deps::LocalDeps};
+
+        use super::super::*;
+
+        ///////////////////////////////////////////////////////////////////////////
+
+        #[async_std::test]
+        async fn set_role() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let moderator = TestAgent::new("web", "moderator", USR_AUDIENCE);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                let room = shared_helpers::insert_room(&conn);
+                shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                room
+            };
+
+            // Allow moderator to update the room and list agents.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            authz.allow(moderator.account_id(), vec!["rooms", &room_id], "update");
+            authz.allow(moderator.account_id(), vec!["rooms", &room_id], "read");
+
+            // Make agent.set_role request.
+            let mut context = TestContext::new(db, authz);
+
+            let payload = SetRoleRequest {
+                room_id: room.id(),
+                account_id: agent.account_id().to_owned(),
+                role: Some(RoomRole::Speaker),
+            };
+
+            let messages = handle_request::<SetRoleHandler>(&mut context, &moderator, payload)
+                .await
+                .expect("Setting agent role failed");
+
+            let (_, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+
+            // Assert the role is present in the agents list.
+            let payload = ListRequest {
+                room_id: room.id(),
+                offset: None,
+                limit: None,
+            };
+
+            let messages = handle_request::<ListHandler>(&mut context, &moderator, payload)
+                .await
+                .expect("Agents listing failed");
+
+            let (agents, _, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(agents[0]["agent_id"], agent.agent_id().to_string());
+            assert_eq!(agents[0]["role"], "speaker");
+        }
+
+        #[async_std::test]
+        async fn set_role_not_authorized() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                shared_helpers::insert_room(&conn)
+            };
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = SetRoleRequest {
+                room_id: room.id(),
+                account_id: agent.account_id().to_owned(),
+                role: Some(RoomRole::Moderator),
+            };
+
+            let err = handle_request::<SetRoleHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on setting agent role");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
+        }
+    }
+
+    mod raise_hand {
+        use serde_json::Value as JsonValue;
+
+        use crate::test_helpers::{prelude::*, test_deps::LocalDeps};
+
+        use super::super::*;
 
-use crate::{
-    app::{context::Context, endpoint::prelude::*, metrics::HistogramExt},
-    db,
-};
+        ///////////////////////////////////////////////////////////////////////////
 
-///////////////////////////////////////////////////////////////////////////////
+        #[async_std::test]
+        async fn raise_hand() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent1 = TestAgent::new("web", "user1", USR_AUDIENCE);
+            let agent2 = TestAgent::new("web", "user2", USR_AUDIENCE);
 
-const MAX_LIMIT: i64 = 25;
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
 
-#[derive(Debug, Deserialize)]
-pub struct ListRequest {
-    room_id: db::room::Id,
-    offset: Option<i64>,
-    limit: Option<i64>,
-}
+                let room = shared_helpers::insert_room(&conn);
+                shared_helpers::insert_agent(&conn, agent1.agent_id(), room.id());
+                shared_helpers::insert_agent(&conn, agent2.agent_id(), room.id());
+                db::hand_raise::InsertQuery::new(room.id(), agent1.agent_id())
+                    .execute(&conn)
+                    .expect("Failed to raise hand");
 
-pub struct ListHandler;
+                room
+            };
 
-#[async_trait]
-impl RequestHandler for ListHandler {
-    type Payload = ListRequest;
-    const ERROR_TITLE: &'static str = "Failed to list agents";
+            // Make agent.raise_hand request.
+            let mut context = TestContext::new(db, TestAuthz::new());
+            let payload = RaiseHandRequest { room_id: room.id() };
 
-    async fn handle<C: Context>(
-        context: &mut C,
-        payload: Self::Payload,
-        reqp: &IncomingRequestProperties,
-    ) -> Result {
-        let conn = context.get_conn().await?;
-        let room = task::spawn_blocking({
-            let room_id = payload.room_id;
-            move || helpers::find_room_by_id(room_id, helpers::RoomTimeRequirement::Open, &conn)
-        })
-        .await?;
-        helpers::add_room_logger_tags(context, &room);
+            let messages = handle_request::<RaiseHandHandler>(&mut context, &agent2, payload)
+                .await
+                .expect("Hand raising failed");
 
-        // Authorize agents listing in the room.
-        let room_id = room.id().to_string();
-        let object = vec!["rooms", &room_id];
+            // Assert response.
+            let (queue, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(
+                queue["agents"][0]["agent_id"],
+                agent1.agent_id().to_string()
+            );
+            assert_eq!(
+                queue["agents"][1]["agent_id"],
+                agent2.agent_id().to_string()
+            );
 
-        let authz_time = context
-            .authz()
-            .authorize(room.audience(), reqp, object, "read")
-            .await?;
-        context.metrics().observe_auth(authz_time);
+            // Assert notification.
+            let (event, evp, topic) = find_event::<JsonValue>(messages.as_slice());
+            assert_eq!(evp.label(), "room.hand_queue.update");
+            assert_eq!(topic, format!("rooms/{}/events", room.id()));
+            assert_eq!(event, queue);
+        }
 
-        // Get agents list in the room.
-        let conn = context.get_conn().await?;
-        let agents = task::spawn_blocking(move || {
-            db::agent::ListQuery::new()
-                .room_id(payload.room_id)
-                .offset(payload.offset.unwrap_or(0))
-                .limit(std::cmp::min(payload.limit.unwrap_or(MAX_LIMIT), MAX_LIMIT))
-                .execute(&conn)
-        })
-        .await?;
-        context
-            .metrics()
-            .request_duration
-            .agent_list
-            .observe_timestamp(context.start_timestamp());
+        #[async_std::test]
+        async fn raise_hand_not_entered() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
-        // Respond with agents list.
-        Ok(Box::new(stream::once(helpers::build_response(
-            ResponseStatus::OK,
-            agents,
-            reqp,
-            context.start_timestamp(),
-            Some(authz_time),
-        ))))
-    }
-}
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
 
-///////////////////////////////////////////////////////////////////////////////
+                shared_helpers::insert_room(&conn)
+            };
 
-#[cfg(test)]
-mod tests {
-    mod list {
-        use serde::Deserialize;
-        use svc_agent::AgentId;
+            let mut context = TestContext::new(db, TestAuthz::new());
+            let payload = RaiseHandRequest { room_id: room.id() };
+
+            let err = handle_request::<RaiseHandHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on hand raising");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "agent_not_entered_the_room");
+        }
+    }
+
+    mod lower_hand {
+        use serde_json::Value as JsonValue;
 
         use crate::test_helpers::{prelude::*, test_deps::LocalDeps};
 
@@ -90,17 +1446,106 @@ mod tests {
 
         ///////////////////////////////////////////////////////////////////////////
 
-        #[derive(Deserialize)]
-        struct Agent {
-            agent_id: AgentId,
-            room_id: db::room::Id,
+        #[async_std::test]
+        async fn lower_own_hand() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                let room = shared_helpers::insert_room(&conn);
+                shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                db::hand_raise::InsertQuery::new(room.id(), agent.agent_id())
+                    .execute(&conn)
+                    .expect("Failed to raise hand");
+
+                room
+            };
+
+            // Make agent.lower_hand request.
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = LowerHandRequest {
+                room_id: room.id(),
+                agent_id: None,
+            };
+
+            let messages = handle_request::<LowerHandHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Hand lowering failed");
+
+            // Assert the queue is empty now.
+            let (queue, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(queue["agents"].as_array().map(|a| a.len()), Some(0));
+
+            let (_, evp, _) = find_event::<JsonValue>(messages.as_slice());
+            assert_eq!(evp.label(), "room.hand_queue.update");
         }
 
         #[async_std::test]
-        async fn list_agents() {
+        async fn lower_other_hand_not_authorized() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent1 = TestAgent::new("web", "user1", USR_AUDIENCE);
+            let agent2 = TestAgent::new("web", "user2", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                let room = shared_helpers::insert_room(&conn);
+                shared_helpers::insert_agent(&conn, agent1.agent_id(), room.id());
+                db::hand_raise::InsertQuery::new(room.id(), agent1.agent_id())
+                    .execute(&conn)
+                    .expect("Failed to raise hand");
+
+                room
+            };
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = LowerHandRequest {
+                room_id: room.id(),
+                agent_id: Some(agent1.agent_id().to_owned()),
+            };
+
+            let err = handle_request::<LowerHandHandler>(&mut context, &agent2, payload)
+                .await
+                .expect_err("Unexpected success on hand lowering");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
+        }
+    }
+
+    mod grant_speak {
+        use serde_json::Value as JsonValue;
+
+        use crate::{
+            db::agent::ListQuery as AgentListQuery,
+            test_helpers::{prelude::*, test_deps::LocalDeps},
+        };
+
+        use super::super::*;
+
+        ///////////////////////////////////////////////////////////////////////////
+
+        #[async_std::test]
+        async fn grant_speak() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
+            let moderator = TestAgent::new("web", "moderator", USR_AUDIENCE);
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
             let room = {
@@ -109,44 +1554,59 @@ mod tests {
                     .get()
                     .expect("Failed to get DB connection");
 
-                // Create room and put the agent online.
                 let room = shared_helpers::insert_room(&conn);
                 shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                db::hand_raise::InsertQuery::new(room.id(), agent.agent_id())
+                    .execute(&conn)
+                    .expect("Failed to raise hand");
+
                 room
             };
 
-            // Allow agent to list agents in the room.
+            // Allow moderator to update the room.
             let mut authz = TestAuthz::new();
             let room_id = room.id().to_string();
+            authz.allow(moderator.account_id(), vec!["rooms", &room_id], "update");
 
-            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
-
-            // Make agent.list request.
-            let mut context = TestContext::new(db, authz);
+            // Make agent.grant_speak request.
+            let mut context = TestContext::new(db.clone(), authz);
 
-            let payload = ListRequest {
+            let payload = GrantSpeakRequest {
                 room_id: room.id(),
-                offset: None,
-                limit: None,
+                agent_id: agent.agent_id().to_owned(),
             };
 
-            let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+            let messages = handle_request::<GrantSpeakHandler>(&mut context, &moderator, payload)
                 .await
-                .expect("Agents listing failed");
+                .expect("Granting speak failed");
 
-            // Assert response.
-            let (agents, respp, _) = find_response::<Vec<Agent>>(messages.as_slice());
+            // Assert the agent has been taken out of the queue.
+            let (queue, respp, _) = find_response::<JsonValue>(messages.as_slice());
             assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(queue["agents"].as_array().map(|a| a.len()), Some(0));
+
+            // Assert the agent is granted to speak.
+            let conn = db
+                .connection_pool()
+                .get()
+                .expect("Failed to get DB connection");
+
+            let agents = AgentListQuery::new()
+                .agent_id(agent.agent_id())
+                .room_id(room.id())
+                .execute(&conn)
+                .expect("Failed to execute agent list query");
+
             assert_eq!(agents.len(), 1);
-            assert_eq!(&agents[0].agent_id, agent.agent_id());
-            assert_eq!(agents[0].room_id, room.id());
+            assert!(agents[0].speak_granted());
         }
 
         #[async_std::test]
-        async fn list_agents_not_authorized() {
+        async fn grant_speak_not_authorized() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
+            let moderator = TestAgent::new("web", "moderator", USR_AUDIENCE);
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
             let room = {
@@ -155,31 +1615,48 @@ mod tests {
                     .get()
                     .expect("Failed to get DB connection");
 
-                shared_helpers::insert_room(&conn)
+                let room = shared_helpers::insert_room(&conn);
+                shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                room
             };
 
             let mut context = TestContext::new(db, TestAuthz::new());
 
-            let payload = ListRequest {
+            let payload = GrantSpeakRequest {
                 room_id: room.id(),
-                offset: None,
-                limit: None,
+                agent_id: agent.agent_id().to_owned(),
             };
 
-            let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+            let err = handle_request::<GrantSpeakHandler>(&mut context, &moderator, payload)
                 .await
-                .expect_err("Unexpected success on agents listing");
+                .expect_err("Unexpected success on granting speak");
 
             assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
             assert_eq!(err.kind(), "access_denied");
         }
+    }
+
+    mod attendance {
+        use serde_json::Value as JsonValue;
+        use svc_agent::AgentId;
+
+        use crate::test_helpers::{prelude::*, test_deps::LocalDeps};
+
+        use super::super::*;
+
+        ///////////////////////////////////////////////////////////////////////////
 
         #[async_std::test]
-        async fn list_agents_closed_room() {
+        async fn attendance() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
-            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let moderator = TestAgent::new("web", "moderator", USR_AUDIENCE);
+            let agent1 = TestAgent::new("web", "user1", USR_AUDIENCE);
+            let agent2 = TestAgent::new("web", "user2", USR_AUDIENCE);
+
+            // The same account from another device.
+            let agent1_mobile = AgentId::new("mobile", agent1.account_id().to_owned());
 
             let room = {
                 let conn = db
@@ -187,53 +1664,72 @@ mod tests {
                     .get()
                     .expect("Failed to get DB connection");
 
-                // Create closed room.
-                shared_helpers::insert_closed_room(&conn)
+                let room = shared_helpers::insert_room(&conn);
+
+                for agent_id in &[agent1.agent_id(), &agent1_mobile, agent2.agent_id()] {
+                    db::agent_session::InsertQuery::new(room.id(), agent_id)
+                        .execute(&conn)
+                        .expect("Failed to insert agent session");
+                }
+
+                db::agent_session::CloseQuery::new(
+                    room.id(),
+                    agent2.agent_id(),
+                    db::agent_session::LeaveReason::Request,
+                )
+                .execute(&conn)
+                .expect("Failed to close agent session");
+
+                room
             };
 
-            // Allow agent to list agents in the room.
+            // Allow moderator to update the room.
             let mut authz = TestAuthz::new();
             let room_id = room.id().to_string();
+            authz.allow(moderator.account_id(), vec!["rooms", &room_id], "update");
 
-            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
-
-            // Make agent.list request.
+            // Make agent.attendance request.
             let mut context = TestContext::new(db, authz);
+            let payload = AttendanceRequest { room_id: room.id() };
 
-            let payload = ListRequest {
-                room_id: room.id(),
-                offset: None,
-                limit: None,
-            };
-
-            let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+            let messages = handle_request::<AttendanceHandler>(&mut context, &moderator, payload)
                 .await
-                .expect_err("Unexpected success on agents listing");
+                .expect("Attendance reading failed");
 
-            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
-            assert_eq!(err.kind(), "room_closed");
+            // Assert one item per account.
+            let (items, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(items.as_array().map(|items| items.len()), Some(2));
+            assert_eq!(items[0]["account_id"], agent1.account_id().to_string());
+            assert_eq!(items[1]["account_id"], agent2.account_id().to_string());
+            assert!(items[1]["presence"].as_i64().is_some());
         }
 
         #[async_std::test]
-        async fn list_agents_missing_room() {
+        async fn attendance_not_authorized() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
-            let mut context = TestContext::new(db, TestAuthz::new());
 
-            let payload = ListRequest {
-                room_id: db::room::Id::random(),
-                offset: None,
-                limit: None,
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                shared_helpers::insert_room(&conn)
             };
 
-            let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+            let mut context = TestContext::new(db, TestAuthz::new());
+            let payload = AttendanceRequest { room_id: room.id() };
+
+            let err = handle_request::<AttendanceHandler>(&mut context, &agent, payload)
                 .await
-                .expect_err("Unexpected success on agents listing");
+                .expect_err("Unexpected success on reading attendance");
 
-            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
-            assert_eq!(err.kind(), "room_not_found");
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
         }
     }
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, result::Result as StdResult};
 
 use crate::{
     app::{context::Context, endpoint::prelude::*, metrics::HistogramExt},
@@ -142,6 +142,29 @@ impl RequestHandler for UpdateHandler {
 
                         let mut q = db::rtc_reader_config::UpsertQuery::new(*rtc_id, &agent_id);
 
+                        // New configs start from the room defaults.
+                        let maybe_defaults = room
+                            .default_reader_config()
+                            .context("Failed to parse room default reader config")
+                            .error(AppErrorKind::MessageParsingFailed)?;
+
+                        if let Some(defaults) = maybe_defaults {
+                            let is_new = db::rtc_reader_config::FindQuery::new(*rtc_id, &agent_id)
+                                .execute(&conn)?
+                                .is_none();
+
+                            if is_new
+                                && helpers::are_room_defaults_applicable(&room, &agent_id, &conn)?
+                                && helpers::are_room_defaults_applicable(
+                                    &room,
+                                    &state_config_item.agent_id,
+                                    &conn,
+                                )?
+                            {
+                                q = q.defaults(&defaults);
+                            }
+                        }
+
                         if let Some(receive_video) = state_config_item.receive_video {
                             q = q.receive_video(receive_video);
                         }
@@ -175,32 +198,9 @@ impl RequestHandler for UpdateHandler {
         helpers::add_room_logger_tags(context, &room);
 
         if let Some(backend) = maybe_backend {
-            let items = rtc_reader_configs_with_rtcs
-                .iter()
-                .map(
-                    |(rtc_reader_config, rtc)| UpdateReaderConfigRequestBodyConfigItem {
-                        reader_id: rtc_reader_config.reader_id().to_owned(),
-                        stream_id: rtc.id(),
-                        receive_video: rtc_reader_config.receive_video(),
-                        receive_audio: rtc_reader_config.receive_audio(),
-                    },
-                )
-                .collect();
-
-            let request = UpdateReaderConfigRequest {
-                session_id: backend.session_id(),
-                handle_id: backend.handle_id(),
-                body: UpdateReaderConfigRequestBody::new(items),
-            };
-            context
-                .janus_clients()
-                .get_or_insert(&backend)
-                .error(AppErrorKind::BackendClientCreationFailed)?
-                .reader_update(request)
-                .await
-                .context("Reader update")
-                .error(AppErrorKind::BackendRequestFailed)?
+            update_janus(context, &backend, &rtc_reader_configs_with_rtcs).await?;
         }
+
         let response = helpers::build_response(
             ResponseStatus::OK,
             State::new(room.id(), &rtc_reader_configs_with_rtcs),
@@ -217,6 +217,42 @@ impl RequestHandler for UpdateHandler {
     }
 }
 
+/// Sends the reader configs to the Janus backend hosting the room.
+pub(super) async fn update_janus<C: Context>(
+    context: &mut C,
+    backend: &db::janus_backend::Object,
+    rtc_reader_configs_with_rtcs: &[(RtcReaderConfig, Rtc)],
+) -> StdResult<(), AppError> {
+    let items = rtc_reader_configs_with_rtcs
+        .iter()
+        .map(
+            |(rtc_reader_config, rtc)| UpdateReaderConfigRequestBodyConfigItem {
+                reader_id: rtc_reader_config.reader_id().to_owned(),
+                stream_id: rtc.id(),
+                receive_video: rtc_reader_config.receive_video(),
+                receive_audio: rtc_reader_config.receive_audio(),
+            },
+        )
+        .collect();
+
+    let request = UpdateReaderConfigRequest {
+        session_id: backend.session_id(),
+        handle_id: backend.handle_id(),
+        body: UpdateReaderConfigRequestBody::new(items),
+    };
+
+    context
+        .janus_clients()
+        .get_or_insert(backend)
+        .error(AppErrorKind::BackendClientCreationFailed)?
+        .reader_update(request)
+        .await
+        .context("Reader update")
+        .error(AppErrorKind::BackendRequestFailed)?;
+
+    Ok(())
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 #[derive(Debug, Deserialize)]
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, convert::TryFrom, result::Result as StdResult};
 
 use crate::{
     app::{context::Context, endpoint::prelude::*, metrics::HistogramExt},
@@ -7,9 +7,12 @@ use crate::{
         UpdateWriterConfigRequestBodyConfigItem,
     },
     db,
-    db::{rtc::Object as Rtc, rtc_writer_config::Object as RtcWriterConfig},
+    db::{
+        janus_rtc_stream::Kind as StreamKind, room_role::Role as RoomRole, rtc::Object as Rtc,
+        rtc_writer_config::Object as RtcWriterConfig,
+    },
 };
-use anyhow::anyhow;
+use anyhow::{anyhow, Context as AnyhowContext};
 use async_std::{stream, task};
 use async_trait::async_trait;
 use diesel::Connection;
@@ -30,7 +33,7 @@ pub struct State {
 }
 
 impl State {
-    fn new(
+    pub(super) fn new(
         room_id: db::room::Id,
         rtc_writer_configs_with_rtcs: &[(RtcWriterConfig, Rtc)],
     ) -> State {
@@ -38,13 +41,32 @@ impl State {
             .iter()
             .map(|(rtc_writer_config, rtc)| {
                 let mut config_item = StateConfigItem::new(rtc.created_by().to_owned())
+                    .kind(rtc_writer_config.kind())
                     .send_video(rtc_writer_config.send_video())
-                    .send_audio(rtc_writer_config.send_audio());
+                    .send_audio(rtc_writer_config.send_audio())
+                    .send_video_locked(rtc_writer_config.send_video_locked())
+                    .send_audio_locked(rtc_writer_config.send_audio_locked());
 
                 if let Some(video_remb) = rtc_writer_config.video_remb() {
                     config_item = config_item.video_remb(video_remb as u32);
                 }
 
+                if let Some(audio_remb) = rtc_writer_config.audio_remb() {
+                    config_item = config_item.audio_remb(audio_remb as u32);
+                }
+
+                if let Some(max_height) = rtc_writer_config.max_height() {
+                    config_item = config_item.max_height(max_height as u32);
+                }
+
+                if let Some(max_width) = rtc_writer_config.max_width() {
+                    config_item = config_item.max_width(max_width as u32);
+                }
+
+                if let Some(max_framerate) = rtc_writer_config.max_framerate() {
+                    config_item = config_item.max_framerate(max_framerate as u32);
+                }
+
                 if let Some(send_audio_updated_by) = rtc_writer_config.send_audio_updated_by() {
                     config_item =
                         config_item.send_audio_updated_by(send_audio_updated_by.to_owned());
@@ -61,24 +83,43 @@ impl State {
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct StateConfigItem {
     agent_id: AgentId,
+    #[serde(default)]
+    kind: StreamKind,
     send_video: Option<bool>,
     send_audio: Option<bool>,
     video_remb: Option<u32>,
+    audio_remb: Option<u32>,
+    max_height: Option<u32>,
+    max_width: Option<u32>,
+    max_framerate: Option<u32>,
     #[cfg_attr(not(test), serde(skip_deserializing))]
     send_audio_updated_by: Option<AgentId>,
+    send_video_locked: Option<bool>,
+    send_audio_locked: Option<bool>,
 }
 
 impl StateConfigItem {
     fn new(agent_id: AgentId) -> Self {
         Self {
             agent_id,
+            kind: StreamKind::default(),
             send_video: None,
             send_audio: None,
             video_remb: None,
+            audio_remb: None,
+            max_height: None,
+            max_width: None,
+            max_framerate: None,
             send_audio_updated_by: None,
+            send_video_locked: None,
+            send_audio_locked: None,
         }
     }
 
+    fn kind(self, kind: StreamKind) -> Self {
+        Self { kind, ..self }
+    }
+
     fn send_video(self, send_video: bool) -> Self {
         Self {
             send_video: Some(send_video),
@@ -100,12 +141,58 @@ impl StateConfigItem {
         }
     }
 
+    fn audio_remb(self, audio_remb: u32) -> Self {
+        Self {
+            audio_remb: Some(audio_remb),
+            ..self
+        }
+    }
+
+    fn max_height(self, max_height: u32) -> Self {
+        Self {
+            max_height: Some(max_height),
+            ..self
+        }
+    }
+
+    fn max_width(self, max_width: u32) -> Self {
+        Self {
+            max_width: Some(max_width),
+            ..self
+        }
+    }
+
+    fn max_framerate(self, max_framerate: u32) -> Self {
+        Self {
+            max_framerate: Some(max_framerate),
+            ..self
+        }
+    }
+
     fn send_audio_updated_by(self, send_audio_updated_by: AgentId) -> Self {
         Self {
             send_audio_updated_by: Some(send_audio_updated_by),
             ..self
         }
     }
+
+    fn send_video_locked(self, send_video_locked: bool) -> Self {
+        Self {
+            send_video_locked: Some(send_video_locked),
+            ..self
+        }
+    }
+
+    fn send_audio_locked(self, send_audio_locked: bool) -> Self {
+        Self {
+            send_audio_locked: Some(send_audio_locked),
+            ..self
+        }
+    }
+
+    fn is_locking(&self) -> bool {
+        self.send_video_locked.is_some() || self.send_audio_locked.is_some()
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -126,6 +213,24 @@ impl RequestHandler for UpdateHandler {
             return Err(anyhow!("Too many items in `configs` list"))
                 .error(AppErrorKind::InvalidPayload)?;
         }
+        // An agent may have a config for each stream kind it publishes.
+        let is_only_owned_config = !payload.configs.is_empty()
+            && payload
+                .configs
+                .iter()
+                .all(|c| &c.agent_id == reqp.as_agent_id());
+
+        // Locks may be set only by room updaters even on their own configs.
+        let is_self_update =
+            is_only_owned_config && !payload.configs.iter().any(|c| c.is_locking());
+
+        // Speakers may update only their own config and listeners may not update any.
+        let allowed_roles: &[RoomRole] = if is_self_update {
+            &[RoomRole::Moderator, RoomRole::Speaker]
+        } else {
+            &[RoomRole::Moderator]
+        };
+
         let conn = context.get_conn().await?;
         let room = task::spawn_blocking({
             let agent_id = reqp.as_agent_id().clone();
@@ -142,16 +247,14 @@ impl RequestHandler for UpdateHandler {
                 }
 
                 helpers::check_room_presence(&room, &agent_id, &conn)?;
+                helpers::check_room_role(&room, &agent_id, allowed_roles, &conn)?;
                 Ok::<_, AppError>(room)
             }
         })
         .await?;
         helpers::add_room_logger_tags(context, &room);
         // Authorize agent writer config updating on the tenant.
-        let is_only_owned_config =
-            payload.configs.len() == 1 && &payload.configs[0].agent_id == reqp.as_agent_id();
-
-        let maybe_authz_time = if is_only_owned_config {
+        let maybe_authz_time = if is_self_update {
             None
         } else {
             let room_id = room.id().to_string();
@@ -168,6 +271,7 @@ impl RequestHandler for UpdateHandler {
         let conn = context.get_conn().await?;
 
         let (rtc_writer_configs_with_rtcs, maybe_backend) = task::spawn_blocking({
+            let room = room.clone();
             let room_id = room.id();
             let backend_id = room.backend_id().cloned();
             let agent_id = reqp.as_agent_id().clone();
@@ -199,7 +303,38 @@ impl RequestHandler for UpdateHandler {
                             })
                             .error(AppErrorKind::InvalidPayload)?;
 
-                        let mut q = db::rtc_writer_config::UpsertQuery::new(*rtc_id);
+                        // Agents may not flip the fields locked by a moderator on their own.
+                        if is_self_update {
+                            check_locks(*rtc_id, &state_config_item, &conn)?;
+                        }
+
+                        let mut q = db::rtc_writer_config::UpsertQuery::new(*rtc_id)
+                            .kind(state_config_item.kind);
+
+                        // New configs start from the room defaults.
+                        let maybe_defaults = room
+                            .default_writer_config()
+                            .context("Failed to parse room default writer config")
+                            .error(AppErrorKind::MessageParsingFailed)?;
+
+                        if let Some(defaults) = maybe_defaults {
+                            let is_new = db::rtc_writer_config::FindQuery::new(
+                                *rtc_id,
+                                state_config_item.kind,
+                            )
+                            .execute(&conn)?
+                            .is_none();
+
+                            if is_new
+                                && helpers::are_room_defaults_applicable(
+                                    &room,
+                                    &state_config_item.agent_id,
+                                    &conn,
+                                )?
+                            {
+                                q = q.defaults(&defaults);
+                            }
+                        }
 
                         if let Some(send_video) = state_config_item.send_video {
                             q = q.send_video(send_video);
@@ -213,6 +348,42 @@ impl RequestHandler for UpdateHandler {
                             q = q.video_remb(video_remb.into());
                         }
 
+                        if let Some(audio_remb) = state_config_item.audio_remb {
+                            q = q.audio_remb(audio_remb.into());
+                        }
+
+                        if let Some(max_height) = state_config_item.max_height {
+                            let max_height = i32::try_from(max_height)
+                                .map_err(|_| anyhow!("Invalid max_height: {}", max_height))
+                                .error(AppErrorKind::InvalidPayload)?;
+
+                            q = q.max_height(max_height);
+                        }
+
+                        if let Some(max_width) = state_config_item.max_width {
+                            let max_width = i32::try_from(max_width)
+                                .map_err(|_| anyhow!("Invalid max_width: {}", max_width))
+                                .error(AppErrorKind::InvalidPayload)?;
+
+                            q = q.max_width(max_width);
+                        }
+
+                        if let Some(max_framerate) = state_config_item.max_framerate {
+                            let max_framerate = i32::try_from(max_framerate)
+                                .map_err(|_| anyhow!("Invalid max_framerate: {}", max_framerate))
+                                .error(AppErrorKind::InvalidPayload)?;
+
+                            q = q.max_framerate(max_framerate);
+                        }
+
+                        if let Some(send_video_locked) = state_config_item.send_video_locked {
+                            q = q.send_video_locked(send_video_locked);
+                        }
+
+                        if let Some(send_audio_locked) = state_config_item.send_audio_locked {
+                            q = q.send_audio_locked(send_audio_locked);
+                        }
+
                         q.execute(&conn)?;
                     }
 
@@ -234,30 +405,7 @@ impl RequestHandler for UpdateHandler {
         .await?;
 
         if let Some(backend) = maybe_backend {
-            let items = rtc_writer_configs_with_rtcs
-                .iter()
-                .map(
-                    |(rtc_writer_config, rtc)| UpdateWriterConfigRequestBodyConfigItem {
-                        stream_id: rtc.id(),
-                        send_video: rtc_writer_config.send_video(),
-                        send_audio: rtc_writer_config.send_audio(),
-                        video_remb: rtc_writer_config.video_remb().map(|x| x as u32),
-                    },
-                )
-                .collect::<Vec<UpdateWriterConfigRequestBodyConfigItem>>();
-
-            let request = UpdateWriterConfigRequest {
-                session_id: backend.session_id(),
-                handle_id: backend.handle_id(),
-                body: UpdateWriterConfigRequestBody::new(items),
-            };
-            context
-                .janus_clients()
-                .get_or_insert(&backend)
-                .error(AppErrorKind::BackendClientCreationFailed)?
-                .writer_update(request)
-                .await
-                .error(AppErrorKind::BackendRequestFailed)?;
+            update_janus(context, &backend, &rtc_writer_configs_with_rtcs).await?;
         }
 
         let state = State::new(room.id(), &rtc_writer_configs_with_rtcs);
@@ -289,6 +437,76 @@ impl RequestHandler for UpdateHandler {
     }
 }
 
+/// Sends the current writer configs to the Janus backend hosting the room.
+pub(super) async fn update_janus<C: Context>(
+    context: &mut C,
+    backend: &db::janus_backend::Object,
+    rtc_writer_configs_with_rtcs: &[(RtcWriterConfig, Rtc)],
+) -> StdResult<(), AppError> {
+    let items = rtc_writer_configs_with_rtcs
+        .iter()
+        .map(
+            |(rtc_writer_config, rtc)| UpdateWriterConfigRequestBodyConfigItem {
+                stream_id: rtc.id(),
+                kind: rtc_writer_config.kind(),
+                send_video: rtc_writer_config.send_video(),
+                send_audio: rtc_writer_config.send_audio(),
+                video_remb: rtc_writer_config.video_remb().map(|x| x as u32),
+                audio_remb: rtc_writer_config.audio_remb().map(|x| x as u32),
+                max_height: rtc_writer_config.max_height().map(|x| x as u32),
+                max_width: rtc_writer_config.max_width().map(|x| x as u32),
+                max_framerate: rtc_writer_config.max_framerate().map(|x| x as u32),
+            },
+        )
+        .collect::<Vec<UpdateWriterConfigRequestBodyConfigItem>>();
+
+    let request = UpdateWriterConfigRequest {
+        session_id: backend.session_id(),
+        handle_id: backend.handle_id(),
+        body: UpdateWriterConfigRequestBody::new(items),
+    };
+
+    context
+        .janus_clients()
+        .get_or_insert(backend)
+        .error(AppErrorKind::BackendClientCreationFailed)?
+        .writer_update(request)
+        .await
+        .error(AppErrorKind::BackendRequestFailed)?;
+
+    Ok(())
+}
+
+fn check_locks(
+    rtc_id: db::rtc::Id,
+    state_config_item: &StateConfigItem,
+    conn: &diesel::pg::PgConnection,
+) -> StdResult<(), AppError> {
+    let config = match db::rtc_writer_config::FindQuery::new(rtc_id, state_config_item.kind)
+        .execute(conn)?
+    {
+        Some(config) => config,
+        None => return Ok(()),
+    };
+
+    let is_flipping_video = config.send_video_locked()
+        && state_config_item
+            .send_video
+            .map_or(false, |send_video| send_video != config.send_video());
+
+    let is_flipping_audio = config.send_audio_locked()
+        && state_config_item
+            .send_audio
+            .map_or(false, |send_audio| send_audio != config.send_audio());
+
+    if is_flipping_video || is_flipping_audio {
+        return Err(anyhow!("The writer config is locked by a moderator"))
+            .error(AppErrorKind::WriterConfigLocked);
+    }
+
+    Ok(())
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 #[derive(Debug, Deserialize)]
@@ -358,7 +576,7 @@ mod tests {
         use std::ops::Bound;
 
         use crate::{
-            db::rtc::SharingPolicy as RtcSharingPolicy,
+            db::{room::WriterConfigDefaults, rtc::SharingPolicy as RtcSharingPolicy},
             test_helpers::{prelude::*, test_deps::LocalDeps},
         };
         use chrono::{Duration, Utc};
@@ -425,17 +643,31 @@ mod tests {
                 configs: vec![
                     StateConfigItem {
                         agent_id: agent2.agent_id().to_owned(),
+                        kind: StreamKind::Camera,
                         send_video: Some(true),
                         send_audio: Some(false),
                         video_remb: Some(300_000),
+                        audio_remb: None,
+                        max_height: None,
+                        max_width: None,
+                        max_framerate: None,
                         send_audio_updated_by: None,
+                        send_video_locked: None,
+                        send_audio_locked: None,
                     },
                     StateConfigItem {
                         agent_id: agent3.agent_id().to_owned(),
+                        kind: StreamKind::Camera,
                         send_video: Some(false),
                         send_audio: Some(false),
                         video_remb: None,
+                        audio_remb: None,
+                        max_height: None,
+                        max_width: None,
+                        max_framerate: None,
                         send_audio_updated_by: None,
+                        send_video_locked: None,
+                        send_audio_locked: None,
                     },
                 ],
             };
@@ -516,17 +748,31 @@ mod tests {
                 configs: vec![
                     StateConfigItem {
                         agent_id: agent4.agent_id().to_owned(),
+                        kind: StreamKind::Camera,
                         send_video: Some(true),
                         send_audio: Some(true),
                         video_remb: Some(1_000_000),
+                        audio_remb: None,
+                        max_height: None,
+                        max_width: None,
+                        max_framerate: None,
                         send_audio_updated_by: None,
+                        send_video_locked: None,
+                        send_audio_locked: None,
                     },
                     StateConfigItem {
                         agent_id: agent3.agent_id().to_owned(),
+                        kind: StreamKind::Camera,
                         send_video: None,
                         send_audio: Some(true),
                         video_remb: None,
+                        audio_remb: None,
+                        max_height: None,
+                        max_width: None,
+                        max_framerate: None,
                         send_audio_updated_by: None,
+                        send_video_locked: None,
+                        send_audio_locked: None,
                     },
                 ],
             };
@@ -650,6 +896,208 @@ mod tests {
             Ok(())
         }
 
+        #[async_std::test]
+        async fn listener_role() -> std::io::Result<()> {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+
+            // Insert a room with the agent having listener role.
+            let room = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(Utc::now()), Bound::Unbounded))
+                        .rtc_sharing_policy(RtcSharingPolicy::Owned)
+                        .insert(&conn);
+
+                    shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+
+                    factory::Rtc::new(room.id())
+                        .created_by(agent.agent_id().to_owned())
+                        .insert(&conn);
+
+                    db::room_role::UpsertQuery::new(
+                        room.id(),
+                        agent.account_id(),
+                        RoomRole::Listener,
+                    )
+                    .execute(&conn)
+                    .expect("Failed to insert room role");
+
+                    room
+                })
+                .unwrap();
+
+            // Make agent_writer_config.update request for the agent's own config.
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = State {
+                room_id: room.id(),
+                configs: vec![StateConfigItem::new(agent.agent_id().to_owned()).send_video(true)],
+            };
+
+            let err = handle_request::<UpdateHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected agent writer config update success");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
+            Ok(())
+        }
+
+        #[async_std::test]
+        async fn unmute_locked() -> std::io::Result<()> {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+            let moderator = TestAgent::new("web", "moderator", USR_AUDIENCE);
+
+            // Insert a room with the agent muted and locked by the moderator.
+            let room = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(Utc::now()), Bound::Unbounded))
+                        .rtc_sharing_policy(RtcSharingPolicy::Owned)
+                        .insert(&conn);
+
+                    shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+
+                    let rtc = factory::Rtc::new(room.id())
+                        .created_by(agent.agent_id().to_owned())
+                        .insert(&conn);
+
+                    factory::RtcWriterConfig::new(&rtc)
+                        .send_audio(false)
+                        .send_audio_updated_by(moderator.agent_id())
+                        .send_audio_locked(true)
+                        .insert(&conn);
+
+                    room
+                })
+                .unwrap();
+
+            // Make agent_writer_config.update request to unmute self.
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = State {
+                room_id: room.id(),
+                configs: vec![StateConfigItem::new(agent.agent_id().to_owned()).send_audio(true)],
+            };
+
+            let err = handle_request::<UpdateHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected agent writer config update success");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "writer_config_locked");
+            Ok(())
+        }
+
+        #[async_std::test]
+        async fn new_config_from_room_defaults() -> std::io::Result<()> {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+
+            // Insert a room with video off by default and the agent's RTC.
+            let room = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(Utc::now()), Bound::Unbounded))
+                        .rtc_sharing_policy(RtcSharingPolicy::Owned)
+                        .default_writer_config(WriterConfigDefaults::new(Some(false), None))
+                        .insert(&conn);
+
+                    shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+
+                    factory::Rtc::new(room.id())
+                        .created_by(agent.agent_id().to_owned())
+                        .insert(&conn);
+
+                    room
+                })
+                .unwrap();
+
+            // Make agent_writer_config.update request to mute own audio.
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = State {
+                room_id: room.id(),
+                configs: vec![StateConfigItem::new(agent.agent_id().to_owned()).send_audio(false)],
+            };
+
+            let messages = handle_request::<UpdateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Agent writer config update failed");
+
+            // Assert that the video has been taken from the room defaults.
+            let (state, respp, _) = find_response::<State>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(state.configs.len(), 1);
+            assert_eq!(state.configs[0].send_video, Some(false));
+            assert_eq!(state.configs[0].send_audio, Some(false));
+            Ok(())
+        }
+
+        #[async_std::test]
+        async fn lock_own_config_unauthorized() -> std::io::Result<()> {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+
+            // Insert a room with the agent and its RTC.
+            let room = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(Utc::now()), Bound::Unbounded))
+                        .rtc_sharing_policy(RtcSharingPolicy::Owned)
+                        .insert(&conn);
+
+                    shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+
+                    factory::Rtc::new(room.id())
+                        .created_by(agent.agent_id().to_owned())
+                        .insert(&conn);
+
+                    room
+                })
+                .unwrap();
+
+            // Make agent_writer_config.update request to lock own config without permission.
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = State {
+                room_id: room.id(),
+                configs: vec![StateConfigItem::new(agent.agent_id().to_owned())
+                    .send_audio(true)
+                    .send_audio_locked(true)],
+            };
+
+            let err = handle_request::<UpdateHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected agent writer config update success");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
+            Ok(())
+        }
+
         #[async_std::test]
         async fn too_many_config_items() -> std::io::Result<()> {
             // Make agent_writer_config.update request.
@@ -665,10 +1113,17 @@ mod tests {
 
                     StateConfigItem {
                         agent_id: agent.agent_id().to_owned(),
+                        kind: StreamKind::Camera,
                         send_video: Some(false),
                         send_audio: Some(true),
                         video_remb: Some(300_000),
+                        audio_remb: None,
+                        max_height: None,
+                        max_width: None,
+                        max_framerate: None,
                         send_audio_updated_by: None,
+                        send_video_locked: None,
+                        send_audio_locked: None,
                     }
                 })
                 .collect::<Vec<_>>();
@@ -0,0 +1,626 @@
+use std::{collections::HashMap, result::Result as StdResult};
+
+use anyhow::anyhow;
+use async_std::{stream, task};
+use async_trait::async_trait;
+use chrono::{serde::ts_seconds, DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use svc_agent::{
+    mqtt::{IncomingRequestProperties, ResponseStatus},
+    AgentId,
+};
+
+use crate::{
+    app::{context::Context, endpoint::prelude::*, metrics::HistogramExt},
+    db,
+};
+
+////////////////////////////////////////////////////////////////////////////////
+
+const MAX_LIMIT: i64 = 100;
+
+/// Backend state as seen by the balancer with live loads and usage.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Backend {
+    id: AgentId,
+    capacity: Option<i32>,
+    balancer_capacity: Option<i32>,
+    group: Option<String>,
+    region: Option<String>,
+    api_version: String,
+    draining: bool,
+    #[serde(with = "ts_seconds")]
+    online_since: DateTime<Utc>,
+    online_for: i64,
+    reserve_load: i64,
+    taken_load: i64,
+    connected_agents: i64,
+    pinned_rooms: Vec<db::room::Id>,
+}
+
+fn load_backends(
+    backends: Vec<db::janus_backend::Object>,
+    conn: &diesel::pg::PgConnection,
+) -> StdResult<Vec<Backend>, diesel::result::Error> {
+    let ids = backends.iter().map(|b| b.id()).collect::<Vec<_>>();
+
+    let loads = db::janus_backend::reserve_load_for_each_backend(conn)?
+        .into_iter()
+        .map(|load| (load.backend_id, (load.load, load.taken)))
+        .collect::<HashMap<_, _>>();
+
+    let connected_agents = db::janus_backend::connected_agents_for_each_backend(conn)?
+        .into_iter()
+        .map(|row| (row.backend_id, row.agents))
+        .collect::<HashMap<_, _>>();
+
+    let mut pinned_rooms = HashMap::<AgentId, Vec<db::room::Id>>::new();
+
+    for (backend_id, room_id) in db::room::pinned_room_ids(&ids, conn)? {
+        pinned_rooms.entry(backend_id).or_default().push(room_id);
+    }
+
+    let now = Utc::now();
+
+    let backends = backends
+        .iter()
+        .map(|backend| {
+            let (reserve_load, taken_load) = loads.get(backend.id()).copied().unwrap_or((0, 0));
+
+            Backend {
+                id: backend.id().to_owned(),
+                capacity: backend.capacity(),
+                balancer_capacity: backend.balancer_capacity(),
+                group: backend.group().map(ToOwned::to_owned),
+                region: backend.region().map(ToOwned::to_owned),
+                api_version: backend.api_version().to_owned(),
+                draining: backend.is_draining(),
+                online_since: backend.created_at(),
+                online_for: (now - backend.created_at()).num_seconds(),
+                reserve_load,
+                taken_load,
+                connected_agents: connected_agents.get(backend.id()).copied().unwrap_or(0),
+                pinned_rooms: pinned_rooms.remove(backend.id()).unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    Ok(backends)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub struct ListRequest {
+    offset: Option<i64>,
+    limit: Option<i64>,
+}
+
+pub struct ListHandler;
+
+#[async_trait]
+impl RequestHandler for ListHandler {
+    type Payload = ListRequest;
+    const ERROR_TITLE: &'static str = "Failed to list backends";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        // Authorization: only trusted subjects are allowed to inspect backends.
+        let audience = context.agent_id().as_account_id().audience();
+
+        let authz_time = context
+            .authz()
+            .authorize(audience, reqp, vec!["system"], "read")
+            .await?;
+        context.metrics().observe_auth(authz_time);
+
+        let limit = match payload.limit {
+            Some(limit) if limit < 1 => {
+                return Err(anyhow!("Limit must be positive")).error(AppErrorKind::InvalidPayload);
+            }
+            Some(limit) => std::cmp::min(limit, MAX_LIMIT),
+            None => MAX_LIMIT,
+        };
+
+        if let Some(offset) = payload.offset {
+            if offset < 0 {
+                return Err(anyhow!("Offset must not be negative"))
+                    .error(AppErrorKind::InvalidPayload);
+            }
+        }
+
+        let conn = context.get_conn().await?;
+
+        let backends = task::spawn_blocking(move || {
+            let mut query = db::janus_backend::ListQuery::new();
+
+            if let Some(offset) = payload.offset {
+                query = query.offset(offset);
+            }
+
+            query = query.limit(limit);
+            let backends = query.execute(&conn)?;
+            load_backends(backends, &conn)
+        })
+        .await?;
+
+        context
+            .metrics()
+            .request_duration
+            .backend_list
+            .observe_timestamp(context.start_timestamp());
+
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            backends,
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub struct ReadRequest {
+    id: AgentId,
+}
+
+pub struct ReadHandler;
+
+#[async_trait]
+impl RequestHandler for ReadHandler {
+    type Payload = ReadRequest;
+    const ERROR_TITLE: &'static str = "Failed to read backend";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        // Authorization: only trusted subjects are allowed to inspect backends.
+        let audience = context.agent_id().as_account_id().audience();
+
+        let authz_time = context
+            .authz()
+            .authorize(audience, reqp, vec!["system"], "read")
+            .await?;
+        context.metrics().observe_auth(authz_time);
+
+        let conn = context.get_conn().await?;
+
+        let maybe_backend = task::spawn_blocking(move || {
+            let maybe_backend = db::janus_backend::FindQuery::new()
+                .id(&payload.id)
+                .execute(&conn)?;
+
+            match maybe_backend {
+                Some(backend) => load_backends(vec![backend], &conn).map(|mut v| v.pop()),
+                None => Ok(None),
+            }
+        })
+        .await?;
+
+        let backend = maybe_backend
+            .ok_or_else(|| anyhow!("Backend not found"))
+            .error(AppErrorKind::BackendNotFound)?;
+
+        context
+            .metrics()
+            .request_duration
+            .backend_read
+            .observe_timestamp(context.start_timestamp());
+
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            backend,
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub struct DrainRequest {
+    id: AgentId,
+    #[serde(default = "DrainRequest::default_draining")]
+    draining: bool,
+}
+
+impl DrainRequest {
+    fn default_draining() -> bool {
+        true
+    }
+}
+
+pub struct DrainHandler;
+
+#[async_trait]
+impl RequestHandler for DrainHandler {
+    type Payload = DrainRequest;
+    const ERROR_TITLE: &'static str = "Failed to drain backend";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        // Authorization: only trusted subjects are allowed to perform operations with the system
+        let audience = context.agent_id().as_account_id().audience();
+
+        let authz_time = context
+            .authz()
+            .authorize(audience, reqp, vec!["system"], "update")
+            .await?;
+        context.metrics().observe_auth(authz_time);
+
+        let conn = context.get_conn().await?;
+
+        let maybe_backend = task::spawn_blocking(move || {
+            let maybe_backend =
+                db::janus_backend::set_draining(&payload.id, payload.draining, &conn)?;
+
+            match maybe_backend {
+                Some(backend) => load_backends(vec![backend], &conn).map(|mut v| v.pop()),
+                None => Ok(None),
+            }
+        })
+        .await?;
+
+        let backend = maybe_backend
+            .ok_or_else(|| anyhow!("Backend not found"))
+            .error(AppErrorKind::BackendNotFound)?;
+
+        context
+            .metrics()
+            .request_duration
+            .backend_drain
+            .observe_timestamp(context.start_timestamp());
+
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            backend,
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        ))))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod test {
+    mod list {
+        use crate::{
+            backend::janus::client::{HandleId, SessionId},
+            test_helpers::{prelude::*, test_deps::LocalDeps},
+        };
+
+        use super::super::*;
+
+        #[async_std::test]
+        async fn list_backends() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let mut authz = TestAuthz::new();
+
+            let (backend, idle_backend, room) = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let backend = shared_helpers::insert_janus_backend(
+                        &conn,
+                        "test",
+                        SessionId::random(),
+                        HandleId::random(),
+                    );
+
+                    let idle_backend = shared_helpers::insert_janus_backend(
+                        &conn,
+                        "test",
+                        SessionId::random(),
+                        HandleId::random(),
+                    );
+
+                    // Insert a room pinned to the backend with a connected agent.
+                    let room = shared_helpers::insert_room_with_backend_id(&conn, backend.id());
+                    let rtc = shared_helpers::insert_rtc_with_room(&conn, &room);
+                    let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                    shared_helpers::insert_connected_agent(
+                        &conn,
+                        agent.agent_id(),
+                        room.id(),
+                        rtc.id(),
+                    );
+
+                    // A closed room is not pinned anymore.
+                    shared_helpers::insert_closed_room_with_backend_id(&conn, backend.id());
+
+                    (backend, idle_backend, room)
+                })
+                .expect("Failed to insert backends");
+
+            // Allow admin to inspect backends.
+            let agent = TestAgent::new("alpha", "admin", SVC_AUDIENCE);
+            authz.allow(agent.account_id(), vec!["system"], "read");
+
+            // Make backend.list request.
+            let mut context = TestContext::new(db, authz);
+
+            let payload = ListRequest {
+                offset: None,
+                limit: None,
+            };
+
+            let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Backends listing failed");
+
+            // Assert response.
+            let (backends, respp, _) = find_response::<Vec<Backend>>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(backends.len(), 2);
+
+            let busy = backends
+                .iter()
+                .find(|b| &b.id == backend.id())
+                .expect("Backend not listed");
+
+            assert_eq!(busy.connected_agents, 1);
+            assert_eq!(busy.pinned_rooms, vec![room.id()]);
+
+            let idle = backends
+                .iter()
+                .find(|b| &b.id == idle_backend.id())
+                .expect("Idle backend not listed");
+
+            assert_eq!(idle.connected_agents, 0);
+            assert_eq!(idle.reserve_load, 0);
+            assert_eq!(idle.taken_load, 0);
+            assert!(idle.pinned_rooms.is_empty());
+        }
+
+        #[async_std::test]
+        async fn list_backends_not_authorized() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = ListRequest {
+                offset: None,
+                limit: None,
+            };
+
+            let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on backends listing");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
+        }
+
+        #[async_std::test]
+        async fn list_backends_invalid_pagination() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("alpha", "admin", SVC_AUDIENCE);
+            let mut authz = TestAuthz::new();
+            authz.allow(agent.account_id(), vec!["system"], "read");
+            let mut context = TestContext::new(db, authz);
+
+            let payloads = vec![(None, Some(0)), (None, Some(-1)), (Some(-1), None)];
+
+            for (offset, limit) in payloads {
+                let payload = ListRequest { offset, limit };
+
+                let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect_err("Unexpected success on backends listing");
+
+                assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+                assert_eq!(err.kind(), "invalid_payload");
+            }
+        }
+    }
+
+    mod read {
+        use crate::{
+            backend::janus::client::{HandleId, SessionId},
+            test_helpers::{prelude::*, test_deps::LocalDeps},
+        };
+
+        use super::super::*;
+
+        #[async_std::test]
+        async fn read_backend() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let mut authz = TestAuthz::new();
+
+            let backend = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    shared_helpers::insert_janus_backend(
+                        &conn,
+                        "test",
+                        SessionId::random(),
+                        HandleId::random(),
+                    )
+                })
+                .expect("Failed to insert backend");
+
+            // Allow admin to inspect backends.
+            let agent = TestAgent::new("alpha", "admin", SVC_AUDIENCE);
+            authz.allow(agent.account_id(), vec!["system"], "read");
+
+            // Make backend.read request.
+            let mut context = TestContext::new(db, authz);
+
+            let payload = ReadRequest {
+                id: backend.id().to_owned(),
+            };
+
+            let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Backend reading failed");
+
+            // Assert response.
+            let (resp_backend, respp, _) = find_response::<Backend>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(&resp_backend.id, backend.id());
+            assert_eq!(resp_backend.api_version, backend.api_version());
+            assert!(resp_backend.pinned_rooms.is_empty());
+        }
+
+        #[async_std::test]
+        async fn read_missing_backend() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let mut authz = TestAuthz::new();
+
+            let agent = TestAgent::new("alpha", "admin", SVC_AUDIENCE);
+            authz.allow(agent.account_id(), vec!["system"], "read");
+            let mut context = TestContext::new(db, authz);
+
+            let payload = ReadRequest {
+                id: TestAgent::new("alpha", "janus", SVC_AUDIENCE)
+                    .agent_id()
+                    .to_owned(),
+            };
+
+            let err = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on backend reading");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "backend_not_found");
+        }
+    }
+    mod drain {
+        use crate::{
+            app::endpoint::helpers,
+            backend::janus::client::{HandleId, SessionId},
+            config::BalancerConfig,
+            test_helpers::{prelude::*, test_deps::LocalDeps},
+        };
+
+        use super::super::*;
+
+        #[async_std::test]
+        async fn drain_backend() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let mut authz = TestAuthz::new();
+
+            let (backend, other_backend) = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let backend = shared_helpers::insert_janus_backend(
+                        &conn,
+                        "test",
+                        SessionId::random(),
+                        HandleId::random(),
+                    );
+
+                    let other_backend = shared_helpers::insert_janus_backend(
+                        &conn,
+                        "test",
+                        SessionId::random(),
+                        HandleId::random(),
+                    );
+
+                    (backend, other_backend)
+                })
+                .expect("Failed to insert backends");
+
+            // Allow cron to drain backends.
+            let agent = TestAgent::new("alpha", "cron", SVC_AUDIENCE);
+            authz.allow(agent.account_id(), vec!["system"], "update");
+
+            // Make backend.drain request.
+            let mut context = TestContext::new(db.clone(), authz);
+
+            let payload = DrainRequest {
+                id: backend.id().to_owned(),
+                draining: true,
+            };
+
+            let messages = handle_request::<DrainHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Backend draining failed");
+
+            // Assert response.
+            let (resp_backend, respp, _) = find_response::<Backend>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(&resp_backend.id, backend.id());
+            assert!(resp_backend.draining);
+
+            // Assert the balancer doesn't consider the draining backend anymore.
+            let conn = db
+                .connection_pool()
+                .get()
+                .expect("Failed to get DB connection");
+
+            let backends = helpers::load_backends(None, &BalancerConfig::default(), &conn)
+                .expect("Failed to load backends");
+
+            let ids = backends
+                .iter()
+                .map(|b| b.id().to_owned())
+                .collect::<Vec<_>>();
+            assert_eq!(ids, vec![other_backend.id().to_owned()]);
+        }
+
+        #[async_std::test]
+        async fn drain_backend_not_authorized() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+
+            let backend = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    shared_helpers::insert_janus_backend(
+                        &conn,
+                        "test",
+                        SessionId::random(),
+                        HandleId::random(),
+                    )
+                })
+                .expect("Failed to insert backend");
+
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = DrainRequest {
+                id: backend.id().to_owned(),
+                draining: true,
+            };
+
+            let err = handle_request::<DrainHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on backend draining");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
+        }
+    }
+}
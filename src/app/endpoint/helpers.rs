@@ -2,16 +2,18 @@ use std::ops::Bound;
 
 use crate::{
     app::{
+        balancer::{BackendLoad, Balancer, Selection},
         context::Context,
         error::{Error as AppError, ErrorExt, ErrorKind as AppErrorKind},
         API_VERSION,
     },
+    config::BalancerConfig,
     db,
     db::room::Object as Room,
 };
 use anyhow::anyhow;
 use chrono::{DateTime, Duration, Utc};
-use diesel::pg::PgConnection;
+use diesel::{pg::PgConnection, Connection};
 use serde::Serialize;
 use slog::o;
 use svc_agent::{
@@ -19,7 +21,7 @@ use svc_agent::{
         IncomingRequestProperties, IntoPublishableMessage, OutgoingEvent, OutgoingEventProperties,
         OutgoingResponse, ResponseStatus, ShortTermTimingProperties,
     },
-    AgentId,
+    AgentId, Authenticable,
 };
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -170,6 +172,45 @@ pub fn check_room_presence(
     }
 }
 
+pub fn find_room_role(
+    room: &db::room::Object,
+    agent_id: &AgentId,
+    conn: &PgConnection,
+) -> Result<Option<db::room_role::Role>, AppError> {
+    let maybe_room_role =
+        db::room_role::FindQuery::new(room.id(), agent_id.as_account_id()).execute(conn)?;
+
+    Ok(maybe_room_role.map(|room_role| room_role.role()))
+}
+
+/// Room default writer and reader configs don't apply to moderators so that e.g. the teacher
+/// stays visible and sees everyone when participants' video is off by default.
+pub fn are_room_defaults_applicable(
+    room: &db::room::Object,
+    agent_id: &AgentId,
+    conn: &PgConnection,
+) -> Result<bool, AppError> {
+    let role = find_room_role(room, agent_id, conn)?;
+    Ok(role != Some(db::room_role::Role::Moderator))
+}
+
+/// Local role-based check in front of svc-authz. Agents without a role in the room are
+/// not restricted by it.
+pub fn check_room_role(
+    room: &db::room::Object,
+    agent_id: &AgentId,
+    allowed_roles: &[db::room_role::Role],
+    conn: &PgConnection,
+) -> Result<(), AppError> {
+    match find_room_role(room, agent_id, conn)? {
+        Some(role) if !allowed_roles.contains(&role) => {
+            Err(anyhow!("Action is not allowed for '{:?}' room role", role))
+                .error(AppErrorKind::AccessDenied)
+        }
+        _ => Ok(()),
+    }
+}
+
 pub fn add_room_logger_tags<C: Context>(context: &mut C, room: &db::room::Object) {
     context.add_logger_tags(o!("room_id" => room.id().to_string()));
 
@@ -177,3 +218,156 @@ pub fn add_room_logger_tags<C: Context>(context: &mut C, room: &db::room::Object
         context.add_logger_tags(o!("scope" => scope.to_string()));
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Reservation period limit when `max_room_duration` isn't configured.
+const DEFAULT_BALANCING_PERIOD_HOURS: i64 = 24;
+
+/// Period the room needs a backend for: from its opening or now whichever is later till closing
+/// but no longer than the max room duration so rooms without closing time don't hold
+/// the backend forever.
+pub fn balancing_period(
+    room: &Room,
+    now: DateTime<Utc>,
+    max_room_duration: Option<i64>,
+) -> db::room::Time {
+    let start = match room.time().0 {
+        Bound::Included(dt) | Bound::Excluded(dt) if dt > now => dt,
+        _ => now,
+    };
+
+    let max_end =
+        start + Duration::hours(max_room_duration.unwrap_or(DEFAULT_BALANCING_PERIOD_HOURS));
+
+    let end = match room.time().1 {
+        Bound::Included(dt) | Bound::Excluded(dt) if dt < max_end => dt.max(start),
+        _ => max_end,
+    };
+
+    (Bound::Included(start), Bound::Excluded(end))
+}
+
+/// Loads of backends considering rooms open now and stats measured by backends if configured.
+pub fn load_backends(
+    group: Option<&str>,
+    config: &BalancerConfig,
+    conn: &PgConnection,
+) -> Result<Vec<BackendLoad>, AppError> {
+    let rows = db::janus_backend::balancer_load(group, conn)?;
+    Ok(blend_stats(rows, config, Utc::now()))
+}
+
+/// Loads of backends at the peak of the room's balancing period considering rooms scheduled
+/// for it and stats measured by backends if configured.
+pub fn load_backends_for_period(
+    room: &Room,
+    group: Option<&str>,
+    config: &BalancerConfig,
+    max_room_duration: Option<i64>,
+    conn: &PgConnection,
+) -> Result<Vec<BackendLoad>, AppError> {
+    let now = Utc::now();
+    let period = balancing_period(room, now, max_room_duration);
+    let rows = db::janus_backend::reserve_load(room.id(), period, group, conn)?;
+    Ok(blend_stats(rows, config, now))
+}
+
+fn blend_stats(
+    rows: Vec<db::janus_backend::BalancerLoadRow>,
+    config: &BalancerConfig,
+    now: DateTime<Utc>,
+) -> Vec<BackendLoad> {
+    rows.into_iter()
+        .map(BackendLoad::from)
+        .map(|backend| match config.stats {
+            Some(ref stats_config) => backend.blend_stats(stats_config, now),
+            None => backend,
+        })
+        .collect::<Vec<_>>()
+}
+
+/// Backends hosting other rooms of the room's classroom if classroom stickiness is enabled.
+pub fn classroom_backend_ids(
+    room: &Room,
+    config: &BalancerConfig,
+    conn: &PgConnection,
+) -> Result<Vec<AgentId>, AppError> {
+    match room.classroom_id() {
+        Some(classroom_id) if config.classroom_sticky => {
+            let ids = db::room::classroom_backend_ids(classroom_id, room.id(), conn)?;
+            Ok(ids)
+        }
+        _ => Ok(vec![]),
+    }
+}
+
+/// Assigns a backend capable to host the room's reserve for its whole period ahead of time.
+/// A room having a backend already keeps it if it's still capable, e.g. after the reserve
+/// or the time has changed. Otherwise the room gets moved to another capable backend unless
+/// it has got streams on the current one. Returns the updated room or `None` if there's
+/// no capable backend for it.
+pub fn reserve_backend(
+    room: &Room,
+    balancer: &Balancer,
+    config: &BalancerConfig,
+    group: Option<&str>,
+    max_room_duration: Option<i64>,
+    conn: &PgConnection,
+) -> Result<Option<Room>, AppError> {
+    conn.transaction::<_, AppError, _>(|| {
+        // Concurrent reservations would see the same load and might choose the same backend.
+        db::room::lock_reservations(conn)?;
+
+        let strategy_kind = config.strategy_kind(group, room.audience());
+        let backends = load_backends_for_period(room, group, config, max_room_duration, conn)?;
+        let reserve = room.reserve().unwrap_or(1);
+
+        if let Some(backend_id) = room.backend_id() {
+            let is_capable = backends
+                .iter()
+                .any(|backend| backend.id() == backend_id && backend.is_capable(reserve));
+
+            if is_capable {
+                return Ok(Some(room.to_owned()));
+            }
+
+            // Streams and their recordings are bound to the backend.
+            let streams = db::janus_rtc_stream::ListQuery::new()
+                .room_id(room.id())
+                .limit(1)
+                .execute(conn)?;
+
+            if !streams.is_empty() {
+                return Ok(None);
+            }
+        }
+
+        let preferred = classroom_backend_ids(room, config, conn)?;
+
+        match balancer.select_preferred(
+            strategy_kind,
+            &backends,
+            reserve,
+            room.region(),
+            &preferred,
+        ) {
+            Some(Selection::Capable(backend)) | Some(Selection::OutOfRegion(backend)) => {
+                let room = db::room::UpdateQuery::new(room.id())
+                    .backend_id(Some(backend.id()))
+                    .execute(conn)?;
+
+                Ok(Some(room))
+            }
+            Some(Selection::Fallback(_)) | None => Ok(None),
+        }
+    })
+}
+
+/// Whether the room's backend should be reserved or its reservation checked
+/// on creation or update.
+pub fn needs_reservation(room: &Room) -> bool {
+    room.reserve().is_some()
+        && room.rtc_sharing_policy() != db::rtc::SharingPolicy::None
+        && !room.is_closed()
+}
@@ -1,31 +1,46 @@
 use crate::{
-    app::{context::Context, endpoint::prelude::*, metrics::HistogramExt, API_VERSION},
+    app::{
+        context::Context,
+        endpoint::prelude::*,
+        message_handler::{build_error_response, MessageStream},
+        metrics::HistogramExt,
+        API_VERSION,
+    },
     db,
+    db::room_role::Role as RoomRole,
 };
-use anyhow::anyhow;
+use anyhow::{anyhow, Context as AnyhowContext};
 use async_std::{stream, task};
 use async_trait::async_trait;
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value as JsonValue};
+use slog::warn;
+use std::collections::HashMap;
 use svc_agent::{
     mqtt::{
         IncomingRequestProperties, IncomingResponseProperties, IntoPublishableMessage,
-        OutgoingRequest, OutgoingResponse, OutgoingResponseProperties, ResponseStatus,
-        ShortTermTimingProperties, SubscriptionTopic,
+        OutgoingEvent, OutgoingEventProperties, OutgoingRequest, OutgoingResponse,
+        OutgoingResponseProperties, ResponseStatus, ShortTermTimingProperties, SubscriptionTopic,
     },
     Addressable, AgentId, Subscription,
 };
+use uuid::Uuid;
 
 ////////////////////////////////////////////////////////////////////////////////
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CorrelationDataPayload {
+    /// Key of the request in pending unicasts.
+    /// Missing for requests sent before pending unicasts tracking was introduced.
+    #[serde(default)]
+    id: Option<Uuid>,
     reqp: IncomingRequestProperties,
 }
 
 impl CorrelationDataPayload {
-    pub fn new(reqp: IncomingRequestProperties) -> Self {
-        Self { reqp }
+    pub fn new(id: Uuid, reqp: IncomingRequestProperties) -> Self {
+        Self { id: Some(id), reqp }
     }
 }
 
@@ -59,6 +74,21 @@ impl RequestHandler for UnicastHandler {
 
                 helpers::check_room_presence(&room, &reqp_agent_id, &conn)?;
                 helpers::check_room_presence(&room, &payload_agent_id, &conn)?;
+
+                // Listeners may send unicast messages to moderators only.
+                let sender_role = helpers::find_room_role(&room, &reqp_agent_id, &conn)?;
+
+                if sender_role == Some(RoomRole::Listener) {
+                    let recipient_role = helpers::find_room_role(&room, &payload_agent_id, &conn)?;
+
+                    if recipient_role != Some(RoomRole::Moderator) {
+                        return Err(anyhow!(
+                            "Listeners may send unicast messages to moderators only"
+                        ))
+                        .error(AppErrorKind::AccessDenied);
+                    }
+                }
+
                 Ok::<_, AppError>(room)
             })
             .await?;
@@ -71,7 +101,8 @@ impl RequestHandler for UnicastHandler {
                 .map_err(|err| anyhow!("Error building responses subscription topic: {}", err))
                 .error(AppErrorKind::MessageBuildingFailed)?;
 
-        let corr_data_payload = CorrelationDataPayload::new(reqp.to_owned());
+        let id = Uuid::new_v4();
+        let corr_data_payload = CorrelationDataPayload::new(id, reqp.to_owned());
 
         let corr_data = CorrelationData::MessageUnicast(corr_data_payload)
             .dump()
@@ -90,6 +121,30 @@ impl RequestHandler for UnicastHandler {
             &payload.agent_id,
             API_VERSION,
         );
+
+        // Track the request until the target agent responds so the sender doesn't wait forever.
+        let timeout = chrono::Duration::from_std(context.config().unicast.timeout)
+            .context("Invalid unicast timeout")
+            .error(AppErrorKind::MessageBuildingFailed)?;
+
+        let reqp_json = serde_json::to_value(reqp)
+            .context("Failed to serialize request properties")
+            .error(AppErrorKind::MessageBuildingFailed)?;
+
+        let deadline = context.start_timestamp() + timeout;
+        let conn = context.get_conn().await?;
+
+        task::spawn_blocking({
+            let room_id = payload.room_id;
+            let target = payload.agent_id.to_owned();
+
+            move || {
+                db::pending_unicast::InsertQuery::new(id, room_id, &target, reqp_json, deadline)
+                    .execute(&conn)
+            }
+        })
+        .await?;
+
         context
             .metrics()
             .request_duration
@@ -103,6 +158,136 @@ impl RequestHandler for UnicastHandler {
 
 ///////////////////////////////////////////////////////////////////////////////
 
+#[derive(Debug, Deserialize)]
+pub struct MulticastRequest {
+    room_id: db::room::Id,
+    /// Either `agent_ids` or `role` must be specified.
+    agent_ids: Option<Vec<AgentId>>,
+    role: Option<RoomRole>,
+    data: JsonValue,
+}
+
+#[derive(Debug, Serialize)]
+struct MulticastResponse {
+    agent_ids: Vec<AgentId>,
+}
+
+pub struct MulticastHandler;
+
+#[async_trait]
+impl RequestHandler for MulticastHandler {
+    type Payload = MulticastRequest;
+    const ERROR_TITLE: &'static str = "Failed to send multicast message";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let conn = context.get_conn().await?;
+        let (room, agent_ids) = task::spawn_blocking({
+            let reqp_agent_id = reqp.as_agent_id().clone();
+            let room_id = payload.room_id;
+            let agent_ids = payload.agent_ids.clone();
+            let role = payload.role;
+
+            move || {
+                let room =
+                    helpers::find_room_by_id(room_id, helpers::RoomTimeRequirement::Open, &conn)?;
+
+                helpers::check_room_presence(&room, &reqp_agent_id, &conn)?;
+
+                let roles = db::room_role::ListQuery::new(room.id())
+                    .execute(&conn)?
+                    .into_iter()
+                    .map(|room_role| (room_role.account_id().to_owned(), room_role.role()))
+                    .collect::<HashMap<_, _>>();
+
+                let agent_ids = match (agent_ids, role) {
+                    (Some(mut agent_ids), None) => {
+                        agent_ids.sort_by_key(|agent_id| agent_id.to_string());
+                        agent_ids.dedup();
+
+                        for agent_id in &agent_ids {
+                            helpers::check_room_presence(&room, agent_id, &conn)?;
+                        }
+
+                        agent_ids
+                    }
+                    (None, Some(role)) => db::agent::ListQuery::new()
+                        .room_id(room.id())
+                        .statuses(&[db::agent::Status::Ready])
+                        .execute(&conn)?
+                        .into_iter()
+                        .map(|agent| agent.agent_id().to_owned())
+                        .filter(|agent_id| {
+                            agent_id != &reqp_agent_id
+                                && roles.get(agent_id.as_account_id()) == Some(&role)
+                        })
+                        .collect::<Vec<_>>(),
+                    _ => {
+                        return Err(anyhow!("Either agent_ids or role must be specified"))
+                            .error(AppErrorKind::InvalidPayload);
+                    }
+                };
+
+                // Listeners may send multicast messages to moderators only.
+                if roles.get(reqp_agent_id.as_account_id()) == Some(&RoomRole::Listener) {
+                    let is_moderators_only = agent_ids.iter().all(|agent_id| {
+                        roles.get(agent_id.as_account_id()) == Some(&RoomRole::Moderator)
+                    });
+
+                    if !is_moderators_only {
+                        return Err(anyhow!(
+                            "Listeners may send multicast messages to moderators only"
+                        ))
+                        .error(AppErrorKind::AccessDenied);
+                    }
+                }
+
+                Ok::<_, AppError>((room, agent_ids))
+            }
+        })
+        .await?;
+        helpers::add_room_logger_tags(context, &room);
+
+        // Fan out to the inbox of each target agent.
+        let mut messages = agent_ids
+            .iter()
+            .map(|agent_id| {
+                let timing = ShortTermTimingProperties::until_now(context.start_timestamp());
+                let mut props = OutgoingEventProperties::new("message.multicast", timing);
+                props.set_tracking(reqp.tracking().to_owned());
+
+                let event =
+                    OutgoingEvent::unicast(payload.data.clone(), props, agent_id, API_VERSION);
+
+                Box::new(event) as Box<dyn IntoPublishableMessage + Send>
+            })
+            .collect::<Vec<_>>();
+
+        let response = helpers::build_response(
+            ResponseStatus::OK,
+            MulticastResponse { agent_ids },
+            reqp,
+            context.start_timestamp(),
+            None,
+        );
+
+        messages.insert(0, response);
+
+        context
+            .metrics()
+            .request_duration
+            .message_multicast
+            .observe_timestamp(context.start_timestamp());
+
+        Ok(Box::new(stream::from_iter(messages)))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug, Deserialize)]
 pub struct BroadcastRequest {
     room_id: db::room::Id,
@@ -123,24 +308,49 @@ impl RequestHandler for BroadcastHandler {
         reqp: &IncomingRequestProperties,
     ) -> Result {
         let conn = context.get_conn().await?;
-        let room = task::spawn_blocking({
+        let (room, maybe_message) = task::spawn_blocking({
             let agent_id = reqp.as_agent_id().clone();
             let room_id = payload.room_id;
+            let label = payload.label.clone();
+            let data = payload.data.clone();
+
             move || {
                 let room =
                     helpers::find_room_by_id(room_id, helpers::RoomTimeRequirement::Open, &conn)?;
 
                 helpers::check_room_presence(&room, &agent_id, &conn)?;
-                Ok::<_, AppError>(room)
+
+                // Store the message if the room keeps history for its label.
+                let maybe_message = match label {
+                    Some(ref label) if room.is_message_persistent(label) => Some(
+                        db::message::InsertQuery::new(room.id(), &agent_id, label, &data)
+                            .execute(&conn)?,
+                    ),
+                    _ => None,
+                };
+
+                Ok::<_, AppError>((room, maybe_message))
             }
         })
         .await?;
         helpers::add_room_logger_tags(context, &room);
 
         // Respond and broadcast to the room topic.
+        // Stored messages are sent with their ids so that they could be deleted later.
+        let (response_payload, notification_payload) = match maybe_message {
+            Some(message) => {
+                let message = serde_json::to_value(message)
+                    .context("Failed to serialize message")
+                    .error(AppErrorKind::MessageBuildingFailed)?;
+
+                (message.clone(), message)
+            }
+            None => (json!({}), payload.data),
+        };
+
         let response = helpers::build_response(
             ResponseStatus::OK,
-            json!({}),
+            response_payload,
             reqp,
             context.start_timestamp(),
             None,
@@ -149,7 +359,7 @@ impl RequestHandler for BroadcastHandler {
         let notification = helpers::build_notification(
             "message.broadcast",
             &format!("rooms/{}/events", room.id()),
-            payload.data,
+            notification_payload,
             reqp,
             context.start_timestamp(),
         );
@@ -163,6 +373,194 @@ impl RequestHandler for BroadcastHandler {
     }
 }
 
+///////////////////////////////////////////////////////////////////////////////
+
+const MAX_LIMIT: i64 = 25;
+
+#[derive(Debug, Deserialize)]
+pub struct ListRequest {
+    room_id: db::room::Id,
+    label: Option<String>,
+    /// Id of the last message of the previous page. Omit to start from the latest ones.
+    before_id: Option<Uuid>,
+    limit: Option<i64>,
+}
+
+pub struct ListHandler;
+
+#[async_trait]
+impl RequestHandler for ListHandler {
+    type Payload = ListRequest;
+    const ERROR_TITLE: &'static str = "Failed to list messages";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let conn = context.get_conn().await?;
+        let (room, messages) = task::spawn_blocking({
+            let agent_id = reqp.as_agent_id().clone();
+
+            move || {
+                let room = helpers::find_room_by_id(
+                    payload.room_id,
+                    helpers::RoomTimeRequirement::Open,
+                    &conn,
+                )?;
+
+                helpers::check_room_presence(&room, &agent_id, &conn)?;
+
+                let limit = match payload.limit {
+                    Some(limit) if limit < 1 => {
+                        return Err(anyhow!("Limit must be positive"))
+                            .error(AppErrorKind::InvalidPayload);
+                    }
+                    Some(limit) => std::cmp::min(limit, MAX_LIMIT),
+                    None => MAX_LIMIT,
+                };
+
+                let maybe_before = match payload.before_id {
+                    Some(before_id) => {
+                        let message = db::message::FindQuery::new(before_id)
+                            .execute(&conn)?
+                            .filter(|message| message.room_id() == room.id())
+                            .ok_or_else(|| anyhow!("Message not found"))
+                            .error(AppErrorKind::MessageNotFound)?;
+
+                        Some(message)
+                    }
+                    None => None,
+                };
+
+                let mut query = db::message::ListQuery::new(room.id()).limit(limit);
+
+                if let Some(ref label) = payload.label {
+                    query = query.label(label);
+                }
+
+                if let Some(ref before) = maybe_before {
+                    query = query.before(before);
+                }
+
+                let messages = query.execute(&conn)?;
+                Ok::<_, AppError>((room, messages))
+            }
+        })
+        .await?;
+        helpers::add_room_logger_tags(context, &room);
+
+        context
+            .metrics()
+            .request_duration
+            .message_list
+            .observe_timestamp(context.start_timestamp());
+
+        Ok(Box::new(stream::once(helpers::build_response(
+            ResponseStatus::OK,
+            messages,
+            reqp,
+            context.start_timestamp(),
+            None,
+        ))))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteRequest {
+    room_id: db::room::Id,
+    id: Uuid,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DeleteEvent {
+    room_id: db::room::Id,
+    id: Uuid,
+}
+
+pub struct DeleteHandler;
+
+#[async_trait]
+impl RequestHandler for DeleteHandler {
+    type Payload = DeleteRequest;
+    const ERROR_TITLE: &'static str = "Failed to delete message";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let conn = context.get_conn().await?;
+        let room = task::spawn_blocking({
+            let room_id = payload.room_id;
+
+            move || {
+                helpers::find_room_by_id(room_id, helpers::RoomTimeRequirement::NotClosed, &conn)
+            }
+        })
+        .await?;
+        helpers::add_room_logger_tags(context, &room);
+
+        // Only those who may update the room are allowed to delete messages.
+        let room_id = room.id().to_string();
+        let object = vec!["rooms", &room_id];
+
+        let authz_time = context
+            .authz()
+            .authorize(room.audience(), reqp, object, "update")
+            .await?;
+        context.metrics().observe_auth(authz_time);
+
+        let conn = context.get_conn().await?;
+        task::spawn_blocking({
+            let room_id = room.id();
+            let id = payload.id;
+
+            move || {
+                db::message::FindQuery::new(id)
+                    .execute(&conn)?
+                    .filter(|message| message.room_id() == room_id)
+                    .ok_or_else(|| anyhow!("Message not found"))
+                    .error(AppErrorKind::MessageNotFound)?;
+
+                db::message::DeleteQuery::new(id).execute(&conn)?;
+                Ok::<_, AppError>(())
+            }
+        })
+        .await?;
+
+        // Respond and broadcast a tombstone to the room topic.
+        let response = helpers::build_response(
+            ResponseStatus::OK,
+            json!({}),
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        );
+
+        let notification = helpers::build_notification(
+            "message.delete",
+            &format!("rooms/{}/events", room.id()),
+            DeleteEvent {
+                room_id: room.id(),
+                id: payload.id,
+            },
+            reqp,
+            context.start_timestamp(),
+        );
+
+        context
+            .metrics()
+            .request_duration
+            .message_delete
+            .observe_timestamp(context.start_timestamp());
+
+        Ok(Box::new(stream::from_iter(vec![response, notification])))
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 pub struct UnicastResponseHandler;
@@ -178,6 +576,25 @@ impl ResponseHandler for UnicastResponseHandler {
         respp: &IncomingResponseProperties,
         corr_data: &Self::CorrelationData,
     ) -> Result {
+        // The sender has already got an error if the request has timed out
+        // or the target agent has left the room in the meantime.
+        if let Some(id) = corr_data.id {
+            let conn = context.get_conn().await?;
+            let maybe_pending_unicast = task::spawn_blocking(move || {
+                db::pending_unicast::DeleteQuery::new(id).execute(&conn)
+            })
+            .await?;
+
+            if maybe_pending_unicast.is_none() {
+                warn!(
+                    context.logger(),
+                    "Dropping response to unicast request that is not pending anymore"
+                );
+
+                return Ok(Box::new(stream::empty()));
+            }
+        }
+
         let short_term_timing = ShortTermTimingProperties::until_now(context.start_timestamp());
 
         let long_term_timing = respp
@@ -207,6 +624,103 @@ impl ResponseHandler for UnicastResponseHandler {
 
 ///////////////////////////////////////////////////////////////////////////////
 
+/// Answers pending unicast requests that have timed out or whose target agent
+/// has left the room with an error.
+///
+/// Requests to agents leaving through this instance are failed right away in
+/// `subscription::leave_room`. This is a fallback for agents which have left
+/// through another instance.
+pub(crate) async fn check_pending_unicasts<C: Context>(context: &mut C) -> MessageStream {
+    let result = async {
+        let conn = context.get_conn().await?;
+
+        task::spawn_blocking(move || {
+            let expired = db::pending_unicast::delete_expired(Utc::now(), &conn)?;
+            let left = db::pending_unicast::delete_left(&conn)?;
+            Ok::<_, AppError>((expired, left))
+        })
+        .await
+    }
+    .await;
+
+    let (expired, left) = match result {
+        Ok(pending_unicasts) => pending_unicasts,
+        Err(err) => {
+            warn!(
+                context.logger(),
+                "Failed to check pending unicasts: {:?}",
+                err.source()
+            );
+
+            return Box::new(stream::empty());
+        }
+    };
+
+    let mut messages = vec![];
+
+    for pending_unicast in expired {
+        let err = AppError::new(
+            AppErrorKind::UnicastTimeout,
+            anyhow!("The target agent hasn't responded in time"),
+        );
+
+        messages.extend(build_pending_unicast_error(context, &pending_unicast, err));
+    }
+
+    messages.extend(fail_pending_unicasts(context, &left));
+    Box::new(stream::from_iter(messages))
+}
+
+/// Answers pending unicast requests to the agent that has left the room with an error.
+pub(crate) fn fail_pending_unicasts<C: Context>(
+    context: &C,
+    pending_unicasts: &[db::pending_unicast::Object],
+) -> Vec<Box<dyn IntoPublishableMessage + Send>> {
+    pending_unicasts
+        .iter()
+        .filter_map(|pending_unicast| {
+            let err = AppError::new(
+                AppErrorKind::AgentNotEnteredTheRoom,
+                anyhow!("The target agent has left the room"),
+            );
+
+            build_pending_unicast_error(context, pending_unicast, err)
+        })
+        .collect()
+}
+
+fn build_pending_unicast_error<C: Context>(
+    context: &C,
+    pending_unicast: &db::pending_unicast::Object,
+    err: AppError,
+) -> Option<Box<dyn IntoPublishableMessage + Send>> {
+    let reqp = match pending_unicast.reqp() {
+        Ok(reqp) => reqp,
+        Err(parse_err) => {
+            warn!(
+                context.logger(),
+                "Failed to parse pending unicast request properties: {}", parse_err
+            );
+
+            return None;
+        }
+    };
+
+    let response = build_error_response(
+        err.status(),
+        err.kind(),
+        err.title(),
+        &err.source().to_string(),
+        &reqp,
+        context.start_timestamp(),
+    );
+
+    context.metrics().observe_app_result(&Err(err));
+    Some(response)
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
 #[cfg(test)]
 mod test {
     mod unicast {
@@ -266,50 +780,33 @@ mod test {
         }
 
         #[async_std::test]
-        async fn unicast_message_to_missing_room() {
-            let local_deps = LocalDeps::new();
-            let postgres = local_deps.run_postgres();
-            let db = TestDb::with_local_postgres(&postgres);
-
-            let mut context = TestContext::new(db, TestAuthz::new());
-            let sender = TestAgent::new("web", "sender", USR_AUDIENCE);
-            let receiver = TestAgent::new("web", "receiver", USR_AUDIENCE);
-
-            let payload = UnicastRequest {
-                agent_id: receiver.agent_id().to_owned(),
-                room_id: db::room::Id::random(),
-                data: json!({ "key": "value" }),
-            };
-
-            let err = handle_request::<UnicastHandler>(&mut context, &sender, payload)
-                .await
-                .expect_err("Unexpected success on unicast message sending");
+        async fn unicast_response_on_another_instance() {
+            use diesel::prelude::*;
 
-            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
-            assert_eq!(err.kind(), "room_not_found");
-        }
+            use crate::schema::pending_unicast;
 
-        #[async_std::test]
-        async fn unicast_message_when_sender_is_not_in_the_room() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
             let sender = TestAgent::new("web", "sender", USR_AUDIENCE);
             let receiver = TestAgent::new("web", "receiver", USR_AUDIENCE);
 
-            // Insert room with online receiver only.
+            // Insert room with online both sender and receiver.
             let room = db
                 .connection_pool()
                 .get()
                 .map(|conn| {
                     let room = shared_helpers::insert_room(&conn);
+                    shared_helpers::insert_agent(&conn, sender.agent_id(), room.id());
                     shared_helpers::insert_agent(&conn, receiver.agent_id(), room.id());
                     room
                 })
                 .expect("Failed to insert room");
 
-            // Make message.unicast request.
-            let mut context = TestContext::new(db, TestAuthz::new());
+            // Make message.unicast request on one instance. The request expires immediately
+            // so the instance would answer with timeout error if it was still pending.
+            let mut sender_context = TestContext::new(db.clone(), TestAuthz::new());
+            sender_context.config_mut().unicast.timeout = std::time::Duration::from_secs(0);
 
             let payload = UnicastRequest {
                 agent_id: receiver.agent_id().to_owned(),
@@ -317,52 +814,574 @@ mod test {
                 data: json!({ "key": "value" }),
             };
 
-            let err = handle_request::<UnicastHandler>(&mut context, &sender, payload)
+            handle_request::<UnicastHandler>(&mut sender_context, &sender, payload)
                 .await
-                .expect_err("Unexpected success on unicast message sending");
+                .expect("Unicast message sending failed");
 
-            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
-            assert_eq!(err.kind(), "agent_not_entered_the_room");
+            let id = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                pending_unicast::table
+                    .select(pending_unicast::id)
+                    .get_result::<Uuid>(&conn)
+                    .expect("Failed to find pending unicast")
+            };
+
+            // The receiver's response comes to another instance.
+            let mut context = TestContext::new(db, TestAuthz::new());
+            let reqp = build_reqp(sender.agent_id(), "message.unicast");
+            let corr_data = CorrelationDataPayload::new(id, reqp);
+
+            let messages = handle_response::<UnicastResponseHandler>(
+                &mut context,
+                &receiver,
+                json!({ "answer": 42 }),
+                &corr_data,
+            )
+            .await
+            .expect("Unicast response handling failed");
+
+            // Assert the response is forwarded to the sender.
+            let (payload, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(payload, json!({ "answer": 42 }));
+
+            // Assert the sender's instance doesn't answer with timeout error later.
+            let messages = parse_messages(check_pending_unicasts(&mut sender_context).await).await;
+            assert!(messages.is_empty());
+
+            // Assert a duplicate response is dropped.
+            let messages = handle_response::<UnicastResponseHandler>(
+                &mut sender_context,
+                &receiver,
+                json!({ "answer": 42 }),
+                &corr_data,
+            )
+            .await
+            .expect("Unicast response handling failed");
+
+            assert!(messages.is_empty());
         }
 
         #[async_std::test]
-        async fn unicast_message_when_receiver_is_not_in_the_room() {
+        async fn unicast_message_timeout() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
             let sender = TestAgent::new("web", "sender", USR_AUDIENCE);
             let receiver = TestAgent::new("web", "receiver", USR_AUDIENCE);
 
-            // Insert room with online sender only.
+            // Insert room with online both sender and receiver.
             let room = db
                 .connection_pool()
                 .get()
                 .map(|conn| {
                     let room = shared_helpers::insert_room(&conn);
                     shared_helpers::insert_agent(&conn, sender.agent_id(), room.id());
+                    shared_helpers::insert_agent(&conn, receiver.agent_id(), room.id());
+                    room
+                })
+                .expect("Failed to insert room");
+
+            // Make message.unicast request that expires immediately.
+            let mut context = TestContext::new(db, TestAuthz::new());
+            context.config_mut().unicast.timeout = std::time::Duration::from_secs(0);
+
+            let payload = UnicastRequest {
+                agent_id: receiver.agent_id().to_owned(),
+                room_id: room.id(),
+                data: json!({ "key": "value" }),
+            };
+
+            handle_request::<UnicastHandler>(&mut context, &sender, payload)
+                .await
+                .expect("Unicast message sending failed");
+
+            // Assert the sender gets timeout error.
+            let messages = parse_messages(check_pending_unicasts(&mut context).await).await;
+            let (_, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::GATEWAY_TIMEOUT);
+
+            // Assert the request is not pending anymore.
+            let messages = parse_messages(check_pending_unicasts(&mut context).await).await;
+            assert!(messages.is_empty());
+        }
+
+        #[async_std::test]
+        async fn unicast_message_target_left() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let sender = TestAgent::new("web", "sender", USR_AUDIENCE);
+            let receiver = TestAgent::new("web", "receiver", USR_AUDIENCE);
+
+            // Insert room with online both sender and receiver.
+            let room = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let room = shared_helpers::insert_room(&conn);
+                    shared_helpers::insert_agent(&conn, sender.agent_id(), room.id());
+                    shared_helpers::insert_agent(&conn, receiver.agent_id(), room.id());
+                    room
+                })
+                .expect("Failed to insert room");
+
+            // Make message.unicast request.
+            let mut context = TestContext::new(db.clone(), TestAuthz::new());
+
+            let payload = UnicastRequest {
+                agent_id: receiver.agent_id().to_owned(),
+                room_id: room.id(),
+                data: json!({ "key": "value" }),
+            };
+
+            handle_request::<UnicastHandler>(&mut context, &sender, payload)
+                .await
+                .expect("Unicast message sending failed");
+
+            // The receiver leaves before responding.
+            let conn = db
+                .connection_pool()
+                .get()
+                .expect("Failed to get DB connection");
+
+            db::agent::DeleteQuery::new()
+                .agent_id(receiver.agent_id())
+                .room_id(room.id())
+                .execute(&conn)
+                .expect("Failed to delete agent");
+
+            // Assert the sender gets an error.
+            let messages = parse_messages(check_pending_unicasts(&mut context).await).await;
+            let (_, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::NOT_FOUND);
+        }
+
+        #[async_std::test]
+        async fn unicast_message_target_unsubscribed() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let sender = TestAgent::new("web", "sender", USR_AUDIENCE);
+            let receiver = TestAgent::new("web", "receiver", USR_AUDIENCE);
+
+            // Insert room with online both sender and receiver.
+            let room = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let room = shared_helpers::insert_room(&conn);
+                    shared_helpers::insert_agent(&conn, sender.agent_id(), room.id());
+                    shared_helpers::insert_agent(&conn, receiver.agent_id(), room.id());
+                    room
+                })
+                .expect("Failed to insert room");
+
+            // Make message.unicast request.
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = UnicastRequest {
+                agent_id: receiver.agent_id().to_owned(),
+                room_id: room.id(),
+                data: json!({ "key": "value" }),
+            };
+
+            handle_request::<UnicastHandler>(&mut context, &sender, payload)
+                .await
+                .expect("Unicast message sending failed");
+
+            // The receiver's subscription gets deleted before responding.
+            let leave_reason = db::agent_session::LeaveReason::SubscriptionDelete;
+
+            let messages = crate::app::endpoint::subscription::leave_room(
+                &mut context,
+                receiver.agent_id(),
+                room.id(),
+                leave_reason,
+            )
+            .await
+            .expect("Failed to leave room")
+            .expect("The receiver is not in the room");
+
+            // Assert the sender gets an error right away.
+            let messages = parse_messages(Box::new(stream::from_iter(messages))).await;
+            let (_, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::NOT_FOUND);
+
+            // Assert the request is not pending anymore.
+            let messages = parse_messages(check_pending_unicasts(&mut context).await).await;
+            assert!(messages.is_empty());
+        }
+
+        #[async_std::test]
+        async fn unicast_message_from_listener_to_non_moderator() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let sender = TestAgent::new("web", "sender", USR_AUDIENCE);
+            let receiver = TestAgent::new("web", "receiver", USR_AUDIENCE);
+
+            // Insert room with online both sender and receiver. The sender is a listener.
+            let room = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let room = shared_helpers::insert_room(&conn);
+                    shared_helpers::insert_agent(&conn, sender.agent_id(), room.id());
+                    shared_helpers::insert_agent(&conn, receiver.agent_id(), room.id());
+
+                    db::room_role::UpsertQuery::new(
+                        room.id(),
+                        sender.account_id(),
+                        RoomRole::Listener,
+                    )
+                    .execute(&conn)
+                    .expect("Failed to insert room role");
+
+                    room
+                })
+                .expect("Failed to insert room");
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = UnicastRequest {
+                agent_id: receiver.agent_id().to_owned(),
+                room_id: room.id(),
+                data: json!({ "key": "value" }),
+            };
+
+            let err = handle_request::<UnicastHandler>(&mut context, &sender, payload)
+                .await
+                .expect_err("Unexpected success on unicast message sending");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
+        }
+
+        #[async_std::test]
+        async fn unicast_message_to_missing_room() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+            let sender = TestAgent::new("web", "sender", USR_AUDIENCE);
+            let receiver = TestAgent::new("web", "receiver", USR_AUDIENCE);
+
+            let payload = UnicastRequest {
+                agent_id: receiver.agent_id().to_owned(),
+                room_id: db::room::Id::random(),
+                data: json!({ "key": "value" }),
+            };
+
+            let err = handle_request::<UnicastHandler>(&mut context, &sender, payload)
+                .await
+                .expect_err("Unexpected success on unicast message sending");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "room_not_found");
+        }
+
+        #[async_std::test]
+        async fn unicast_message_when_sender_is_not_in_the_room() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let sender = TestAgent::new("web", "sender", USR_AUDIENCE);
+            let receiver = TestAgent::new("web", "receiver", USR_AUDIENCE);
+
+            // Insert room with online receiver only.
+            let room = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let room = shared_helpers::insert_room(&conn);
+                    shared_helpers::insert_agent(&conn, receiver.agent_id(), room.id());
+                    room
+                })
+                .expect("Failed to insert room");
+
+            // Make message.unicast request.
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = UnicastRequest {
+                agent_id: receiver.agent_id().to_owned(),
+                room_id: room.id(),
+                data: json!({ "key": "value" }),
+            };
+
+            let err = handle_request::<UnicastHandler>(&mut context, &sender, payload)
+                .await
+                .expect_err("Unexpected success on unicast message sending");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "agent_not_entered_the_room");
+        }
+
+        #[async_std::test]
+        async fn unicast_message_when_receiver_is_not_in_the_room() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let sender = TestAgent::new("web", "sender", USR_AUDIENCE);
+            let receiver = TestAgent::new("web", "receiver", USR_AUDIENCE);
+
+            // Insert room with online sender only.
+            let room = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let room = shared_helpers::insert_room(&conn);
+                    shared_helpers::insert_agent(&conn, sender.agent_id(), room.id());
+                    room
+                })
+                .expect("Failed to insert room");
+
+            // Make message.unicast request.
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = UnicastRequest {
+                agent_id: receiver.agent_id().to_owned(),
+                room_id: room.id(),
+                data: json!({ "key": "value" }),
+            };
+
+            let err = handle_request::<UnicastHandler>(&mut context, &sender, payload)
+                .await
+                .expect_err("Unexpected success on unicast message sending");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "agent_not_entered_the_room");
+        }
+    }
+
+    mod multicast {
+        use crate::{
+            app::API_VERSION,
+            test_helpers::{find_event_by_predicate, prelude::*, test_deps::LocalDeps},
+        };
+
+        use super::super::*;
+
+        fn inbox_topic(agent_id: &AgentId) -> String {
+            format!(
+                "agents/{}/api/{}/in/conference.{}",
+                agent_id, API_VERSION, SVC_AUDIENCE
+            )
+        }
+
+        #[async_std::test]
+        async fn multicast_message_to_agents() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let sender = TestAgent::new("web", "sender", USR_AUDIENCE);
+            let receiver1 = TestAgent::new("web", "receiver1", USR_AUDIENCE);
+            let receiver2 = TestAgent::new("web", "receiver2", USR_AUDIENCE);
+            let bystander = TestAgent::new("web", "bystander", USR_AUDIENCE);
+
+            // Insert room with online agents.
+            let room = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let room = shared_helpers::insert_room(&conn);
+
+                    for agent in &[&sender, &receiver1, &receiver2, &bystander] {
+                        shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                    }
+
+                    room
+                })
+                .expect("Failed to insert room");
+
+            // Make message.multicast request.
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = MulticastRequest {
+                room_id: room.id(),
+                agent_ids: Some(vec![
+                    receiver1.agent_id().to_owned(),
+                    receiver2.agent_id().to_owned(),
+                ]),
+                role: None,
+                data: json!({ "key": "value" }),
+            };
+
+            let messages = handle_request::<MulticastHandler>(&mut context, &sender, payload)
+                .await
+                .expect("Multicast message sending failed");
+
+            // Assert response.
+            let (_, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+
+            // Assert events to the receivers only.
+            for agent in &[&receiver1, &receiver2] {
+                let (payload, evp, _) =
+                    find_event_by_predicate::<JsonValue, _>(messages.as_slice(), |_, _, topic| {
+                        topic == inbox_topic(agent.agent_id())
+                    })
+                    .expect("Multicast event not found");
+
+                assert_eq!(evp.label(), "message.multicast");
+                assert_eq!(payload, json!({ "key": "value" }));
+            }
+
+            assert_eq!(messages.len(), 3);
+        }
+
+        #[async_std::test]
+        async fn multicast_message_to_role() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let sender = TestAgent::new("web", "sender", USR_AUDIENCE);
+            let moderator = TestAgent::new("web", "moderator", USR_AUDIENCE);
+            let speaker = TestAgent::new("web", "speaker", USR_AUDIENCE);
+
+            // Insert room with online agents having roles.
+            let room = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let room = shared_helpers::insert_room(&conn);
+
+                    for agent in &[&sender, &moderator, &speaker] {
+                        shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                    }
+
+                    let roles = [
+                        (&sender, RoomRole::Moderator),
+                        (&moderator, RoomRole::Moderator),
+                        (&speaker, RoomRole::Speaker),
+                    ];
+
+                    for (agent, role) in roles.iter() {
+                        db::room_role::UpsertQuery::new(
+                            room.id(),
+                            agent.agent_id().as_account_id(),
+                            *role,
+                        )
+                        .execute(&conn)
+                        .expect("Failed to set room role");
+                    }
+
                     room
                 })
                 .expect("Failed to insert room");
 
-            // Make message.unicast request.
+            // Make message.multicast request to moderators.
             let mut context = TestContext::new(db, TestAuthz::new());
 
-            let payload = UnicastRequest {
-                agent_id: receiver.agent_id().to_owned(),
+            let payload = MulticastRequest {
                 room_id: room.id(),
+                agent_ids: None,
+                role: Some(RoomRole::Moderator),
                 data: json!({ "key": "value" }),
             };
 
-            let err = handle_request::<UnicastHandler>(&mut context, &sender, payload)
+            let messages = handle_request::<MulticastHandler>(&mut context, &sender, payload)
                 .await
-                .expect_err("Unexpected success on unicast message sending");
+                .expect("Multicast message sending failed");
+
+            // Assert the response lists the only moderator except the sender.
+            let (payload, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(payload, json!({ "agent_ids": [moderator.agent_id()] }));
+
+            // Assert the event to the moderator.
+            let (_, _, topic) = find_event::<JsonValue>(messages.as_slice());
+            assert_eq!(topic, inbox_topic(moderator.agent_id()));
+            assert_eq!(messages.len(), 2);
+        }
+
+        #[async_std::test]
+        async fn multicast_message_to_missing_agent() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let sender = TestAgent::new("web", "sender", USR_AUDIENCE);
+            let receiver1 = TestAgent::new("web", "receiver1", USR_AUDIENCE);
+            let receiver2 = TestAgent::new("web", "receiver2", USR_AUDIENCE);
+
+            // Insert room with online sender and only one of the receivers.
+            let room = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let room = shared_helpers::insert_room(&conn);
+                    shared_helpers::insert_agent(&conn, sender.agent_id(), room.id());
+                    shared_helpers::insert_agent(&conn, receiver1.agent_id(), room.id());
+                    room
+                })
+                .expect("Failed to insert room");
+
+            // Make message.multicast request.
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = MulticastRequest {
+                room_id: room.id(),
+                agent_ids: Some(vec![
+                    receiver1.agent_id().to_owned(),
+                    receiver2.agent_id().to_owned(),
+                ]),
+                role: None,
+                data: json!({ "key": "value" }),
+            };
+
+            let err = handle_request::<MulticastHandler>(&mut context, &sender, payload)
+                .await
+                .expect_err("Unexpected success on multicast message sending");
 
             assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
             assert_eq!(err.kind(), "agent_not_entered_the_room");
         }
+
+        #[async_std::test]
+        async fn multicast_message_without_targets() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let sender = TestAgent::new("web", "sender", USR_AUDIENCE);
+
+            // Insert room with online sender.
+            let room = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let room = shared_helpers::insert_room(&conn);
+                    shared_helpers::insert_agent(&conn, sender.agent_id(), room.id());
+                    room
+                })
+                .expect("Failed to insert room");
+
+            // Make message.multicast request with neither agent ids nor role.
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = MulticastRequest {
+                room_id: room.id(),
+                agent_ids: None,
+                role: None,
+                data: json!({ "key": "value" }),
+            };
+
+            let err = handle_request::<MulticastHandler>(&mut context, &sender, payload)
+                .await
+                .expect_err("Unexpected success on multicast message sending");
+
+            assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+            assert_eq!(err.kind(), "invalid_payload");
+        }
     }
 
     mod broadcast {
+        use std::ops::Bound;
+
         use crate::{
             app::API_VERSION,
             test_helpers::{prelude::*, test_deps::LocalDeps},
@@ -420,6 +1439,62 @@ mod test {
             assert_eq!(payload, json!({"key": "value"}));
         }
 
+        #[async_std::test]
+        async fn broadcast_persistent_message() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let sender = TestAgent::new("web", "sender", USR_AUDIENCE);
+
+            // Insert room keeping chat history with online agent.
+            let room = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let room = factory::Room::new()
+                        .audience(USR_AUDIENCE)
+                        .time((Bound::Included(Utc::now()), Bound::Unbounded))
+                        .persistent_message_labels(&["chat"])
+                        .insert(&conn);
+
+                    shared_helpers::insert_agent(&conn, sender.agent_id(), room.id());
+                    room
+                })
+                .expect("Failed to insert room");
+
+            // Make message.broadcast request.
+            let mut context = TestContext::new(db.clone(), TestAuthz::new());
+
+            let payload = BroadcastRequest {
+                room_id: room.id(),
+                data: json!({ "text": "hello" }),
+                label: Some(String::from("chat")),
+            };
+
+            let messages = handle_request::<BroadcastHandler>(&mut context, &sender, payload)
+                .await
+                .expect("Broadcast message sending failed");
+
+            // Assert the event contains the stored message.
+            let (message, _evp, _topic) = find_event::<db::message::Object>(messages.as_slice());
+            assert_eq!(message.room_id(), room.id());
+            assert_eq!(message.label(), "chat");
+            assert_eq!(message.data(), &json!({ "text": "hello" }));
+
+            // Assert the message is in the DB.
+            let conn = db
+                .connection_pool()
+                .get()
+                .expect("Failed to get DB connection");
+
+            let db_messages = db::message::ListQuery::new(room.id())
+                .execute(&conn)
+                .expect("Failed to list messages");
+
+            assert_eq!(db_messages.len(), 1);
+            assert_eq!(db_messages[0].id(), message.id());
+        }
+
         #[async_std::test]
         async fn broadcast_message_to_missing_room() {
             let local_deps = LocalDeps::new();
@@ -473,4 +1548,308 @@ mod test {
             assert_eq!(err.kind(), "agent_not_entered_the_room");
         }
     }
+
+    mod list {
+        use diesel::Connection;
+
+        use crate::test_helpers::{prelude::*, test_deps::LocalDeps};
+
+        use super::super::*;
+
+        #[async_std::test]
+        async fn list_messages() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            // Insert room with online agent and a couple of stored messages.
+            let room = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let room = shared_helpers::insert_room(&conn);
+                    shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+
+                    for text in &["first", "second", "third"] {
+                        let data = json!({ "text": text });
+
+                        db::message::InsertQuery::new(room.id(), agent.agent_id(), "chat", &data)
+                            .execute(&conn)
+                            .expect("Failed to insert message");
+                    }
+
+                    room
+                })
+                .expect("Failed to insert room");
+
+            // Make message.list request.
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = ListRequest {
+                room_id: room.id(),
+                label: Some(String::from("chat")),
+                before_id: None,
+                limit: Some(2),
+            };
+
+            let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Messages listing failed");
+
+            // Assert the latest messages come first.
+            let (messages, respp, _) =
+                find_response::<Vec<db::message::Object>>(messages.as_slice());
+
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(messages.len(), 2);
+            assert_eq!(messages[0].data(), &json!({ "text": "third" }));
+            assert_eq!(messages[1].data(), &json!({ "text": "second" }));
+        }
+
+        #[async_std::test]
+        async fn list_messages_next_page() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            // Insert room with online agent and messages created at the same time.
+            let (room, stored_messages) = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let room = shared_helpers::insert_room(&conn);
+                    shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+
+                    let stored_messages = conn
+                        .transaction::<_, diesel::result::Error, _>(|| {
+                            ["first", "second", "third"]
+                                .iter()
+                                .map(|text| {
+                                    let data = json!({ "text": text });
+
+                                    db::message::InsertQuery::new(
+                                        room.id(),
+                                        agent.agent_id(),
+                                        "chat",
+                                        &data,
+                                    )
+                                    .execute(&conn)
+                                })
+                                .collect::<std::result::Result<Vec<_>, _>>()
+                        })
+                        .expect("Failed to insert messages");
+
+                    (room, stored_messages)
+                })
+                .expect("Failed to insert room");
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            // Fetch the first page.
+            let payload = ListRequest {
+                room_id: room.id(),
+                label: None,
+                before_id: None,
+                limit: Some(2),
+            };
+
+            let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Messages listing failed");
+
+            let (first_page, _, _) = find_response::<Vec<db::message::Object>>(messages.as_slice());
+            assert_eq!(first_page.len(), 2);
+
+            // Fetch the next page.
+            let payload = ListRequest {
+                room_id: room.id(),
+                label: None,
+                before_id: Some(first_page[1].id()),
+                limit: Some(2),
+            };
+
+            let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Messages listing failed");
+
+            let (next_page, _, _) = find_response::<Vec<db::message::Object>>(messages.as_slice());
+            assert_eq!(next_page.len(), 1);
+
+            // Assert each message has been listed exactly once.
+            let mut listed_ids = first_page
+                .iter()
+                .chain(next_page.iter())
+                .map(|message| message.id())
+                .collect::<Vec<_>>();
+
+            let mut stored_ids = stored_messages
+                .iter()
+                .map(|message| message.id())
+                .collect::<Vec<_>>();
+
+            listed_ids.sort();
+            stored_ids.sort();
+            assert_eq!(listed_ids, stored_ids);
+        }
+
+        #[async_std::test]
+        async fn list_messages_invalid_limit() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let room = shared_helpers::insert_room(&conn);
+                    shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                    room
+                })
+                .expect("Failed to insert room");
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = ListRequest {
+                room_id: room.id(),
+                label: None,
+                before_id: None,
+                limit: Some(0),
+            };
+
+            let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on messages listing");
+
+            assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+            assert_eq!(err.kind(), "invalid_payload");
+        }
+
+        #[async_std::test]
+        async fn list_messages_not_entered() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = db
+                .connection_pool()
+                .get()
+                .map(|conn| shared_helpers::insert_room(&conn))
+                .expect("Failed to insert room");
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = ListRequest {
+                room_id: room.id(),
+                label: None,
+                before_id: None,
+                limit: None,
+            };
+
+            let err = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on messages listing");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "agent_not_entered_the_room");
+        }
+    }
+
+    mod delete {
+        use crate::test_helpers::{prelude::*, test_deps::LocalDeps};
+
+        use super::super::*;
+
+        #[async_std::test]
+        async fn delete_message() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let moderator = TestAgent::new("web", "moderator", USR_AUDIENCE);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            // Insert room with a stored message.
+            let (room, message) = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    let room = shared_helpers::insert_room(&conn);
+                    let data = json!({ "text": "spam" });
+
+                    let message =
+                        db::message::InsertQuery::new(room.id(), agent.agent_id(), "chat", &data)
+                            .execute(&conn)
+                            .expect("Failed to insert message");
+
+                    (room, message)
+                })
+                .expect("Failed to insert room");
+
+            // Allow moderator to update the room.
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            authz.allow(moderator.account_id(), vec!["rooms", &room_id], "update");
+
+            // Make message.delete request.
+            let mut context = TestContext::new(db.clone(), authz);
+
+            let payload = DeleteRequest {
+                room_id: room.id(),
+                id: message.id(),
+            };
+
+            let messages = handle_request::<DeleteHandler>(&mut context, &moderator, payload)
+                .await
+                .expect("Message deletion failed");
+
+            // Assert tombstone event.
+            let (event, evp, _) = find_event::<DeleteEvent>(messages.as_slice());
+            assert_eq!(evp.label(), "message.delete");
+            assert_eq!(event.id, message.id());
+
+            // Assert the message is gone from the DB.
+            let conn = db
+                .connection_pool()
+                .get()
+                .expect("Failed to get DB connection");
+
+            let maybe_message = db::message::FindQuery::new(message.id())
+                .execute(&conn)
+                .expect("Failed to find message");
+
+            assert!(maybe_message.is_none());
+        }
+
+        #[async_std::test]
+        async fn delete_message_not_authorized() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = db
+                .connection_pool()
+                .get()
+                .map(|conn| shared_helpers::insert_room(&conn))
+                .expect("Failed to insert room");
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = DeleteRequest {
+                room_id: room.id(),
+                id: Uuid::new_v4(),
+            };
+
+            let err = handle_request::<DeleteHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on message deletion");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
+        }
+    }
 }
@@ -4,17 +4,28 @@ pub(self) use crate::app::message_handler::MessageStream;
 use crate::{
     app::{
         context::Context,
-        error::Error as AppError,
-        message_handler::{EventEnvelopeHandler, RequestEnvelopeHandler, ResponseEnvelopeHandler},
+        error::{Error as AppError, ErrorExt, ErrorKind as AppErrorKind},
+        handle_id::HandleId,
+        message_handler::{
+            build_error_response, EventEnvelopeHandler, RequestEnvelopeHandler,
+            ResponseEnvelopeHandler,
+        },
+        rate_limiter::Exceeded,
     },
     backend::janus,
+    db,
 };
+use anyhow::anyhow;
+use async_std::{stream, task};
 use async_trait::async_trait;
 use serde::de::DeserializeOwned;
 use slog::warn;
-use svc_agent::mqtt::{
-    IncomingEvent, IncomingEventProperties, IncomingRequest, IncomingRequestProperties,
-    IncomingResponse, IncomingResponseProperties,
+use svc_agent::{
+    mqtt::{
+        IncomingEvent, IncomingEventProperties, IncomingRequest, IncomingRequestProperties,
+        IncomingResponse, IncomingResponseProperties,
+    },
+    AgentId,
 };
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -42,7 +53,10 @@ macro_rules! request_routes {
         ) -> Option<MessageStream> {
             match request.properties().method() {
                 $(
-                    $m => Some(<$h>::handle_envelope::<C>(context, request).await),
+                    $m => Some(match check_rate_limit(context, request).await {
+                        Ok(()) => <$h>::handle_envelope::<C>(context, request).await,
+                        Err(message_stream) => message_stream,
+                    }),
                 )*
                 _ => None,
             }
@@ -52,27 +66,204 @@ macro_rules! request_routes {
 
 // Request routes configuration: method => RequestHandler
 request_routes!(
+    "agent.attendance" => agent::AttendanceHandler,
+    "agent.ban" => agent::BanHandler,
+    "agent.grant_speak" => agent::GrantSpeakHandler,
+    "agent.kick" => agent::KickHandler,
     "agent.list" => agent::ListHandler,
+    "agent.lower_hand" => agent::LowerHandHandler,
+    "agent.raise_hand" => agent::RaiseHandHandler,
+    "agent.set_role" => agent::SetRoleHandler,
     "agent_reader_config.read" => agent_reader_config::ReadHandler,
     "agent_reader_config.update" => agent_reader_config::UpdateHandler,
     "agent_writer_config.read" => agent_writer_config::ReadHandler,
     "agent_writer_config.update" => agent_writer_config::UpdateHandler,
+    "backend.drain" => backend::DrainHandler,
+    "backend.list" => backend::ListHandler,
+    "backend.read" => backend::ReadHandler,
     "message.broadcast" => message::BroadcastHandler,
+    "message.delete" => message::DeleteHandler,
+    "message.list" => message::ListHandler,
+    "message.multicast" => message::MulticastHandler,
     "message.unicast" => message::UnicastHandler,
+    "room.admit" => room::AdmitHandler,
+    "room.apply_defaults" => room::ApplyDefaultsHandler,
+    "room.close_breakouts" => room::CloseBreakoutsHandler,
     "room.create" => room::CreateHandler,
+    "room.create_breakouts" => room::CreateBreakoutsHandler,
     "room.enter" => room::EnterHandler,
     "room.leave" => room::LeaveHandler,
     "room.read" => room::ReadHandler,
     "room.update" => room::UpdateHandler,
     "rtc.connect" => rtc::ConnectHandler,
     "rtc.create" => rtc::CreateHandler,
+    "rtc.delete" => rtc::DeleteHandler,
     "rtc.list" => rtc::ListHandler,
     "rtc.read" => rtc::ReadHandler,
     "rtc_signal.create" => rtc_signal::CreateHandler,
     "rtc_stream.list" => rtc_stream::ListHandler,
+    "system.sweep" => system::SweepHandler,
     "system.vacuum" => system::VacuumHandler
 );
 
+macro_rules! http_request_routes {
+    ($($m: pat => $h: ty),*) => {
+        /// Calls the request handler for the method available through the HTTP API.
+        /// Returns `None` for methods that are MQTT-only.
+        pub async fn route_http_request<C: Context>(
+            context: &mut C,
+            payload: &str,
+            reqp: &IncomingRequestProperties,
+        ) -> Option<Result> {
+            match reqp.method() {
+                $(
+                    $m => Some(handle_http_request::<$h, C>(context, payload, reqp).await),
+                )*
+                _ => None,
+            }
+        }
+    }
+}
+
+// HTTP API routes configuration: method => RequestHandler
+http_request_routes!(
+    "agent.list" => agent::ListHandler,
+    "backend.drain" => backend::DrainHandler,
+    "backend.list" => backend::ListHandler,
+    "backend.read" => backend::ReadHandler,
+    "room.read" => room::ReadHandler,
+    "rtc.list" => rtc::ListHandler,
+    "rtc.read" => rtc::ReadHandler,
+    "rtc_stream.list" => rtc_stream::ListHandler,
+    "system.vacuum" => system::VacuumHandler
+);
+
+async fn handle_http_request<H: RequestHandler, C: Context>(
+    context: &mut C,
+    payload: &str,
+    reqp: &IncomingRequestProperties,
+) -> Result {
+    let payload = serde_json::from_str::<H::Payload>(payload)
+        .map_err(|err| anyhow!("Failed to parse payload: {}", err))
+        .error(AppErrorKind::InvalidPayload)?;
+
+    let app_result = H::handle(context, payload, reqp).await;
+    context.metrics().observe_app_result(&app_result);
+    app_result
+}
+
+#[derive(Debug, Deserialize)]
+struct RateLimitPayload {
+    room_id: Option<db::room::Id>,
+    handle_id: Option<HandleId>,
+}
+
+async fn check_rate_limit<C: Context>(
+    context: &mut C,
+    request: &IncomingRequest<String>,
+) -> StdResult<(), MessageStream> {
+    let reqp = request.properties();
+
+    let has_room_limit = match context.config().rate_limit.get(reqp.method()) {
+        Some(method_config) => method_config.room.is_some(),
+        None => return Ok(()),
+    };
+
+    let room_id = if has_room_limit {
+        find_rate_limit_room_id(context, reqp.as_agent_id(), request.payload()).await
+    } else {
+        None
+    };
+
+    let result = context.rate_limiter().check(
+        &context.config().rate_limit,
+        reqp.method(),
+        reqp.as_agent_id(),
+        room_id,
+        context.start_timestamp(),
+    );
+
+    let exceeded = match result {
+        Ok(()) => return Ok(()),
+        Err(Exceeded::Agent) => "agent",
+        Err(Exceeded::Room) => "room",
+    };
+
+    let app_error = AppError::new(
+        AppErrorKind::TooManyRequests,
+        anyhow!("Rate limit for the {} exceeded", exceeded),
+    );
+
+    warn!(context.logger(), "Request rejected: {}", app_error.source());
+
+    let response = build_error_response(
+        app_error.status(),
+        app_error.kind(),
+        app_error.title(),
+        &app_error.source().to_string(),
+        reqp,
+        context.start_timestamp(),
+    );
+
+    context.metrics().observe_app_result(&Err(app_error));
+    Err(Box::new(stream::once(response)))
+}
+
+// Methods are being keyed by room either with `room_id` or with `handle_id` of the room's rtc.
+// The room is taken into account only if the sender is in it so that outsiders
+// couldn't exhaust the room's bucket.
+async fn find_rate_limit_room_id<C: Context>(
+    context: &mut C,
+    agent_id: &AgentId,
+    payload: &str,
+) -> Option<db::room::Id> {
+    let payload = serde_json::from_str::<RateLimitPayload>(payload).ok()?;
+
+    if payload.room_id.is_none() && payload.handle_id.is_none() {
+        return None;
+    }
+
+    let agent_id = agent_id.to_owned();
+    let conn = context.get_conn().await.ok()?;
+
+    let maybe_room_id = task::spawn_blocking(move || {
+        let room_id = match (payload.room_id, payload.handle_id) {
+            (Some(room_id), _) => room_id,
+            (None, Some(handle_id)) => {
+                match db::rtc::FindQuery::new()
+                    .id(handle_id.rtc_id())
+                    .execute(&conn)?
+                {
+                    Some(rtc) => rtc.room_id(),
+                    None => return Ok(None),
+                }
+            }
+            (None, None) => return Ok(None),
+        };
+
+        let agents = db::agent::ListQuery::new()
+            .agent_id(&agent_id)
+            .room_id(room_id)
+            .limit(1)
+            .execute(&conn)?;
+
+        Ok::<_, diesel::result::Error>(agents.first().map(|_| room_id))
+    })
+    .await;
+
+    match maybe_room_id {
+        Ok(maybe_room_id) => maybe_room_id,
+        Err(err) => {
+            warn!(
+                context.logger(),
+                "Failed to find room for the rate limit: {}", err
+            );
+
+            None
+        }
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 
 use serde::{Deserialize, Serialize};
@@ -81,6 +272,8 @@ use serde::{Deserialize, Serialize};
 pub enum CorrelationData {
     SubscriptionCreate(subscription::CorrelationDataPayload),
     SubscriptionDelete(subscription::CorrelationDataPayload),
+    SubscriptionKick(subscription::CorrelationDataPayload),
+    SubscriptionSweep(subscription::CorrelationDataPayload),
     MessageUnicast(message::CorrelationDataPayload),
 }
 
@@ -128,6 +321,8 @@ macro_rules! response_routes {
 response_routes!(
     SubscriptionCreate => subscription::CreateResponseHandler,
     SubscriptionDelete => subscription::DeleteResponseHandler,
+    SubscriptionKick => subscription::KickResponseHandler,
+    SubscriptionSweep => subscription::SweepResponseHandler,
     MessageUnicast => message::UnicastResponseHandler
 );
 
@@ -196,6 +391,7 @@ event_routes!(
 mod agent;
 mod agent_reader_config;
 mod agent_writer_config;
+mod backend;
 pub mod helpers;
 mod message;
 mod room;
@@ -205,6 +401,8 @@ pub mod rtc_stream;
 mod subscription;
 pub mod system;
 
+pub(crate) use message::check_pending_unicasts;
+
 pub(self) mod prelude {
     pub(super) use super::{helpers, EventHandler, RequestHandler, ResponseHandler, Result};
     pub(super) use crate::app::{
@@ -212,3 +410,69 @@ pub(self) mod prelude {
         error::{Error as AppError, ErrorExt, ErrorKind as AppErrorKind},
     };
 }
+
+#[cfg(test)]
+mod test {
+    mod find_rate_limit_room_id {
+        use serde_json::json;
+
+        use crate::{
+            app::handle_id::HandleId,
+            backend::janus::client::{HandleId as JanusHandleId, SessionId},
+            test_helpers::{prelude::*, test_deps::LocalDeps},
+        };
+
+        use super::super::*;
+
+        #[async_std::test]
+        async fn find_room_id_by_handle_id() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let outsider = TestAgent::new("web", "user456", USR_AUDIENCE);
+
+            let rtc = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                let rtc = shared_helpers::insert_rtc(&conn);
+                shared_helpers::insert_agent(&conn, agent.agent_id(), rtc.room_id());
+                rtc
+            };
+
+            let handle_id = HandleId::new(
+                crate::db::janus_rtc_stream::Id::random(),
+                rtc.id(),
+                JanusHandleId::random(),
+                SessionId::random(),
+                TestAgent::new("alpha", "janus", SVC_AUDIENCE)
+                    .agent_id()
+                    .to_owned(),
+            );
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+            let payload = json!({ "handle_id": handle_id }).to_string();
+            let room_id = find_rate_limit_room_id(&mut context, agent.agent_id(), &payload).await;
+            assert_eq!(room_id, Some(rtc.room_id()));
+
+            let payload = json!({ "id": rtc.id() }).to_string();
+            let room_id = find_rate_limit_room_id(&mut context, agent.agent_id(), &payload).await;
+            assert_eq!(room_id, None);
+
+            // Requests of agents that are not in the room don't charge the room's bucket.
+            let payload = json!({ "handle_id": handle_id }).to_string();
+            let room_id =
+                find_rate_limit_room_id(&mut context, outsider.agent_id(), &payload).await;
+            assert_eq!(room_id, None);
+
+            let payload = json!({ "room_id": rtc.room_id() }).to_string();
+            let room_id =
+                find_rate_limit_room_id(&mut context, outsider.agent_id(), &payload).await;
+            assert_eq!(room_id, None);
+        }
+    }
+}
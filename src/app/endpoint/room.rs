@@ -2,39 +2,46 @@ use anyhow::{anyhow, Context as AnyhowContext};
 use async_std::{stream, task};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use diesel::Connection;
 use serde::{Deserialize, Serialize};
-use serde_json::Value as JsonValue;
-use std::ops::Bound;
+use serde_json::{json, Value as JsonValue};
+use slog::{o, warn};
+use std::{ops::Bound, result::Result as StdResult};
 use svc_agent::{
     mqtt::{
         IncomingRequestProperties, IntoPublishableMessage, OutgoingRequest, ResponseStatus,
         ShortTermTimingProperties, SubscriptionTopic,
     },
-    Addressable, AgentId, Subscription,
+    Addressable, AgentId, Authenticable, Subscription,
 };
 use uuid::Uuid;
 
 use crate::{
     app::{
         context::Context,
+        endpoint,
         endpoint::{prelude::*, subscription::CorrelationDataPayload},
         metrics::HistogramExt,
         API_VERSION,
     },
     db,
-    db::{room::RoomBackend, rtc::SharingPolicy as RtcSharingPolicy},
+    db::{
+        janus_rtc_stream::Kind as StreamKind,
+        room::{ReaderConfigDefaults, RoomBackend, WriterConfigDefaults},
+        rtc::SharingPolicy as RtcSharingPolicy,
+    },
 };
 
 ///////////////////////////////////////////////////////////////////////////////
 
 #[derive(Debug, Serialize)]
-struct SubscriptionRequest {
+pub(super) struct SubscriptionRequest {
     subject: AgentId,
     object: Vec<String>,
 }
 
 impl SubscriptionRequest {
-    fn new(subject: AgentId, object: Vec<String>) -> Self {
+    pub(super) fn new(subject: AgentId, object: Vec<String>) -> Self {
         Self { subject, object }
     }
 }
@@ -54,6 +61,13 @@ pub struct CreateRequest {
     reserve: Option<i32>,
     tags: Option<JsonValue>,
     classroom_id: Option<Uuid>,
+    #[serde(default)]
+    lobby_enabled: Option<bool>,
+    default_writer_config: Option<WriterConfigDefaults>,
+    default_reader_config: Option<ReaderConfigDefaults>,
+    persistent_message_labels: Option<Vec<String>>,
+    /// Preferred region of the backend to host the room.
+    region: Option<String>,
 }
 
 pub struct CreateHandler;
@@ -100,12 +114,33 @@ impl RequestHandler for CreateHandler {
                     q = q.classroom_id(classroom_id);
                 }
 
+                if let Some(lobby_enabled) = payload.lobby_enabled {
+                    q = q.lobby_enabled(lobby_enabled);
+                }
+
+                if let Some(ref default_writer_config) = payload.default_writer_config {
+                    q = q.default_writer_config(default_writer_config);
+                }
+
+                if let Some(ref default_reader_config) = payload.default_reader_config {
+                    q = q.default_reader_config(default_reader_config);
+                }
+
+                if let Some(ref persistent_message_labels) = payload.persistent_message_labels {
+                    q = q.persistent_message_labels(persistent_message_labels);
+                }
+
+                if let Some(ref region) = payload.region {
+                    q = q.region(region);
+                }
+
                 q.execute(&conn)
             }
         })
         .await?;
 
         helpers::add_room_logger_tags(context, &room);
+        let (room, maybe_warning) = reserve_backend_ahead(context, room, reqp).await?;
 
         // Respond and broadcast to the audience topic.
         let response = helpers::build_response(
@@ -125,7 +160,9 @@ impl RequestHandler for CreateHandler {
             context.start_timestamp(),
         );
 
-        Ok(Box::new(stream::from_iter(vec![response, notification])))
+        let mut responses = vec![response, notification];
+        responses.extend(maybe_warning);
+        Ok(Box::new(stream::from_iter(responses)))
     }
 }
 
@@ -190,6 +227,12 @@ pub struct UpdateRequest {
     reserve: Option<Option<i32>>,
     tags: Option<JsonValue>,
     classroom_id: Option<Uuid>,
+    #[serde(default)]
+    lobby_enabled: Option<bool>,
+    default_writer_config: Option<Option<WriterConfigDefaults>>,
+    default_reader_config: Option<Option<ReaderConfigDefaults>>,
+    persistent_message_labels: Option<Vec<String>>,
+    region: Option<String>,
 }
 pub struct UpdateHandler;
 
@@ -229,6 +272,8 @@ impl RequestHandler for UpdateHandler {
         context.metrics().observe_auth(authz_time);
 
         let room_was_open = !room.is_closed();
+        // An assigned backend is being checked again only if the reservation has changed.
+        let reservation_changed = payload.reserve.is_some() || payload.time.is_some();
 
         // Update room.
         let conn = context.get_conn().await?;
@@ -275,9 +320,20 @@ impl RequestHandler for UpdateHandler {
                 .reserve(payload.reserve)
                 .tags(payload.tags)
                 .classroom_id(payload.classroom_id)
+                .lobby_enabled(payload.lobby_enabled)
+                .default_writer_config(payload.default_writer_config.as_ref().map(|c| c.as_ref()))
+                .default_reader_config(payload.default_reader_config.as_ref().map(|c| c.as_ref()))
+                .persistent_message_labels(payload.persistent_message_labels)
+                .region(payload.region.as_deref())
                 .execute(&conn)?)
         }).await?;
 
+        let (room, maybe_warning) = if room.backend_id().is_none() || reservation_changed {
+            reserve_backend_ahead(context, room, reqp).await?
+        } else {
+            (room, None)
+        };
+
         // Respond and broadcast to the audience topic.
         let response = helpers::build_response(
             ResponseStatus::OK,
@@ -296,6 +352,7 @@ impl RequestHandler for UpdateHandler {
         );
 
         let mut responses = vec![response, notification];
+        responses.extend(maybe_warning);
 
         // Publish room closed notification.
         if let (_, Bound::Excluded(closed_at)) = room.time() {
@@ -327,6 +384,74 @@ impl RequestHandler for UpdateHandler {
     }
 }
 
+/// Assigns a backend to the room with a reserve ahead of its opening if enabled in the config.
+/// When there's no backend capable to host the reserve for the room's period the room is
+/// left as is and a `room.reservation_failed` warning event is returned for the audience.
+async fn reserve_backend_ahead<C: Context>(
+    context: &mut C,
+    room: db::room::Object,
+    reqp: &IncomingRequestProperties,
+) -> StdResult<
+    (
+        db::room::Object,
+        Option<Box<dyn IntoPublishableMessage + Send>>,
+    ),
+    AppError,
+> {
+    if !context.config().balancer.reserve_ahead || !helpers::needs_reservation(&room) {
+        return Ok((room, None));
+    }
+
+    let group = context.config().janus_group.clone();
+    let config = context.config().balancer.clone();
+    let max_room_duration = context.config().max_room_duration;
+    let balancer = context.balancer().clone();
+    let conn = context.get_conn().await?;
+
+    let maybe_room = task::spawn_blocking({
+        let room = room.clone();
+
+        move || {
+            helpers::reserve_backend(
+                &room,
+                &balancer,
+                &config,
+                group.as_deref(),
+                max_room_duration,
+                &conn,
+            )
+        }
+    })
+    .await?;
+
+    match maybe_room {
+        Some(room) => {
+            if let Some(backend_id) = room.backend_id() {
+                context.add_logger_tags(o!("backend_id" => backend_id.to_string()));
+            }
+
+            Ok((room, None))
+        }
+        None => {
+            warn!(
+                context.logger(),
+                "No capable backends to reserve for the room: reserve = {:?}",
+                room.reserve()
+            );
+
+            let notification = helpers::build_notification(
+                "room.reservation_failed",
+                &format!("audiences/{}/events", room.audience()),
+                room.clone(),
+                reqp,
+                context.start_timestamp(),
+            );
+
+            Ok((room, Some(notification)))
+        }
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 
 pub type EnterRequest = ReadRequest;
@@ -359,14 +484,69 @@ impl RequestHandler for EnterHandler {
             .await?;
         context.metrics().observe_auth(authz_time);
 
-        // Register agent in `in_progress` state.
+        // Register agent in `in_progress` state unless it's banned in the room.
+        // When the lobby is enabled the agent waits there for admission instead
+        // unless it has already been admitted before.
         let conn = context.get_conn().await?;
-        task::spawn_blocking({
+        let status = task::spawn_blocking({
             let agent_id = reqp.as_agent_id().clone();
-            move || db::agent::InsertQuery::new(&agent_id, room.id()).execute(&conn)
+
+            move || {
+                let maybe_ban = db::room_ban::FindQuery::new(room.id(), agent_id.as_account_id())
+                    .execute(&conn)?;
+
+                if maybe_ban.is_some() {
+                    return Err(anyhow!("The agent is banned in the room"))
+                        .error(AppErrorKind::AgentBanned);
+                }
+
+                let status = if room.lobby_enabled() {
+                    let maybe_agent = db::agent::ListQuery::new()
+                        .room_id(room.id())
+                        .agent_id(&agent_id)
+                        .statuses(&[
+                            db::agent::Status::InProgress,
+                            db::agent::Status::Ready,
+                            db::agent::Status::Waiting,
+                        ])
+                        .limit(1)
+                        .execute(&conn)?;
+
+                    match maybe_agent.first() {
+                        Some(agent) => agent.status(),
+                        None => db::agent::Status::Waiting,
+                    }
+                } else {
+                    db::agent::Status::InProgress
+                };
+
+                db::agent::InsertQuery::new(&agent_id, room.id())
+                    .status(status)
+                    .execute(&conn)?;
+
+                Ok::<_, AppError>(status)
+            }
         })
         .await?;
 
+        if status == db::agent::Status::Waiting {
+            context
+                .metrics()
+                .request_duration
+                .room_enter
+                .observe_timestamp(context.start_timestamp());
+
+            let response = helpers::build_response(
+                ResponseStatus::ACCEPTED,
+                json!({}),
+                reqp,
+                context.start_timestamp(),
+                Some(authz_time),
+            );
+
+            return Ok(Box::new(stream::once(response)));
+        }
+
         // Send dynamic subscription creation request to the broker.
         let subject = reqp.as_agent_id().to_owned();
         let object = vec!["rooms", &room_id, "events"];
@@ -433,17 +613,49 @@ impl RequestHandler for LeaveHandler {
                 let presence = db::agent::ListQuery::new()
                     .room_id(room.id())
                     .agent_id(&agent_id)
+                    .statuses(&[db::agent::Status::Ready, db::agent::Status::Waiting])
                     .execute(&conn)?;
 
+                // An agent waiting in the lobby has no subscription to delete so just drop it.
+                if let Some(agent) = presence.first() {
+                    if agent.status() == db::agent::Status::Waiting {
+                        db::agent::DeleteQuery::new()
+                            .agent_id(&agent_id)
+                            .room_id(room.id())
+                            .execute(&conn)?;
+                    }
+                }
+
                 Ok::<_, AppError>((room, presence))
             }
         })
         .await?;
         helpers::add_room_logger_tags(context, &room);
 
-        if presence.is_empty() {
-            return Err(anyhow!("Agent is not online in the room"))
-                .error(AppErrorKind::AgentNotEnteredTheRoom);
+        let agent = match presence.first() {
+            Some(agent) => agent,
+            None => {
+                return Err(anyhow!("Agent is not online in the room"))
+                    .error(AppErrorKind::AgentNotEnteredTheRoom)
+            }
+        };
+
+        if agent.status() == db::agent::Status::Waiting {
+            context
+                .metrics()
+                .request_duration
+                .room_leave
+                .observe_timestamp(context.start_timestamp());
+
+            let response = helpers::build_response(
+                ResponseStatus::OK,
+                json!({}),
+                reqp,
+                context.start_timestamp(),
+                None,
+            );
+
+            return Ok(Box::new(stream::once(response)));
         }
 
         // Send dynamic subscription deletion request to the broker.
@@ -484,162 +696,1518 @@ impl RequestHandler for LeaveHandler {
 
 ///////////////////////////////////////////////////////////////////////////////
 
-#[cfg(test)]
-mod test {
-    use serde::Deserialize;
+#[derive(Debug, Deserialize)]
+pub struct AdmitRequest {
+    id: db::room::Id,
+    agent_id: AgentId,
+}
 
-    use super::AgentId;
+pub struct AdmitHandler;
 
-    #[derive(Deserialize)]
-    struct DynSubRequest {
-        subject: AgentId,
-        object: Vec<String>,
-    }
+#[async_trait]
+impl RequestHandler for AdmitHandler {
+    type Payload = AdmitRequest;
+    const ERROR_TITLE: &'static str = "Failed to admit agent to room";
 
-    mod create {
-        use std::ops::Bound;
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let conn = context.get_conn().await?;
+        let room = task::spawn_blocking(move || {
+            helpers::find_room_by_id(payload.id, helpers::RoomTimeRequirement::NotClosed, &conn)
+        })
+        .await?;
+        helpers::add_room_logger_tags(context, &room);
 
-        use chrono::Utc;
-        use serde_json::json;
+        // Authorize admission to the room.
+        let room_id = room.id().to_string();
+        let object = vec!["rooms", &room_id];
 
-        use crate::{
-            db::room::Object as Room,
-            test_helpers::{prelude::*, test_deps::LocalDeps},
-        };
+        let authz_time = context
+            .authz()
+            .authorize(room.audience(), reqp, object, "update")
+            .await?;
+        context.metrics().observe_auth(authz_time);
 
-        use super::super::*;
+        // Move the agent from the lobby to `in_progress` state.
+        let conn = context.get_conn().await?;
+        let maybe_agent = task::spawn_blocking({
+            let agent_id = payload.agent_id.clone();
 
-        #[test]
-        fn create() {
-            async_std::task::block_on(async {
-                let local_deps = LocalDeps::new();
-                let postgres = local_deps.run_postgres();
-                let db = TestDb::with_local_postgres(&postgres);
+            move || {
+                let waiting_agents = db::agent::ListQuery::new()
+                    .room_id(room.id())
+                    .agent_id(&agent_id)
+                    .statuses(&[db::agent::Status::Waiting])
+                    .execute(&conn)?;
 
-                // Allow user to create rooms.
-                let mut authz = TestAuthz::new();
-                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
-                authz.allow(agent.account_id(), vec!["rooms"], "create");
+                if waiting_agents.is_empty() {
+                    return Ok::<_, AppError>(None);
+                }
 
-                // Make room.create request.
-                let mut context = TestContext::new(db.clone(), authz);
-                let time = (Bound::Unbounded, Bound::Unbounded);
-                let classroom_id = Uuid::new_v4();
+                let agent = db::agent::UpdateQuery::new(&agent_id, room.id())
+                    .status(db::agent::Status::InProgress)
+                    .execute(&conn)?;
 
-                let payload = CreateRequest {
-                    time: time.clone(),
-                    audience: USR_AUDIENCE.to_owned(),
-                    backend: None,
-                    rtc_sharing_policy: Some(db::rtc::SharingPolicy::Shared),
-                    reserve: Some(123),
-                    tags: Some(json!({ "foo": "bar" })),
-                    classroom_id: Some(classroom_id),
-                };
+                Ok(agent)
+            }
+        })
+        .await?;
 
-                let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
-                    .await
-                    .expect("Room creation failed");
+        if maybe_agent.is_none() {
+            return Err(anyhow!("The agent is not waiting in the room's lobby"))
+                .error(AppErrorKind::AgentNotEnteredTheRoom);
+        }
 
-                // Assert response.
-                let (room, respp, _) = find_response::<Room>(messages.as_slice());
-                assert_eq!(respp.status(), ResponseStatus::OK);
-                assert_eq!(room.audience(), USR_AUDIENCE);
-                assert_eq!(room.time(), &time);
-                assert_eq!(room.rtc_sharing_policy(), db::rtc::SharingPolicy::Shared);
-                assert_eq!(room.reserve(), Some(123));
-                assert_eq!(room.tags(), &json!({ "foo": "bar" }));
-                assert_eq!(room.classroom_id(), Some(classroom_id));
+        // Send dynamic subscription creation request to the broker on behalf of the agent.
+        // The response to this request is being sent when the broker confirms the subscription.
+        let subject = payload.agent_id;
+        let object = vec![String::from("rooms"), room_id, String::from("events")];
+        let payload = SubscriptionRequest::new(subject.clone(), object.clone());
 
-                // Assert notification.
-                let (room, evp, topic) = find_event::<Room>(messages.as_slice());
-                assert!(topic.ends_with(&format!("/audiences/{}/events", USR_AUDIENCE)));
-                assert_eq!(evp.label(), "room.create");
-                assert_eq!(room.audience(), USR_AUDIENCE);
-                assert_eq!(room.time(), &time);
-                assert_eq!(room.rtc_sharing_policy(), db::rtc::SharingPolicy::Shared);
-                assert_eq!(room.reserve(), Some(123));
-                assert_eq!(room.tags(), &json!({ "foo": "bar" }));
-                assert_eq!(room.classroom_id(), Some(classroom_id));
-            });
-        }
+        let broker_id = AgentId::new("nevermind", context.config().broker_id.to_owned());
 
-        #[async_std::test]
-        async fn create_room_unauthorized() {
-            let local_deps = LocalDeps::new();
-            let postgres = local_deps.run_postgres();
-            let db = TestDb::with_local_postgres(&postgres);
+        let response_topic = Subscription::unicast_responses_from(&broker_id)
+            .subscription_topic(context.agent_id(), API_VERSION)
+            .context("Failed to build response topic")
+            .error(AppErrorKind::BrokerRequestFailed)?;
 
-            let mut context = TestContext::new(db, TestAuthz::new());
-            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+        let corr_data_payload = CorrelationDataPayload::new(reqp.to_owned(), subject, object);
 
-            // Make room.create request.
-            let payload = CreateRequest {
-                time: (Bound::Included(Utc::now()), Bound::Unbounded),
-                audience: USR_AUDIENCE.to_owned(),
-                backend: None,
-                rtc_sharing_policy: Some(db::rtc::SharingPolicy::Shared),
-                reserve: None,
-                tags: None,
-                classroom_id: None,
-            };
+        let corr_data = CorrelationData::SubscriptionCreate(corr_data_payload)
+            .dump()
+            .context("Failed to dump correlation data")
+            .error(AppErrorKind::BrokerRequestFailed)?;
 
-            let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
-                .await
-                .expect_err("Unexpected success on room creation");
+        let mut timing = ShortTermTimingProperties::until_now(context.start_timestamp());
+        timing.set_authorization_time(authz_time);
 
-            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
-            assert_eq!(err.kind(), "access_denied");
-        }
-    }
+        let props = reqp.to_request("subscription.create", &response_topic, &corr_data, timing);
+        let to = &context.config().broker_id;
+        let outgoing_request = OutgoingRequest::multicast(payload, props, to, API_VERSION);
+        let boxed_request = Box::new(outgoing_request) as Box<dyn IntoPublishableMessage + Send>;
+        context
+            .metrics()
+            .request_duration
+            .room_admit
+            .observe_timestamp(context.start_timestamp());
 
-    mod read {
-        use crate::{
-            db::room::Object as Room,
-            test_helpers::{prelude::*, test_deps::LocalDeps},
-        };
+        Ok(Box::new(stream::once(boxed_request)))
+    }
+}
 
-        use super::super::*;
+///////////////////////////////////////////////////////////////////////////////
 
-        #[async_std::test]
-        async fn read_room() {
-            let local_deps = LocalDeps::new();
-            let postgres = local_deps.run_postgres();
-            let db = TestDb::with_local_postgres(&postgres);
+#[derive(Debug, Deserialize)]
+pub struct ApplyDefaultsRequest {
+    id: db::room::Id,
+}
 
-            let room = {
-                let conn = db
-                    .connection_pool()
-                    .get()
-                    .expect("Failed to get DB connection");
+pub struct ApplyDefaultsHandler;
 
-                // Create room.
-                shared_helpers::insert_room(&conn)
-            };
+#[async_trait]
+impl RequestHandler for ApplyDefaultsHandler {
+    type Payload = ApplyDefaultsRequest;
+    const ERROR_TITLE: &'static str = "Failed to apply room defaults";
 
-            // Allow agent to read the room.
-            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
-            let mut authz = TestAuthz::new();
-            let room_id = room.id().to_string();
-            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let conn = context.get_conn().await?;
+        let room = task::spawn_blocking(move || {
+            helpers::find_room_by_id(payload.id, helpers::RoomTimeRequirement::Open, &conn)
+        })
+        .await?;
+        helpers::add_room_logger_tags(context, &room);
 
-            // Make room.read request.
-            let mut context = TestContext::new(db, authz);
-            let payload = ReadRequest { id: room.id() };
+        if room.rtc_sharing_policy() != RtcSharingPolicy::Owned {
+            return Err(anyhow!(
+                "Room defaults are available only for rooms with owned RTC sharing policy"
+            ))
+            .error(AppErrorKind::InvalidPayload);
+        }
 
-            let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
-                .await
-                .expect("Room reading failed");
+        // Authorize room updating on the tenant.
+        let room_id = room.id().to_string();
+        let object = vec!["rooms", &room_id];
 
-            // Assert response.
+        let authz_time = context
+            .authz()
+            .authorize(room.audience(), reqp, object, "update")
+            .await?;
+        context.metrics().observe_auth(authz_time);
+
+        // Overwrite configs of everyone in the room except moderators with the defaults.
+        let conn = context.get_conn().await?;
+        let (rtc_writer_configs_with_rtcs, rtc_reader_configs_with_rtcs, maybe_backend) =
+            task::spawn_blocking({
+                let room = room.clone();
+                move || {
+                    conn.transaction::<_, AppError, _>(|| {
+                        let rtcs = db::rtc::ListQuery::new()
+                            .room_id(room.id())
+                            .execute(&conn)?;
+
+                        let maybe_defaults = room
+                            .default_writer_config()
+                            .context("Failed to parse room default writer config")
+                            .error(AppErrorKind::MessageParsingFailed)?;
+
+                        if let Some(defaults) = maybe_defaults {
+                            let current_configs =
+                                db::rtc_writer_config::ListWithRtcQuery::new(room.id())
+                                    .execute(&conn)?;
+
+                            for rtc in &rtcs {
+                                if !helpers::are_room_defaults_applicable(
+                                    &room,
+                                    rtc.created_by(),
+                                    &conn,
+                                )? {
+                                    continue;
+                                }
+
+                                // Apply to every stream kind the writer has a config for.
+                                let mut kinds = current_configs
+                                    .iter()
+                                    .filter(|(_, config_rtc)| config_rtc.id() == rtc.id())
+                                    .map(|(config, _)| config.kind())
+                                    .collect::<Vec<_>>();
+
+                                if kinds.is_empty() {
+                                    kinds.push(StreamKind::default());
+                                }
+
+                                for kind in kinds {
+                                    db::rtc_writer_config::UpsertQuery::new(rtc.id())
+                                        .kind(kind)
+                                        .defaults(&defaults)
+                                        .execute(&conn)?;
+                                }
+                            }
+                        }
+
+                        let mut rtc_reader_configs_with_rtcs = vec![];
+
+                        let maybe_defaults = room
+                            .default_reader_config()
+                            .context("Failed to parse room default reader config")
+                            .error(AppErrorKind::MessageParsingFailed)?;
+
+                        if let Some(defaults) = maybe_defaults {
+                            let readers = db::agent::ListQuery::new()
+                                .room_id(room.id())
+                                .execute(&conn)?;
+
+                            for reader in &readers {
+                                if !helpers::are_room_defaults_applicable(
+                                    &room,
+                                    reader.agent_id(),
+                                    &conn,
+                                )? {
+                                    continue;
+                                }
+
+                                for rtc in &rtcs {
+                                    if rtc.created_by() == reader.agent_id()
+                                        || !helpers::are_room_defaults_applicable(
+                                            &room,
+                                            rtc.created_by(),
+                                            &conn,
+                                        )?
+                                    {
+                                        continue;
+                                    }
+
+                                    db::rtc_reader_config::UpsertQuery::new(
+                                        rtc.id(),
+                                        reader.agent_id(),
+                                    )
+                                    .defaults(&defaults)
+                                    .execute(&conn)?;
+                                }
+
+                                let reader_configs = db::rtc_reader_config::ListWithRtcQuery::new(
+                                    room.id(),
+                                    reader.agent_id(),
+                                )
+                                .execute(&conn)?;
+
+                                rtc_reader_configs_with_rtcs.extend(reader_configs);
+                            }
+                        }
+
+                        let rtc_writer_configs_with_rtcs =
+                            db::rtc_writer_config::ListWithRtcQuery::new(room.id())
+                                .execute(&conn)?;
+
+                        let maybe_backend = match room.backend_id() {
+                            None => None,
+                            Some(backend_id) => db::janus_backend::FindQuery::new()
+                                .id(backend_id)
+                                .execute(&conn)?,
+                        };
+
+                        Ok((
+                            rtc_writer_configs_with_rtcs,
+                            rtc_reader_configs_with_rtcs,
+                            maybe_backend,
+                        ))
+                    })
+                }
+            })
+            .await?;
+
+        if let Some(backend) = maybe_backend {
+            if let Ok(Some(_)) = room.default_writer_config() {
+                endpoint::agent_writer_config::update_janus(
+                    context,
+                    &backend,
+                    &rtc_writer_configs_with_rtcs,
+                )
+                .await?;
+            }
+
+            if !rtc_reader_configs_with_rtcs.is_empty() {
+                endpoint::agent_reader_config::update_janus(
+                    context,
+                    &backend,
+                    &rtc_reader_configs_with_rtcs,
+                )
+                .await?;
+            }
+        }
+
+        let response = helpers::build_response(
+            ResponseStatus::OK,
+            json!({}),
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        );
+
+        let notification = helpers::build_notification(
+            "agent_writer_config.update",
+            &format!("rooms/{}/events", room.id()),
+            endpoint::agent_writer_config::State::new(room.id(), &rtc_writer_configs_with_rtcs),
+            reqp,
+            context.start_timestamp(),
+        );
+
+        context
+            .metrics()
+            .request_duration
+            .room_apply_defaults
+            .observe_timestamp(context.start_timestamp());
+
+        Ok(Box::new(stream::from_iter(vec![response, notification])))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+const MAX_BREAKOUTS: usize = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateBreakoutsRequest {
+    id: db::room::Id,
+    count: usize,
+}
+
+pub struct CreateBreakoutsHandler;
+
+#[async_trait]
+impl RequestHandler for CreateBreakoutsHandler {
+    type Payload = CreateBreakoutsRequest;
+    const ERROR_TITLE: &'static str = "Failed to create breakout rooms";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        if payload.count == 0 || payload.count > MAX_BREAKOUTS {
+            return Err(anyhow!(
+                "Breakout rooms count must be from 1 to {}",
+                MAX_BREAKOUTS
+            ))
+            .error(AppErrorKind::InvalidPayload);
+        }
+
+        let conn = context.get_conn().await?;
+        let parent = task::spawn_blocking({
+            let id = payload.id;
+            move || helpers::find_room_by_id(id, helpers::RoomTimeRequirement::NotClosed, &conn)
+        })
+        .await?;
+        helpers::add_room_logger_tags(context, &parent);
+
+        if parent.parent_room_id().is_some() {
+            return Err(anyhow!(
+                "Breakout rooms can't have their own breakout rooms"
+            ))
+            .error(AppErrorKind::InvalidPayload);
+        }
+
+        // Authorize updating the parent room.
+        let parent_id = parent.id().to_string();
+        let object = vec!["rooms", &parent_id];
+
+        let authz_time = context
+            .authz()
+            .authorize(parent.audience(), reqp, object, "update")
+            .await?;
+        context.metrics().observe_auth(authz_time);
+
+        // Create breakout rooms inheriting the parent's properties
+        // that are open from now on till the parent's closing.
+        let conn = context.get_conn().await?;
+        let breakouts = task::spawn_blocking(move || {
+            let now = Utc::now();
+
+            let opened_at = match parent.time().0 {
+                Bound::Included(dt) | Bound::Excluded(dt) if dt > now => dt,
+                _ => now,
+            };
+
+            let time = (Bound::Included(opened_at), parent.time().1);
+
+            conn.transaction::<_, diesel::result::Error, _>(|| {
+                (0..payload.count)
+                    .map(|_| {
+                        let mut q = db::room::InsertQuery::new(
+                            time,
+                            parent.audience(),
+                            parent.rtc_sharing_policy(),
+                        )
+                        .tags(parent.tags())
+                        .parent_room_id(parent.id());
+
+                        if let Some(classroom_id) = parent.classroom_id() {
+                            q = q.classroom_id(classroom_id);
+                        }
+
+                        q.execute(&conn)
+                    })
+                    .collect::<StdResult<Vec<_>, _>>()
+            })
+        })
+        .await?;
+
+        // Respond and broadcast to the audience topic.
+        let mut responses = vec![helpers::build_response(
+            ResponseStatus::CREATED,
+            breakouts.clone(),
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        )];
+
+        for breakout in breakouts {
+            responses.push(helpers::build_notification(
+                "room.create",
+                &format!("audiences/{}/events", breakout.audience()),
+                breakout,
+                reqp,
+                context.start_timestamp(),
+            ));
+        }
+
+        context
+            .metrics()
+            .request_duration
+            .room_create_breakouts
+            .observe_timestamp(context.start_timestamp());
+
+        Ok(Box::new(stream::from_iter(responses)))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+pub type CloseBreakoutsRequest = ReadRequest;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BreakoutsClosedEvent {
+    parent_room_id: db::room::Id,
+    breakouts: Vec<db::room::Object>,
+}
+
+pub struct CloseBreakoutsHandler;
+
+#[async_trait]
+impl RequestHandler for CloseBreakoutsHandler {
+    type Payload = CloseBreakoutsRequest;
+    const ERROR_TITLE: &'static str = "Failed to close breakout rooms";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        let conn = context.get_conn().await?;
+        let parent = task::spawn_blocking(move || {
+            helpers::find_room_by_id(payload.id, helpers::RoomTimeRequirement::Any, &conn)
+        })
+        .await?;
+        helpers::add_room_logger_tags(context, &parent);
+
+        // Authorize updating the parent room.
+        let parent_id = parent.id().to_string();
+        let object = vec!["rooms", &parent_id];
+
+        let authz_time = context
+            .authz()
+            .authorize(parent.audience(), reqp, object, "update")
+            .await?;
+        context.metrics().observe_auth(authz_time);
+
+        // Close breakout rooms that are not closed yet.
+        let conn = context.get_conn().await?;
+        let breakouts = task::spawn_blocking({
+            let parent_id = parent.id();
+            move || db::room::close_breakouts(parent_id, &conn)
+        })
+        .await?;
+
+        // Respond and notify the parent room and each closed breakout room.
+        let event = BreakoutsClosedEvent {
+            parent_room_id: parent.id(),
+            breakouts,
+        };
+
+        let mut responses = vec![helpers::build_response(
+            ResponseStatus::OK,
+            event.breakouts.clone(),
+            reqp,
+            context.start_timestamp(),
+            Some(authz_time),
+        )];
+
+        for breakout in &event.breakouts {
+            responses.push(helpers::build_notification(
+                "room.close",
+                &format!("rooms/{}/events", breakout.id()),
+                breakout.clone(),
+                reqp,
+                context.start_timestamp(),
+            ));
+
+            responses.push(helpers::build_notification(
+                "room.close",
+                &format!("audiences/{}/events", breakout.audience()),
+                breakout.clone(),
+                reqp,
+                context.start_timestamp(),
+            ));
+        }
+
+        responses.push(helpers::build_notification(
+            "breakouts.closed",
+            &format!("rooms/{}/events", parent.id()),
+            event,
+            reqp,
+            context.start_timestamp(),
+        ));
+
+        context
+            .metrics()
+            .request_duration
+            .room_close_breakouts
+            .observe_timestamp(context.start_timestamp());
+
+        Ok(Box::new(stream::from_iter(responses)))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+
+    use super::AgentId;
+
+    #[derive(Deserialize)]
+    struct DynSubRequest {
+        subject: AgentId,
+        object: Vec<String>,
+    }
+
+    mod create {
+        use std::ops::Bound;
+
+        use chrono::{Duration, Utc};
+        use serde_json::json;
+
+        use crate::{
+            backend::janus::client::{HandleId, SessionId},
+            db::room::Object as Room,
+            test_helpers::{find_event_by_predicate, prelude::*, test_deps::LocalDeps},
+        };
+
+        use super::super::*;
+
+        #[test]
+        fn create() {
+            async_std::task::block_on(async {
+                let local_deps = LocalDeps::new();
+                let postgres = local_deps.run_postgres();
+                let db = TestDb::with_local_postgres(&postgres);
+
+                // Allow user to create rooms.
+                let mut authz = TestAuthz::new();
+                let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+                authz.allow(agent.account_id(), vec!["rooms"], "create");
+
+                // Make room.create request.
+                let mut context = TestContext::new(db.clone(), authz);
+                let time = (Bound::Unbounded, Bound::Unbounded);
+                let classroom_id = Uuid::new_v4();
+
+                let payload = CreateRequest {
+                    time: time.clone(),
+                    audience: USR_AUDIENCE.to_owned(),
+                    backend: None,
+                    rtc_sharing_policy: Some(db::rtc::SharingPolicy::Shared),
+                    reserve: Some(123),
+                    tags: Some(json!({ "foo": "bar" })),
+                    classroom_id: Some(classroom_id),
+                    lobby_enabled: None,
+                    default_writer_config: None,
+                    default_reader_config: None,
+                    persistent_message_labels: None,
+                    region: None,
+                };
+
+                let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                    .await
+                    .expect("Room creation failed");
+
+                // Assert response.
+                let (room, respp, _) = find_response::<Room>(messages.as_slice());
+                assert_eq!(respp.status(), ResponseStatus::OK);
+                assert_eq!(room.audience(), USR_AUDIENCE);
+                assert_eq!(room.time(), &time);
+                assert_eq!(room.rtc_sharing_policy(), db::rtc::SharingPolicy::Shared);
+                assert_eq!(room.reserve(), Some(123));
+                assert_eq!(room.tags(), &json!({ "foo": "bar" }));
+                assert_eq!(room.classroom_id(), Some(classroom_id));
+
+                // Assert notification.
+                let (room, evp, topic) = find_event::<Room>(messages.as_slice());
+                assert!(topic.ends_with(&format!("/audiences/{}/events", USR_AUDIENCE)));
+                assert_eq!(evp.label(), "room.create");
+                assert_eq!(room.audience(), USR_AUDIENCE);
+                assert_eq!(room.time(), &time);
+                assert_eq!(room.rtc_sharing_policy(), db::rtc::SharingPolicy::Shared);
+                assert_eq!(room.reserve(), Some(123));
+                assert_eq!(room.tags(), &json!({ "foo": "bar" }));
+                assert_eq!(room.classroom_id(), Some(classroom_id));
+            });
+        }
+
+        #[async_std::test]
+        async fn create_room_unauthorized() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            // Make room.create request.
+            let payload = CreateRequest {
+                time: (Bound::Included(Utc::now()), Bound::Unbounded),
+                audience: USR_AUDIENCE.to_owned(),
+                backend: None,
+                rtc_sharing_policy: Some(db::rtc::SharingPolicy::Shared),
+                reserve: None,
+                tags: None,
+                classroom_id: None,
+                lobby_enabled: None,
+                default_writer_config: None,
+                default_reader_config: None,
+                persistent_message_labels: None,
+                region: None,
+            };
+
+            let err = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on room creation");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
+        }
+
+        #[async_std::test]
+        async fn create_room_with_backend_reserved_ahead() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let now = Utc::now();
+            let opened_at = now + Duration::hours(1);
+
+            let backend = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Insert two backends.
+                let backend1_agent = TestAgent::new("alpha", "janus", SVC_AUDIENCE);
+
+                let backend1 = factory::JanusBackend::new(
+                    backend1_agent.agent_id().to_owned(),
+                    HandleId::random(),
+                    SessionId::random(),
+                    String::from("https://janus.example.org"),
+                )
+                .capacity(200)
+                .insert(&conn);
+
+                let backend2_agent = TestAgent::new("beta", "janus", SVC_AUDIENCE);
+
+                let backend2 = factory::JanusBackend::new(
+                    backend2_agent.agent_id().to_owned(),
+                    HandleId::random(),
+                    SessionId::random(),
+                    String::from("https://janus.example.org"),
+                )
+                .capacity(200)
+                .insert(&conn);
+
+                // Schedule a room on the first backend overlapping with the new one.
+                factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((
+                        Bound::Included(now + Duration::minutes(30)),
+                        Bound::Excluded(now + Duration::hours(2)),
+                    ))
+                    .rtc_sharing_policy(db::rtc::SharingPolicy::Shared)
+                    .reserve(150)
+                    .backend_id(backend1.id())
+                    .insert(&conn);
+
+                // A room on the second backend that gets closed before the new one opens.
+                factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((
+                        Bound::Included(now),
+                        Bound::Excluded(now + Duration::minutes(30)),
+                    ))
+                    .rtc_sharing_policy(db::rtc::SharingPolicy::Shared)
+                    .reserve(150)
+                    .backend_id(backend2.id())
+                    .insert(&conn);
+
+                backend2
+            };
+
+            // Allow user to create rooms.
+            let mut authz = TestAuthz::new();
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            authz.allow(agent.account_id(), vec!["rooms"], "create");
+
+            // Make room.create request.
+            let mut context = TestContext::new(db, authz);
+            context.config_mut().balancer.reserve_ahead = true;
+
+            let payload = CreateRequest {
+                time: (
+                    Bound::Included(opened_at),
+                    Bound::Excluded(opened_at + Duration::hours(1)),
+                ),
+                audience: USR_AUDIENCE.to_owned(),
+                backend: None,
+                rtc_sharing_policy: Some(db::rtc::SharingPolicy::Shared),
+                reserve: Some(100),
+                tags: None,
+                classroom_id: None,
+                lobby_enabled: None,
+                default_writer_config: None,
+                default_reader_config: None,
+                persistent_message_labels: None,
+                region: None,
+            };
+
+            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Room creation failed");
+
+            // Assert the backend that is free during the room's period is reserved.
+            let (room, respp, _) = find_response::<Room>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(room.backend_id(), Some(backend.id()));
+        }
+
+        #[async_std::test]
+        async fn create_room_with_backend_reservation_failed() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let now = Utc::now();
+
+            {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                let backend_agent = TestAgent::new("alpha", "janus", SVC_AUDIENCE);
+
+                let backend = factory::JanusBackend::new(
+                    backend_agent.agent_id().to_owned(),
+                    HandleId::random(),
+                    SessionId::random(),
+                    String::from("https://janus.example.org"),
+                )
+                .capacity(100)
+                .insert(&conn);
+
+                // Schedule a room taking most of the backend's capacity.
+                factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((
+                        Bound::Included(now + Duration::hours(1)),
+                        Bound::Excluded(now + Duration::hours(2)),
+                    ))
+                    .rtc_sharing_policy(db::rtc::SharingPolicy::Shared)
+                    .reserve(80)
+                    .backend_id(backend.id())
+                    .insert(&conn);
+            }
+
+            // Allow user to create rooms.
+            let mut authz = TestAuthz::new();
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            authz.allow(agent.account_id(), vec!["rooms"], "create");
+
+            // Make room.create request for an overlapping room.
+            let mut context = TestContext::new(db, authz);
+            context.config_mut().balancer.reserve_ahead = true;
+
+            let payload = CreateRequest {
+                time: (
+                    Bound::Included(now + Duration::minutes(90)),
+                    Bound::Excluded(now + Duration::hours(3)),
+                ),
+                audience: USR_AUDIENCE.to_owned(),
+                backend: None,
+                rtc_sharing_policy: Some(db::rtc::SharingPolicy::Shared),
+                reserve: Some(50),
+                tags: None,
+                classroom_id: None,
+                lobby_enabled: None,
+                default_writer_config: None,
+                default_reader_config: None,
+                persistent_message_labels: None,
+                region: None,
+            };
+
+            let messages = handle_request::<CreateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Room creation failed");
+
+            // Assert the room is created without a backend.
+            let (room, respp, _) = find_response::<Room>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(room.backend_id(), None);
+
+            // Assert the warning to the audience.
+            let (room, _, topic) =
+                find_event_by_predicate::<Room, _>(messages.as_slice(), |evp, _, _| {
+                    evp.label() == "room.reservation_failed"
+                })
+                .expect("Failed to find room.reservation_failed event");
+
+            assert!(topic.ends_with(&format!("/audiences/{}/events", USR_AUDIENCE)));
+            assert_eq!(room.reserve(), Some(50));
+        }
+    }
+
+    mod read {
+        use crate::{
+            db::room::Object as Room,
+            test_helpers::{prelude::*, test_deps::LocalDeps},
+        };
+
+        use super::super::*;
+
+        #[async_std::test]
+        async fn read_room() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Create room.
+                shared_helpers::insert_room(&conn)
+            };
+
+            // Allow agent to read the room.
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+
+            // Make room.read request.
+            let mut context = TestContext::new(db, authz);
+            let payload = ReadRequest { id: room.id() };
+
+            let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Room reading failed");
+
+            // Assert response.
+            let (resp_room, respp, _) = find_response::<Room>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(resp_room.audience(), room.audience());
+            assert_eq!(resp_room.time(), room.time());
+            assert_eq!(resp_room.rtc_sharing_policy(), room.rtc_sharing_policy());
+        }
+
+        #[async_std::test]
+        async fn read_room_not_authorized() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                shared_helpers::insert_room(&conn)
+            };
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+            let payload = ReadRequest { id: room.id() };
+
+            let err = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on room reading");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
+        }
+
+        #[async_std::test]
+        async fn read_room_missing() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut context = TestContext::new(db, TestAuthz::new());
+            let payload = ReadRequest {
+                id: db::room::Id::random(),
+            };
+
+            let err = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on room reading");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "room_not_found");
+        }
+    }
+
+    mod update {
+        use std::ops::Bound;
+
+        use chrono::{Duration, SubsecRound, Utc};
+        use serde_json::json;
+        use uuid::Uuid;
+
+        use crate::{
+            backend::janus::client::{HandleId, SessionId},
+            db::room::Object as Room,
+            test_helpers::{find_event_by_predicate, prelude::*, test_deps::LocalDeps},
+        };
+
+        use super::super::*;
+
+        #[async_std::test]
+        async fn update_room() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let now = Utc::now().trunc_subsecs(0);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Create room.
+                factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((Bound::Unbounded, Bound::Unbounded))
+                    .rtc_sharing_policy(db::rtc::SharingPolicy::Shared)
+                    .insert(&conn)
+            };
+
+            // Allow agent to update the room.
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
+
+            // Make room.update request.
+            let mut context = TestContext::new(db, authz);
+            let classroom_id = Uuid::new_v4();
+
+            let time = (
+                Bound::Included(now + Duration::minutes(50)),
+                Bound::Unbounded,
+            );
+
+            let payload = UpdateRequest {
+                id: room.id(),
+                time: Some(time),
+                reserve: Some(Some(123)),
+                tags: Some(json!({"foo": "bar"})),
+                classroom_id: Some(classroom_id),
+                lobby_enabled: None,
+                default_writer_config: None,
+                default_reader_config: None,
+                persistent_message_labels: None,
+                region: None,
+            };
+
+            let messages = handle_request::<UpdateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Room update failed");
+
+            // Assert response.
+            let (resp_room, respp, _) = find_response::<Room>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(resp_room.id(), room.id());
+            assert_eq!(resp_room.audience(), room.audience());
+            assert_eq!(resp_room.time(), &time);
+            assert_eq!(
+                resp_room.rtc_sharing_policy(),
+                db::rtc::SharingPolicy::Shared
+            );
+            assert_eq!(resp_room.reserve(), Some(123));
+            assert_eq!(resp_room.tags(), &json!({"foo": "bar"}));
+            assert_eq!(resp_room.classroom_id(), Some(classroom_id));
+        }
+
+        fn insert_backend(
+            conn: &diesel::pg::PgConnection,
+            label: &str,
+        ) -> db::janus_backend::Object {
+            let backend_agent = TestAgent::new(label, "janus", SVC_AUDIENCE);
+
+            factory::JanusBackend::new(
+                backend_agent.agent_id().to_owned(),
+                HandleId::random(),
+                SessionId::random(),
+                String::from("https://janus.example.org"),
+            )
+            .capacity(100)
+            .insert(conn)
+        }
+
+        fn build_reserve_request(room_id: db::room::Id, reserve: i32) -> UpdateRequest {
+            UpdateRequest {
+                id: room_id,
+                time: None,
+                reserve: Some(Some(reserve)),
+                tags: None,
+                classroom_id: None,
+                lobby_enabled: None,
+                default_writer_config: None,
+                default_reader_config: None,
+                persistent_message_labels: None,
+                region: None,
+            }
+        }
+
+        #[async_std::test]
+        async fn update_room_reserve_moves_backend() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let now = Utc::now();
+
+            let time = (
+                Bound::Included(now + Duration::hours(1)),
+                Bound::Excluded(now + Duration::hours(2)),
+            );
+
+            let (room, other_backend) = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                let backend = insert_backend(&conn, "alpha");
+                let other_backend = insert_backend(&conn, "beta");
+
+                // Schedule a concurrent room taking most of the backend's capacity.
+                factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time(time)
+                    .rtc_sharing_policy(db::rtc::SharingPolicy::Shared)
+                    .reserve(80)
+                    .backend_id(backend.id())
+                    .insert(&conn);
+
+                // The room has been reserved on the same backend with a small reserve.
+                let room = factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time(time)
+                    .rtc_sharing_policy(db::rtc::SharingPolicy::Shared)
+                    .reserve(10)
+                    .backend_id(backend.id())
+                    .insert(&conn);
+
+                (room, other_backend)
+            };
+
+            // Allow agent to update the room.
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
+
+            // Make room.update request raising the reserve beyond the backend's free capacity.
+            let mut context = TestContext::new(db, authz);
+            context.config_mut().balancer.reserve_ahead = true;
+            let payload = build_reserve_request(room.id(), 50);
+
+            let messages = handle_request::<UpdateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Room update failed");
+
+            // Assert the room has been moved to the other backend.
             let (resp_room, respp, _) = find_response::<Room>(messages.as_slice());
             assert_eq!(respp.status(), ResponseStatus::OK);
-            assert_eq!(resp_room.audience(), room.audience());
-            assert_eq!(resp_room.time(), room.time());
-            assert_eq!(resp_room.rtc_sharing_policy(), room.rtc_sharing_policy());
+            assert_eq!(resp_room.reserve(), Some(50));
+            assert_eq!(resp_room.backend_id(), Some(other_backend.id()));
+
+            let maybe_warning =
+                find_event_by_predicate::<Room, _>(messages.as_slice(), |evp, _, _| {
+                    evp.label() == "room.reservation_failed"
+                });
+
+            assert!(maybe_warning.is_none());
+        }
+
+        #[async_std::test]
+        async fn update_room_reserve_reservation_failed() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let now = Utc::now();
+
+            let time = (
+                Bound::Included(now + Duration::hours(1)),
+                Bound::Excluded(now + Duration::hours(2)),
+            );
+
+            let (room, backend) = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                let backend = insert_backend(&conn, "alpha");
+                insert_backend(&conn, "beta");
+
+                // Schedule a concurrent room taking most of the backend's capacity.
+                factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time(time)
+                    .rtc_sharing_policy(db::rtc::SharingPolicy::Shared)
+                    .reserve(80)
+                    .backend_id(backend.id())
+                    .insert(&conn);
+
+                let room = factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time(time)
+                    .rtc_sharing_policy(db::rtc::SharingPolicy::Shared)
+                    .reserve(10)
+                    .backend_id(backend.id())
+                    .insert(&conn);
+
+                // The room has got a stream on the backend so it can't be moved.
+                let rtc = factory::Rtc::new(room.id()).insert(&conn);
+
+                factory::JanusRtcStream::new(USR_AUDIENCE)
+                    .backend(&backend)
+                    .rtc(&rtc)
+                    .insert(&conn);
+
+                (room, backend)
+            };
+
+            // Allow agent to update the room.
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
+
+            // Make room.update request raising the reserve beyond the backend's free capacity.
+            let mut context = TestContext::new(db, authz);
+            context.config_mut().balancer.reserve_ahead = true;
+            let payload = build_reserve_request(room.id(), 50);
+
+            let messages = handle_request::<UpdateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Room update failed");
+
+            // Assert the room keeps its backend.
+            let (resp_room, respp, _) = find_response::<Room>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(resp_room.reserve(), Some(50));
+            assert_eq!(resp_room.backend_id(), Some(backend.id()));
+
+            // Assert the warning to the audience.
+            let (warning_room, _, topic) =
+                find_event_by_predicate::<Room, _>(messages.as_slice(), |evp, _, _| {
+                    evp.label() == "room.reservation_failed"
+                })
+                .expect("Failed to find room.reservation_failed event");
+
+            assert!(topic.ends_with(&format!("/audiences/{}/events", USR_AUDIENCE)));
+            assert_eq!(warning_room.id(), room.id());
+        }
+
+        #[async_std::test]
+        async fn update_room_with_wrong_time() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let now = Utc::now().trunc_subsecs(0);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Create room.
+                factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((
+                        Bound::Included(now - Duration::hours(1)),
+                        Bound::Excluded(now + Duration::hours(2)),
+                    ))
+                    .rtc_sharing_policy(db::rtc::SharingPolicy::Shared)
+                    .insert(&conn)
+            };
+
+            // Allow agent to update the room.
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
+
+            // Make room.update request.
+            let mut context = TestContext::new(db, authz);
+
+            let time = (
+                Bound::Included(now + Duration::hours(3)),
+                Bound::Excluded(now - Duration::hours(2)),
+            );
+
+            let payload = UpdateRequest {
+                id: room.id(),
+                time: Some(time),
+                reserve: Some(Some(123)),
+                tags: Some(json!({"foo": "bar"})),
+                classroom_id: None,
+                lobby_enabled: None,
+                default_writer_config: None,
+                default_reader_config: None,
+                persistent_message_labels: None,
+                region: None,
+            };
+
+            handle_request::<UpdateHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Room update succeeded when it should've failed");
+        }
+
+        #[async_std::test]
+        async fn update_and_close_room() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let now = Utc::now().trunc_subsecs(0);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Create room.
+                factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((
+                        Bound::Included(now - Duration::hours(1)),
+                        Bound::Excluded(now + Duration::hours(5)),
+                    ))
+                    .rtc_sharing_policy(db::rtc::SharingPolicy::Shared)
+                    .insert(&conn)
+            };
+
+            // Allow agent to update the room.
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
+
+            // Make room.update request.
+            let mut context = TestContext::new(db, authz);
+
+            let time = (
+                Bound::Included(now - Duration::hours(1)),
+                Bound::Excluded(now - Duration::seconds(5)),
+            );
+
+            let payload = UpdateRequest {
+                id: room.id(),
+                time: Some(time),
+                reserve: Some(Some(123)),
+                tags: Default::default(),
+                classroom_id: Default::default(),
+                lobby_enabled: None,
+                default_writer_config: None,
+                default_reader_config: None,
+                persistent_message_labels: None,
+                region: None,
+            };
+
+            let messages = handle_request::<UpdateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Room update failed");
+
+            assert_eq!(messages.len(), 4);
+
+            let (closed_tenant_notification, _, _) =
+                find_event_by_predicate::<JsonValue, _>(messages.as_slice(), |evp, _, topic| {
+                    evp.label() == "room.close" && topic.contains("audiences")
+                })
+                .expect("Failed to find room.close event");
+
+            assert_eq!(
+                closed_tenant_notification
+                    .get("id")
+                    .and_then(|v| v.as_str()),
+                Some(room.id().to_string()).as_deref()
+            );
+
+            let (closed_room_notification, _, _) =
+                find_event_by_predicate::<JsonValue, _>(messages.as_slice(), |evp, _, topic| {
+                    evp.label() == "room.close" && topic.contains("rooms")
+                })
+                .expect("Failed to find room.close event");
+
+            assert_eq!(
+                closed_room_notification.get("id").and_then(|v| v.as_str()),
+                Some(room.id().to_string()).as_deref()
+            );
+        }
+
+        #[async_std::test]
+        async fn update_and_close_unbounded_room() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let now = Utc::now().trunc_subsecs(0);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Create room.
+                factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((Bound::Included(now - Duration::hours(1)), Bound::Unbounded))
+                    .insert(&conn)
+            };
+
+            // Allow agent to update the room.
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
+
+            // Make room.update request.
+            let mut context = TestContext::new(db, authz);
+
+            let time = (
+                Bound::Included(now - Duration::hours(1)),
+                Bound::Excluded(now - Duration::seconds(5)),
+            );
+
+            let payload = UpdateRequest {
+                id: room.id(),
+                time: Some(time),
+                reserve: Default::default(),
+                tags: Default::default(),
+                classroom_id: Default::default(),
+                lobby_enabled: None,
+                default_writer_config: None,
+                default_reader_config: None,
+                persistent_message_labels: None,
+                region: None,
+            };
+
+            handle_request::<UpdateHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Room update failed");
+        }
+
+        #[async_std::test]
+        async fn update_room_missing() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = UpdateRequest {
+                id: db::room::Id::random(),
+                time: Default::default(),
+                reserve: Default::default(),
+                tags: Default::default(),
+                classroom_id: Default::default(),
+                lobby_enabled: None,
+                default_writer_config: None,
+                default_reader_config: None,
+                persistent_message_labels: None,
+                region: None,
+            };
+
+            let err = handle_request::<UpdateHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on room update");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "room_not_found");
+        }
+
+        #[async_std::test]
+        async fn update_room_closed() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Create closed room.
+                shared_helpers::insert_closed_room(&conn)
+            };
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = UpdateRequest {
+                id: room.id(),
+                time: Some((Bound::Included(Utc::now()), Bound::Excluded(Utc::now()))),
+                reserve: Default::default(),
+                tags: Default::default(),
+                classroom_id: Default::default(),
+                lobby_enabled: None,
+                default_writer_config: None,
+                default_reader_config: None,
+                persistent_message_labels: None,
+                region: None,
+            };
+
+            let err = handle_request::<UpdateHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on room update");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "room_closed");
         }
+    }
+
+    mod enter {
+        use chrono::{Duration, Utc};
+
+        use crate::test_helpers::{prelude::*, test_deps::LocalDeps};
+
+        use super::{super::*, DynSubRequest};
 
         #[async_std::test]
-        async fn read_room_not_authorized() {
+        async fn enter_room() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Create room.
+                shared_helpers::insert_room(&conn)
+            };
+
+            // Allow agent to subscribe to the rooms' events.
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+
+            // Make room.enter request.
+            let mut context = TestContext::new(db, authz);
+            let payload = EnterRequest { id: room.id() };
+
+            let messages = handle_request::<EnterHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Room entrance failed");
+
+            // Assert dynamic subscription request.
+            let (payload, reqp, topic) = find_request::<DynSubRequest>(messages.as_slice());
+
+            let expected_topic = format!(
+                "agents/{}.{}/api/{}/out/{}",
+                context.config().agent_label,
+                context.config().id,
+                API_VERSION,
+                context.config().broker_id,
+            );
+
+            assert_eq!(topic, expected_topic);
+            assert_eq!(reqp.method(), "subscription.create");
+            assert_eq!(payload.subject, agent.agent_id().to_owned());
+            assert_eq!(payload.object, vec!["rooms", &room_id, "events"]);
+        }
+
+        #[async_std::test]
+        async fn enter_room_not_authorized() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
@@ -655,57 +2223,115 @@ mod test {
             };
 
             let mut context = TestContext::new(db, TestAuthz::new());
-            let payload = ReadRequest { id: room.id() };
+            let payload = EnterRequest { id: room.id() };
 
-            let err = handle_request::<ReadHandler>(&mut context, &agent, payload)
+            let err = handle_request::<EnterHandler>(&mut context, &agent, payload)
                 .await
-                .expect_err("Unexpected success on room reading");
+                .expect_err("Unexpected success on room entering");
 
             assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
             assert_eq!(err.kind(), "access_denied");
         }
 
         #[async_std::test]
-        async fn read_room_missing() {
+        async fn enter_room_banned() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let moderator = TestAgent::new("web", "moderator", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Create room and ban the agent in it.
+                let room = shared_helpers::insert_room(&conn);
+
+                db::room_ban::InsertQuery::new(room.id(), agent.account_id(), moderator.agent_id())
+                    .execute(&conn)
+                    .expect("Failed to insert room ban");
+
+                room
+            };
+
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+
+            let mut context = TestContext::new(db, authz);
+            let payload = EnterRequest { id: room.id() };
+
+            let err = handle_request::<EnterHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on room entering");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "agent_banned");
+        }
+
+        #[async_std::test]
+        async fn enter_room_missing() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
 
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
             let mut context = TestContext::new(db, TestAuthz::new());
-            let payload = ReadRequest {
+            let payload = EnterRequest {
                 id: db::room::Id::random(),
             };
 
-            let err = handle_request::<ReadHandler>(&mut context, &agent, payload)
+            let err = handle_request::<EnterHandler>(&mut context, &agent, payload)
                 .await
-                .expect_err("Unexpected success on room reading");
+                .expect_err("Unexpected success on room entering");
 
             assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
             assert_eq!(err.kind(), "room_not_found");
         }
-    }
 
-    mod update {
-        use std::ops::Bound;
+        #[async_std::test]
+        async fn enter_room_closed() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
 
-        use chrono::{Duration, SubsecRound, Utc};
-        use serde_json::json;
-        use uuid::Uuid;
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
 
-        use crate::{
-            db::room::Object as Room,
-            test_helpers::{find_event_by_predicate, prelude::*, test_deps::LocalDeps},
-        };
+                // Create closed room.
+                shared_helpers::insert_closed_room(&conn)
+            };
 
-        use super::super::*;
+            // Allow agent to subscribe to the rooms' events.
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+
+            // Make room.enter request.
+            let mut context = TestContext::new(db, authz);
+            let payload = EnterRequest { id: room.id() };
+
+            let err = handle_request::<EnterHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on room entering");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "room_closed");
+        }
 
         #[async_std::test]
-        async fn update_room() {
+        async fn enter_room_with_no_opening_time() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
-            let now = Utc::now().trunc_subsecs(0);
 
             let room = {
                 let conn = db
@@ -713,62 +2339,75 @@ mod test {
                     .get()
                     .expect("Failed to get DB connection");
 
-                // Create room.
+                // Create room without time.
                 factory::Room::new()
                     .audience(USR_AUDIENCE)
                     .time((Bound::Unbounded, Bound::Unbounded))
-                    .rtc_sharing_policy(db::rtc::SharingPolicy::Shared)
                     .insert(&conn)
             };
 
-            // Allow agent to update the room.
+            // Allow agent to subscribe to the rooms' events.
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
             let mut authz = TestAuthz::new();
             let room_id = room.id().to_string();
-            authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
 
-            // Make room.update request.
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+
+            // Make room.enter request.
             let mut context = TestContext::new(db, authz);
-            let classroom_id = Uuid::new_v4();
+            let payload = EnterRequest { id: room.id() };
 
-            let time = (
-                Bound::Included(now + Duration::minutes(50)),
-                Bound::Unbounded,
-            );
+            let err = handle_request::<EnterHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on room entering");
 
-            let payload = UpdateRequest {
-                id: room.id(),
-                time: Some(time),
-                reserve: Some(Some(123)),
-                tags: Some(json!({"foo": "bar"})),
-                classroom_id: Some(classroom_id),
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "room_closed");
+        }
+
+        #[async_std::test]
+        async fn enter_room_that_opens_in_the_future() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Create room without time.
+                factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((
+                        Bound::Included(Utc::now() + Duration::hours(1)),
+                        Bound::Unbounded,
+                    ))
+                    .insert(&conn)
             };
 
-            let messages = handle_request::<UpdateHandler>(&mut context, &agent, payload)
-                .await
-                .expect("Room update failed");
+            // Allow agent to subscribe to the rooms' events.
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
 
-            // Assert response.
-            let (resp_room, respp, _) = find_response::<Room>(messages.as_slice());
-            assert_eq!(respp.status(), ResponseStatus::OK);
-            assert_eq!(resp_room.id(), room.id());
-            assert_eq!(resp_room.audience(), room.audience());
-            assert_eq!(resp_room.time(), &time);
-            assert_eq!(
-                resp_room.rtc_sharing_policy(),
-                db::rtc::SharingPolicy::Shared
-            );
-            assert_eq!(resp_room.reserve(), Some(123));
-            assert_eq!(resp_room.tags(), &json!({"foo": "bar"}));
-            assert_eq!(resp_room.classroom_id(), Some(classroom_id));
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+
+            // Make room.enter request.
+            let mut context = TestContext::new(db, authz);
+            let payload = EnterRequest { id: room.id() };
+
+            handle_request::<EnterHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Room entrance failed");
         }
 
         #[async_std::test]
-        async fn update_room_with_wrong_time() {
+        async fn enter_room_with_lobby() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
-            let now = Utc::now().trunc_subsecs(0);
 
             let room = {
                 let conn = db
@@ -776,50 +2415,56 @@ mod test {
                     .get()
                     .expect("Failed to get DB connection");
 
-                // Create room.
+                // Create room with the lobby.
                 factory::Room::new()
                     .audience(USR_AUDIENCE)
-                    .time((
-                        Bound::Included(now - Duration::hours(1)),
-                        Bound::Excluded(now + Duration::hours(2)),
-                    ))
-                    .rtc_sharing_policy(db::rtc::SharingPolicy::Shared)
+                    .time((Bound::Included(Utc::now()), Bound::Unbounded))
+                    .lobby_enabled(true)
                     .insert(&conn)
             };
 
-            // Allow agent to update the room.
+            // Allow agent to subscribe to the rooms' events.
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
             let mut authz = TestAuthz::new();
             let room_id = room.id().to_string();
-            authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
 
-            // Make room.update request.
-            let mut context = TestContext::new(db, authz);
-
-            let time = (
-                Bound::Included(now + Duration::hours(3)),
-                Bound::Excluded(now - Duration::hours(2)),
-            );
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
 
-            let payload = UpdateRequest {
-                id: room.id(),
-                time: Some(time),
-                reserve: Some(Some(123)),
-                tags: Some(json!({"foo": "bar"})),
-                classroom_id: None,
-            };
+            // Make room.enter request.
+            let mut context = TestContext::new(db.clone(), authz);
+            let payload = EnterRequest { id: room.id() };
 
-            handle_request::<UpdateHandler>(&mut context, &agent, payload)
+            let messages = handle_request::<EnterHandler>(&mut context, &agent, payload)
                 .await
-                .expect_err("Room update succeeded when it should've failed");
+                .expect("Room entrance failed");
+
+            // Assert there's no dynamic subscription request, just the response.
+            assert_eq!(messages.len(), 1);
+            let (_, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::ACCEPTED);
+
+            // Assert the agent is waiting in the lobby.
+            let conn = db
+                .connection_pool()
+                .get()
+                .expect("Failed to get DB connection");
+
+            let agents = db::agent::ListQuery::new()
+                .room_id(room.id())
+                .agent_id(agent.agent_id())
+                .statuses(&[db::agent::Status::Waiting])
+                .execute(&conn)
+                .expect("Failed to list agents");
+
+            assert_eq!(agents.len(), 1);
         }
 
         #[async_std::test]
-        async fn update_and_close_room() {
+        async fn enter_room_with_lobby_already_admitted() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
-            let now = Utc::now().trunc_subsecs(0);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
             let room = {
                 let conn = db
@@ -827,76 +2472,108 @@ mod test {
                     .get()
                     .expect("Failed to get DB connection");
 
-                // Create room.
-                factory::Room::new()
+                // Create room with the lobby and the agent admitted to it.
+                let room = factory::Room::new()
                     .audience(USR_AUDIENCE)
-                    .time((
-                        Bound::Included(now - Duration::hours(1)),
-                        Bound::Excluded(now + Duration::hours(5)),
-                    ))
-                    .rtc_sharing_policy(db::rtc::SharingPolicy::Shared)
-                    .insert(&conn)
+                    .time((Bound::Included(Utc::now()), Bound::Unbounded))
+                    .lobby_enabled(true)
+                    .insert(&conn);
+
+                shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                room
             };
 
-            // Allow agent to update the room.
-            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            // Allow agent to subscribe to the rooms' events.
             let mut authz = TestAuthz::new();
             let room_id = room.id().to_string();
-            authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
 
-            // Make room.update request.
-            let mut context = TestContext::new(db, authz);
+            // Make room.enter request again as on reconnect.
+            let mut context = TestContext::new(db.clone(), authz);
+            let payload = EnterRequest { id: room.id() };
 
-            let time = (
-                Bound::Included(now - Duration::hours(1)),
-                Bound::Excluded(now - Duration::seconds(5)),
-            );
+            let messages = handle_request::<EnterHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Room entrance failed");
 
-            let payload = UpdateRequest {
-                id: room.id(),
-                time: Some(time),
-                reserve: Some(Some(123)),
-                tags: Default::default(),
-                classroom_id: Default::default(),
+            // Assert dynamic subscription request instead of waiting in the lobby.
+            find_request::<DynSubRequest>(messages.as_slice());
+
+            // Assert the agent is still ready.
+            let conn = db
+                .connection_pool()
+                .get()
+                .expect("Failed to get DB connection");
+
+            let agents = db::agent::ListQuery::new()
+                .room_id(room.id())
+                .agent_id(agent.agent_id())
+                .execute(&conn)
+                .expect("Failed to list agents");
+
+            assert_eq!(agents.len(), 1);
+        }
+    }
+
+    mod leave {
+        use crate::test_helpers::{prelude::*, test_deps::LocalDeps};
+
+        use super::{super::*, DynSubRequest};
+
+        #[async_std::test]
+        async fn leave_room() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Create room.
+                let room = shared_helpers::insert_room(&conn);
+
+                // Put agent online in the room.
+                shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                room
             };
 
-            let messages = handle_request::<UpdateHandler>(&mut context, &agent, payload)
-                .await
-                .expect("Room update failed");
+            // Make room.leave request.
+            let mut context = TestContext::new(db, TestAuthz::new());
+            let payload = LeaveRequest { id: room.id() };
 
-            assert_eq!(messages.len(), 4);
+            let messages = handle_request::<LeaveHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Room leaving failed");
 
-            let (closed_tenant_notification, _, _) =
-                find_event_by_predicate::<JsonValue, _>(messages.as_slice(), |evp, _, topic| {
-                    evp.label() == "room.close" && topic.contains("audiences")
-                })
-                .expect("Failed to find room.close event");
+            // Assert dynamic subscription request.
+            let (payload, reqp, topic) = find_request::<DynSubRequest>(messages.as_slice());
 
-            assert_eq!(
-                closed_tenant_notification
-                    .get("id")
-                    .and_then(|v| v.as_str()),
-                Some(room.id().to_string()).as_deref()
+            let expected_topic = format!(
+                "agents/{}.{}/api/{}/out/{}",
+                context.config().agent_label,
+                context.config().id,
+                API_VERSION,
+                context.config().broker_id,
             );
 
-            let (closed_room_notification, _, _) =
-                find_event_by_predicate::<JsonValue, _>(messages.as_slice(), |evp, _, topic| {
-                    evp.label() == "room.close" && topic.contains("rooms")
-                })
-                .expect("Failed to find room.close event");
+            assert_eq!(topic, expected_topic);
+            assert_eq!(reqp.method(), "subscription.delete");
+            assert_eq!(&payload.subject, agent.agent_id());
 
-            assert_eq!(
-                closed_room_notification.get("id").and_then(|v| v.as_str()),
-                Some(room.id().to_string()).as_deref()
-            );
+            let room_id = room.id().to_string();
+            assert_eq!(payload.object, vec!["rooms", &room_id, "events"]);
         }
 
         #[async_std::test]
-        async fn update_and_close_unbounded_room() {
+        async fn leave_room_while_not_entered() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
-            let now = Utc::now().trunc_subsecs(0);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
             let room = {
                 let conn = db
@@ -904,70 +2581,137 @@ mod test {
                     .get()
                     .expect("Failed to get DB connection");
 
-                // Create room.
-                factory::Room::new()
-                    .audience(USR_AUDIENCE)
-                    .time((Bound::Included(now - Duration::hours(1)), Bound::Unbounded))
-                    .insert(&conn)
+                shared_helpers::insert_room(&conn)
             };
 
-            // Allow agent to update the room.
+            let mut context = TestContext::new(db, TestAuthz::new());
+            let payload = LeaveRequest { id: room.id() };
+
+            let err = handle_request::<LeaveHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on room leaving");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "agent_not_entered_the_room");
+        }
+
+        #[async_std::test]
+        async fn leave_room_missing() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut context = TestContext::new(db, TestAuthz::new());
+            let payload = LeaveRequest {
+                id: db::room::Id::random(),
+            };
+
+            let err = handle_request::<LeaveHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on room leaving");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "room_not_found");
+        }
+    }
+
+    mod admit {
+        use crate::test_helpers::{prelude::*, test_deps::LocalDeps};
+
+        use super::{super::*, DynSubRequest};
+
+        #[async_std::test]
+        async fn admit_agent() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let moderator = TestAgent::new("web", "moderator", USR_AUDIENCE);
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Create room with the lobby and put the agent there.
+                let room = factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((Bound::Included(Utc::now()), Bound::Unbounded))
+                    .lobby_enabled(true)
+                    .insert(&conn);
+
+                factory::Agent::new()
+                    .agent_id(agent.agent_id())
+                    .room_id(room.id())
+                    .status(db::agent::Status::Waiting)
+                    .insert(&conn);
+
+                room
+            };
+
+            // Allow moderator to update the room.
             let mut authz = TestAuthz::new();
             let room_id = room.id().to_string();
-            authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
+            authz.allow(moderator.account_id(), vec!["rooms", &room_id], "update");
 
-            // Make room.update request.
+            // Make room.admit request.
             let mut context = TestContext::new(db, authz);
 
-            let time = (
-                Bound::Included(now - Duration::hours(1)),
-                Bound::Excluded(now - Duration::seconds(5)),
-            );
-
-            let payload = UpdateRequest {
+            let payload = AdmitRequest {
                 id: room.id(),
-                time: Some(time),
-                reserve: Default::default(),
-                tags: Default::default(),
-                classroom_id: Default::default(),
+                agent_id: agent.agent_id().to_owned(),
             };
 
-            handle_request::<UpdateHandler>(&mut context, &agent, payload)
+            let messages = handle_request::<AdmitHandler>(&mut context, &moderator, payload)
                 .await
-                .expect("Room update failed");
+                .expect("Agent admission failed");
+
+            // Assert dynamic subscription request for the admitted agent.
+            let (payload, reqp, _) = find_request::<DynSubRequest>(messages.as_slice());
+            assert_eq!(reqp.method(), "subscription.create");
+            assert_eq!(&payload.subject, agent.agent_id());
+            assert_eq!(payload.object, vec!["rooms", &room_id, "events"]);
         }
 
         #[async_std::test]
-        async fn update_room_missing() {
+        async fn admit_agent_not_authorized() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
-
+            let moderator = TestAgent::new("web", "moderator", USR_AUDIENCE);
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                shared_helpers::insert_room(&conn)
+            };
+
             let mut context = TestContext::new(db, TestAuthz::new());
 
-            let payload = UpdateRequest {
-                id: db::room::Id::random(),
-                time: Default::default(),
-                reserve: Default::default(),
-                tags: Default::default(),
-                classroom_id: Default::default(),
+            let payload = AdmitRequest {
+                id: room.id(),
+                agent_id: agent.agent_id().to_owned(),
             };
 
-            let err = handle_request::<UpdateHandler>(&mut context, &agent, payload)
+            let err = handle_request::<AdmitHandler>(&mut context, &moderator, payload)
                 .await
-                .expect_err("Unexpected success on room update");
+                .expect_err("Unexpected success on agent admission");
 
-            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
-            assert_eq!(err.kind(), "room_not_found");
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
         }
 
         #[async_std::test]
-        async fn update_room_closed() {
+        async fn admit_agent_not_waiting() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
+            let moderator = TestAgent::new("web", "moderator", USR_AUDIENCE);
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
             let room = {
@@ -976,86 +2720,102 @@ mod test {
                     .get()
                     .expect("Failed to get DB connection");
 
-                // Create closed room.
-                shared_helpers::insert_closed_room(&conn)
+                // Create room and put the agent online.
+                let room = shared_helpers::insert_room(&conn);
+                shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                room
             };
 
-            let mut context = TestContext::new(db, TestAuthz::new());
+            let mut authz = TestAuthz::new();
+            let room_id = room.id().to_string();
+            authz.allow(moderator.account_id(), vec!["rooms", &room_id], "update");
+
+            let mut context = TestContext::new(db, authz);
 
-            let payload = UpdateRequest {
+            let payload = AdmitRequest {
                 id: room.id(),
-                time: Some((Bound::Included(Utc::now()), Bound::Excluded(Utc::now()))),
-                reserve: Default::default(),
-                tags: Default::default(),
-                classroom_id: Default::default(),
+                agent_id: agent.agent_id().to_owned(),
             };
 
-            let err = handle_request::<UpdateHandler>(&mut context, &agent, payload)
+            let err = handle_request::<AdmitHandler>(&mut context, &moderator, payload)
                 .await
-                .expect_err("Unexpected success on room update");
+                .expect_err("Unexpected success on agent admission");
 
             assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
-            assert_eq!(err.kind(), "room_closed");
+            assert_eq!(err.kind(), "agent_not_entered_the_room");
         }
     }
 
-    mod enter {
-        use chrono::{Duration, Utc};
-
-        use crate::test_helpers::{prelude::*, test_deps::LocalDeps};
+    mod apply_defaults {
+        use crate::{
+            db::room::WriterConfigDefaults,
+            test_helpers::{prelude::*, test_deps::LocalDeps},
+        };
 
-        use super::{super::*, DynSubRequest};
+        use super::super::*;
 
         #[async_std::test]
-        async fn enter_room() {
+        async fn apply_defaults() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
+            let moderator = TestAgent::new("web", "moderator", USR_AUDIENCE);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
+            // Insert a room with video off by default and the agent's config with video on.
             let room = {
                 let conn = db
                     .connection_pool()
                     .get()
                     .expect("Failed to get DB connection");
 
-                // Create room.
-                shared_helpers::insert_room(&conn)
+                let room = factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((Bound::Included(Utc::now()), Bound::Unbounded))
+                    .rtc_sharing_policy(RtcSharingPolicy::Owned)
+                    .default_writer_config(WriterConfigDefaults::new(Some(false), None))
+                    .insert(&conn);
+
+                shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+
+                let rtc = factory::Rtc::new(room.id())
+                    .created_by(agent.agent_id().to_owned())
+                    .insert(&conn);
+
+                factory::RtcWriterConfig::new(&rtc)
+                    .send_video(true)
+                    .send_audio(true)
+                    .insert(&conn);
+
+                room
             };
 
-            // Allow agent to subscribe to the rooms' events.
-            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            // Allow moderator to update the room.
             let mut authz = TestAuthz::new();
             let room_id = room.id().to_string();
+            authz.allow(moderator.account_id(), vec!["rooms", &room_id], "update");
 
-            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
-
-            // Make room.enter request.
+            // Make room.apply_defaults request.
             let mut context = TestContext::new(db, authz);
-            let payload = EnterRequest { id: room.id() };
-
-            let messages = handle_request::<EnterHandler>(&mut context, &agent, payload)
-                .await
-                .expect("Room entrance failed");
+            let payload = ApplyDefaultsRequest { id: room.id() };
 
-            // Assert dynamic subscription request.
-            let (payload, reqp, topic) = find_request::<DynSubRequest>(messages.as_slice());
+            let messages =
+                handle_request::<ApplyDefaultsHandler>(&mut context, &moderator, payload)
+                    .await
+                    .expect("Room defaults applying failed");
 
-            let expected_topic = format!(
-                "agents/{}.{}/api/{}/out/{}",
-                context.config().agent_label,
-                context.config().id,
-                API_VERSION,
-                context.config().broker_id,
-            );
+            let (_, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
 
-            assert_eq!(topic, expected_topic);
-            assert_eq!(reqp.method(), "subscription.create");
-            assert_eq!(payload.subject, agent.agent_id().to_owned());
-            assert_eq!(payload.object, vec!["rooms", &room_id, "events"]);
+            // Assert that the video is off while the audio is left untouched.
+            let (state, evp, _) = find_event::<JsonValue>(messages.as_slice());
+            assert_eq!(evp.label(), "agent_writer_config.update");
+            assert_eq!(state["configs"][0]["send_video"], json!(false));
+            assert_eq!(state["configs"][0]["send_audio"], json!(true));
         }
 
         #[async_std::test]
-        async fn enter_room_not_authorized() {
+        async fn apply_defaults_not_authorized() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
@@ -1067,249 +2827,326 @@ mod test {
                     .get()
                     .expect("Failed to get DB connection");
 
-                shared_helpers::insert_room(&conn)
+                factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((Bound::Included(Utc::now()), Bound::Unbounded))
+                    .rtc_sharing_policy(RtcSharingPolicy::Owned)
+                    .insert(&conn)
             };
 
             let mut context = TestContext::new(db, TestAuthz::new());
-            let payload = EnterRequest { id: room.id() };
+            let payload = ApplyDefaultsRequest { id: room.id() };
 
-            let err = handle_request::<EnterHandler>(&mut context, &agent, payload)
+            let err = handle_request::<ApplyDefaultsHandler>(&mut context, &agent, payload)
                 .await
-                .expect_err("Unexpected success on room entering");
+                .expect_err("Unexpected success on room defaults applying");
 
             assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
             assert_eq!(err.kind(), "access_denied");
         }
+    }
 
-        #[async_std::test]
-        async fn enter_room_missing() {
-            let local_deps = LocalDeps::new();
-            let postgres = local_deps.run_postgres();
-            let db = TestDb::with_local_postgres(&postgres);
+    mod create_breakouts {
+        use std::ops::Bound;
 
-            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
-            let mut context = TestContext::new(db, TestAuthz::new());
-            let payload = EnterRequest {
-                id: db::room::Id::random(),
-            };
+        use chrono::{Duration, Utc};
 
-            let err = handle_request::<EnterHandler>(&mut context, &agent, payload)
-                .await
-                .expect_err("Unexpected success on room entering");
+        use crate::{
+            db::room::Object as Room,
+            test_helpers::{find_event_by_predicate, prelude::*, test_deps::LocalDeps},
+        };
 
-            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
-            assert_eq!(err.kind(), "room_not_found");
-        }
+        use super::super::*;
 
         #[async_std::test]
-        async fn enter_room_closed() {
+        async fn create_breakouts() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
+            let classroom_id = Uuid::new_v4();
+            let closed_at = Utc::now() + Duration::hours(1);
 
-            let room = {
+            let parent = {
                 let conn = db
                     .connection_pool()
                     .get()
                     .expect("Failed to get DB connection");
 
-                // Create closed room.
-                shared_helpers::insert_closed_room(&conn)
+                factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((
+                        Bound::Included(Utc::now() - Duration::hours(1)),
+                        Bound::Excluded(closed_at),
+                    ))
+                    .rtc_sharing_policy(db::rtc::SharingPolicy::Owned)
+                    .classroom_id(classroom_id)
+                    .insert(&conn)
             };
 
-            // Allow agent to subscribe to the rooms' events.
+            // Allow agent to update the parent room.
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
             let mut authz = TestAuthz::new();
-            let room_id = room.id().to_string();
-
-            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+            let parent_id = parent.id().to_string();
+            authz.allow(agent.account_id(), vec!["rooms", &parent_id], "update");
 
-            // Make room.enter request.
+            // Make room.create_breakouts request.
             let mut context = TestContext::new(db, authz);
-            let payload = EnterRequest { id: room.id() };
 
-            let err = handle_request::<EnterHandler>(&mut context, &agent, payload)
+            let payload = CreateBreakoutsRequest {
+                id: parent.id(),
+                count: 3,
+            };
+
+            let messages = handle_request::<CreateBreakoutsHandler>(&mut context, &agent, payload)
                 .await
-                .expect_err("Unexpected success on room entering");
+                .expect("Breakout rooms creation failed");
 
-            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
-            assert_eq!(err.kind(), "room_closed");
+            // Assert response.
+            let (breakouts, respp, _) = find_response::<Vec<Room>>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::CREATED);
+            assert_eq!(breakouts.len(), 3);
+
+            for breakout in &breakouts {
+                assert_eq!(breakout.parent_room_id(), Some(parent.id()));
+                assert_eq!(breakout.audience(), USR_AUDIENCE);
+                assert_eq!(breakout.rtc_sharing_policy(), db::rtc::SharingPolicy::Owned);
+                assert_eq!(breakout.classroom_id(), Some(classroom_id));
+                assert_eq!(breakout.time().1, parent.time().1);
+                assert!(!breakout.is_closed());
+            }
+
+            // Assert notification.
+            let (room, _, _) =
+                find_event_by_predicate::<Room, _>(messages.as_slice(), |evp, _, _| {
+                    evp.label() == "room.create"
+                })
+                .expect("Failed to find room.create event");
+
+            assert_eq!(room.parent_room_id(), Some(parent.id()));
         }
 
         #[async_std::test]
-        async fn enter_room_with_no_opening_time() {
+        async fn create_breakouts_of_breakout() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
 
-            let room = {
+            let breakout = {
                 let conn = db
                     .connection_pool()
                     .get()
                     .expect("Failed to get DB connection");
 
-                // Create room without time.
+                let parent = shared_helpers::insert_room(&conn);
+
                 factory::Room::new()
                     .audience(USR_AUDIENCE)
-                    .time((Bound::Unbounded, Bound::Unbounded))
+                    .time((Bound::Included(Utc::now()), Bound::Unbounded))
+                    .parent_room_id(parent.id())
                     .insert(&conn)
             };
 
-            // Allow agent to subscribe to the rooms' events.
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
-            let mut authz = TestAuthz::new();
-            let room_id = room.id().to_string();
-
-            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+            let mut context = TestContext::new(db, TestAuthz::new());
 
-            // Make room.enter request.
-            let mut context = TestContext::new(db, authz);
-            let payload = EnterRequest { id: room.id() };
+            let payload = CreateBreakoutsRequest {
+                id: breakout.id(),
+                count: 2,
+            };
 
-            let err = handle_request::<EnterHandler>(&mut context, &agent, payload)
+            let err = handle_request::<CreateBreakoutsHandler>(&mut context, &agent, payload)
                 .await
-                .expect_err("Unexpected success on room entering");
+                .expect_err("Unexpected success on breakout rooms creation");
 
-            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
-            assert_eq!(err.kind(), "room_closed");
+            assert_eq!(err.status(), ResponseStatus::BAD_REQUEST);
+            assert_eq!(err.kind(), "invalid_payload");
         }
 
         #[async_std::test]
-        async fn enter_room_that_opens_in_the_future() {
+        async fn create_breakouts_not_authorized() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
 
-            let room = {
+            let parent = {
                 let conn = db
                     .connection_pool()
                     .get()
                     .expect("Failed to get DB connection");
 
-                // Create room without time.
-                factory::Room::new()
-                    .audience(USR_AUDIENCE)
-                    .time((
-                        Bound::Included(Utc::now() + Duration::hours(1)),
-                        Bound::Unbounded,
-                    ))
-                    .insert(&conn)
+                shared_helpers::insert_room(&conn)
             };
 
-            // Allow agent to subscribe to the rooms' events.
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
-            let mut authz = TestAuthz::new();
-            let room_id = room.id().to_string();
-
-            authz.allow(agent.account_id(), vec!["rooms", &room_id], "read");
+            let mut context = TestContext::new(db, TestAuthz::new());
 
-            // Make room.enter request.
-            let mut context = TestContext::new(db, authz);
-            let payload = EnterRequest { id: room.id() };
+            let payload = CreateBreakoutsRequest {
+                id: parent.id(),
+                count: 2,
+            };
 
-            handle_request::<EnterHandler>(&mut context, &agent, payload)
+            let err = handle_request::<CreateBreakoutsHandler>(&mut context, &agent, payload)
                 .await
-                .expect("Room entrance failed");
+                .expect_err("Unexpected success on breakout rooms creation");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
         }
     }
 
-    mod leave {
-        use crate::test_helpers::{prelude::*, test_deps::LocalDeps};
+    mod close_breakouts {
+        use std::ops::Bound;
 
-        use super::{super::*, DynSubRequest};
+        use chrono::{Duration, Utc};
+
+        use crate::{
+            db::room::Object as Room,
+            test_helpers::{find_event_by_predicate, prelude::*, test_deps::LocalDeps},
+        };
+
+        use super::super::*;
 
         #[async_std::test]
-        async fn leave_room() {
+        async fn close_breakouts() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
-            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
-            let room = {
+            let (parent, breakout, closed_breakout) = {
                 let conn = db
                     .connection_pool()
                     .get()
                     .expect("Failed to get DB connection");
 
-                // Create room.
-                let room = shared_helpers::insert_room(&conn);
+                let parent = shared_helpers::insert_room(&conn);
 
-                // Put agent online in the room.
-                shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
-                room
-            };
+                let breakout = factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((
+                        Bound::Included(Utc::now() - Duration::minutes(10)),
+                        Bound::Unbounded,
+                    ))
+                    .parent_room_id(parent.id())
+                    .insert(&conn);
 
-            // Make room.leave request.
-            let mut context = TestContext::new(db, TestAuthz::new());
-            let payload = LeaveRequest { id: room.id() };
+                let closed_breakout = factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((
+                        Bound::Included(Utc::now() - Duration::minutes(10)),
+                        Bound::Excluded(Utc::now() - Duration::minutes(5)),
+                    ))
+                    .parent_room_id(parent.id())
+                    .insert(&conn);
 
-            let messages = handle_request::<LeaveHandler>(&mut context, &agent, payload)
-                .await
-                .expect("Room leaving failed");
+                // Unrelated room.
+                shared_helpers::insert_room(&conn);
 
-            // Assert dynamic subscription request.
-            let (payload, reqp, topic) = find_request::<DynSubRequest>(messages.as_slice());
+                (parent, breakout, closed_breakout)
+            };
 
-            let expected_topic = format!(
-                "agents/{}.{}/api/{}/out/{}",
-                context.config().agent_label,
-                context.config().id,
-                API_VERSION,
-                context.config().broker_id,
-            );
+            // Allow agent to update the parent room.
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut authz = TestAuthz::new();
+            let parent_id = parent.id().to_string();
+            authz.allow(agent.account_id(), vec!["rooms", &parent_id], "update");
 
-            assert_eq!(topic, expected_topic);
-            assert_eq!(reqp.method(), "subscription.delete");
-            assert_eq!(&payload.subject, agent.agent_id());
+            // Make room.close_breakouts request.
+            let mut context = TestContext::new(db, authz);
+            let payload = CloseBreakoutsRequest { id: parent.id() };
 
-            let room_id = room.id().to_string();
-            assert_eq!(payload.object, vec!["rooms", &room_id, "events"]);
+            let messages = handle_request::<CloseBreakoutsHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Breakout rooms closing failed");
+
+            // Assert response with the only breakout room that has been open.
+            let (breakouts, respp, _) = find_response::<Vec<Room>>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(breakouts.len(), 1);
+            assert_eq!(breakouts[0].id(), breakout.id());
+            assert!(breakouts[0].is_closed());
+
+            // Assert room.close notification.
+            find_event_by_predicate::<JsonValue, _>(messages.as_slice(), |evp, _, topic| {
+                evp.label() == "room.close"
+                    && topic.ends_with(&format!("rooms/{}/events", breakout.id()))
+            })
+            .expect("Failed to find room.close event");
+
+            // Assert breakouts.closed notification on the parent room's topic.
+            let (event, _, _) = find_event_by_predicate::<BreakoutsClosedEvent, _>(
+                messages.as_slice(),
+                |evp, _, _| evp.label() == "breakouts.closed",
+            )
+            .expect("Failed to find breakouts.closed event");
+
+            assert_eq!(event.parent_room_id, parent.id());
+            assert_eq!(event.breakouts.len(), 1);
+            assert_eq!(event.breakouts[0].id(), breakout.id());
+            assert_ne!(event.breakouts[0].id(), closed_breakout.id());
         }
 
         #[async_std::test]
-        async fn leave_room_while_not_entered() {
+        async fn close_not_opened_breakouts() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
-            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let opened_at = Utc::now() + Duration::hours(1);
 
-            let room = {
+            let (parent, breakout) = {
                 let conn = db
                     .connection_pool()
                     .get()
                     .expect("Failed to get DB connection");
 
-                shared_helpers::insert_room(&conn)
-            };
-
-            let mut context = TestContext::new(db, TestAuthz::new());
-            let payload = LeaveRequest { id: room.id() };
+                // A parent room scheduled in the future with a breakout room.
+                let parent = factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((
+                        Bound::Included(opened_at),
+                        Bound::Excluded(opened_at + Duration::hours(1)),
+                    ))
+                    .insert(&conn);
 
-            let err = handle_request::<LeaveHandler>(&mut context, &agent, payload)
-                .await
-                .expect_err("Unexpected success on room leaving");
+                let breakout = factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((
+                        Bound::Included(opened_at),
+                        Bound::Excluded(opened_at + Duration::hours(1)),
+                    ))
+                    .parent_room_id(parent.id())
+                    .insert(&conn);
 
-            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
-            assert_eq!(err.kind(), "agent_not_entered_the_room");
-        }
+                (parent, breakout)
+            };
 
-        #[async_std::test]
-        async fn leave_room_missing() {
-            let local_deps = LocalDeps::new();
-            let postgres = local_deps.run_postgres();
-            let db = TestDb::with_local_postgres(&postgres);
+            // Allow agent to update the parent room.
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
-            let mut context = TestContext::new(db, TestAuthz::new());
-            let payload = LeaveRequest {
-                id: db::room::Id::random(),
-            };
+            let mut authz = TestAuthz::new();
+            let parent_id = parent.id().to_string();
+            authz.allow(agent.account_id(), vec!["rooms", &parent_id], "update");
 
-            let err = handle_request::<LeaveHandler>(&mut context, &agent, payload)
+            // Make room.close_breakouts request.
+            let mut context = TestContext::new(db, authz);
+            let payload = CloseBreakoutsRequest { id: parent.id() };
+
+            let messages = handle_request::<CloseBreakoutsHandler>(&mut context, &agent, payload)
                 .await
-                .expect_err("Unexpected success on room leaving");
+                .expect("Breakout rooms closing failed");
 
-            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
-            assert_eq!(err.kind(), "room_not_found");
+            // Assert the breakout room is closed without being opened.
+            let (breakouts, respp, _) = find_response::<Vec<Room>>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(breakouts.len(), 1);
+            assert_eq!(breakouts[0].id(), breakout.id());
+            assert!(breakouts[0].is_closed());
+
+            match breakouts[0].time() {
+                (Bound::Included(start), Bound::Excluded(end)) => {
+                    assert_eq!(*end - *start, Duration::seconds(1));
+                    assert!(*end < opened_at);
+                }
+                time => panic!("Unexpected breakout room time: {:?}", time),
+            }
         }
     }
 }
@@ -4,23 +4,35 @@ use async_trait::async_trait;
 use chrono::{Duration, Utc};
 use serde::{Deserialize, Serialize};
 use slog::{o, warn};
-use std::{fmt, ops::Bound};
+use std::{fmt, ops::Bound, result::Result as StdResult};
 use svc_agent::{
     mqtt::{
         IncomingRequestProperties, IntoPublishableMessage, OutgoingResponse, ResponseStatus,
         ShortTermTimingProperties,
     },
-    Addressable,
+    Addressable, AgentId,
 };
 
 use crate::{
     app::{
-        context::Context, endpoint, endpoint::prelude::*, handle_id::HandleId,
+        balancer::{Balancer, Selection},
+        context::Context,
+        endpoint,
+        endpoint::prelude::*,
+        handle_id::HandleId,
         metrics::HistogramExt,
     },
-    backend::janus::{client::create_handle::CreateHandleRequest, JANUS_API_VERSION},
-    db::{self, agent, agent_connection, rtc::SharingPolicy as RtcSharingPolicy},
-    diesel::{Connection, Identifiable},
+    backend::janus::{
+        client::{create_handle::CreateHandleRequest, detach_handle::DetachHandleRequest},
+        JANUS_API_VERSION,
+    },
+    config::BalancerConfig,
+    db::{
+        self, agent, agent_connection, janus_rtc_stream::Kind as StreamKind,
+        room_role::Role as RoomRole, rtc::SharingPolicy as RtcSharingPolicy,
+        rtc_reader_config::Object as RtcReaderConfig, rtc_writer_config::Object as RtcWriterConfig,
+    },
+    diesel::{pg::PgConnection, Connection, Identifiable},
 };
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -174,6 +186,7 @@ impl RequestHandler for ReadHandler {
             db::rtc::FindQuery::new()
                 .id(payload.id)
                 .execute(&conn)?
+                .filter(|rtc| !rtc.is_deleted())
                 .ok_or_else(|| anyhow!("RTC not found"))
                 .error(AppErrorKind::RtcNotFound)
         })
@@ -269,7 +282,187 @@ impl RequestHandler for ListHandler {
 
 ////////////////////////////////////////////////////////////////////////////////
 
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, Deserialize)]
+pub struct DeleteRequest {
+    id: db::rtc::Id,
+    #[serde(default)]
+    keep_recording: bool,
+}
+
+pub struct DeleteHandler;
+
+#[async_trait]
+impl RequestHandler for DeleteHandler {
+    type Payload = DeleteRequest;
+    const ERROR_TITLE: &'static str = "Failed to delete rtc";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        context.add_logger_tags(o!("rtc_id" => payload.id.to_string()));
+        let conn = context.get_conn().await?;
+
+        let (room, rtc) = task::spawn_blocking({
+            let payload_id = payload.id;
+
+            move || {
+                let rtc = db::rtc::FindQuery::new()
+                    .id(payload_id)
+                    .execute(&conn)?
+                    .filter(|rtc| !rtc.is_deleted())
+                    .ok_or_else(|| anyhow!("RTC not found"))
+                    .error(AppErrorKind::RtcNotFound)?;
+
+                let room = helpers::find_room_by_id(
+                    rtc.room_id(),
+                    helpers::RoomTimeRequirement::NotClosed,
+                    &conn,
+                )?;
+
+                Ok::<_, AppError>((room, rtc))
+            }
+        })
+        .await?;
+
+        helpers::add_room_logger_tags(context, &room);
+
+        // The creator may delete its own RTC. Others need to be able to update the room.
+        let maybe_authz_time = if rtc.created_by() != reqp.as_agent_id() {
+            let room_id = room.id().to_string();
+            let object = vec!["rooms", &room_id];
+
+            let authz_time = context
+                .authz()
+                .authorize(room.audience(), reqp, object, "update")
+                .await?;
+
+            context.metrics().observe_auth(authz_time);
+            Some(authz_time)
+        } else {
+            None
+        };
+
+        let conn = context.get_conn().await?;
+        let keep_recording = payload.keep_recording;
+
+        let room_backend_id = room.backend_id().cloned();
+
+        let (rtc, stopped_streams, maybe_backend, connections) = task::spawn_blocking(move || {
+            conn.transaction::<_, AppError, _>(|| {
+                // Stop active streams and disconnect their readers.
+                let streams = db::janus_rtc_stream::ListQuery::new()
+                    .rtc_id(rtc.id())
+                    .active(true)
+                    .execute(&conn)?;
+
+                let mut stopped_streams = Vec::with_capacity(streams.len());
+
+                for rtc_stream in streams {
+                    if let Some(rtc_stream) = db::janus_rtc_stream::stop(rtc_stream.id(), &conn)? {
+                        stopped_streams.push(rtc_stream);
+                    }
+                }
+
+                // Handles of the RTC are created on the room's backend in `rtc.connect`.
+                let maybe_backend = match room_backend_id {
+                    Some(ref backend_id) => db::janus_backend::FindQuery::new()
+                        .id(backend_id)
+                        .execute(&conn)?,
+                    None => None,
+                };
+
+                let connections =
+                    agent_connection::BulkDisconnectByRtcQuery::new(rtc.id()).execute(&conn)?;
+
+                // The recording references the RTC so keep the row hidden in that case.
+                let rtc = if keep_recording {
+                    db::rtc_writer_config::DeleteQuery::new(rtc.id()).execute(&conn)?;
+                    db::rtc_reader_config::BulkDeleteByRtcQuery::new(rtc.id()).execute(&conn)?;
+                    db::rtc::SoftDeleteQuery::new(rtc.id()).execute(&conn)?
+                } else {
+                    db::rtc::DeleteQuery::new(rtc.id()).execute(&conn)?;
+                    rtc
+                };
+
+                Ok((rtc, stopped_streams, maybe_backend, connections))
+            })
+        })
+        .await?;
+
+        // Detach the RTC's handles only since `agent.leave` would also drop the agents
+        // from other RTCs of the room hosted on the same backend. The RTC is already deleted
+        // at this point so a failure is not a reason to fail the request.
+        if let Some(backend) = maybe_backend {
+            match context.janus_clients().get_or_insert(&backend) {
+                Ok(client) => {
+                    let detach_tasks = connections.iter().map(|connection| {
+                        client.detach_handle(DetachHandleRequest {
+                            session_id: backend.session_id(),
+                            handle_id: connection.handle_id(),
+                        })
+                    });
+
+                    for result in futures::future::join_all(detach_tasks).await {
+                        if let Err(err) = result {
+                            warn!(
+                                context.logger(),
+                                "Failed to detach handle on rtc.delete: {:?}", err
+                            );
+                        }
+                    }
+                }
+                Err(err) => {
+                    warn!(
+                        context.logger(),
+                        "Failed to create backend client on rtc.delete: {:?}", err
+                    );
+                }
+            }
+        }
+
+        // Respond and broadcast to the room topic.
+        let mut messages = vec![
+            helpers::build_response(
+                ResponseStatus::OK,
+                rtc.clone(),
+                reqp,
+                context.start_timestamp(),
+                maybe_authz_time,
+            ),
+            helpers::build_notification(
+                "rtc.delete",
+                &format!("rooms/{}/events", room.id()),
+                rtc,
+                reqp,
+                context.start_timestamp(),
+            ),
+        ];
+
+        for rtc_stream in stopped_streams {
+            let event = endpoint::rtc_stream::update_event(
+                room.id(),
+                rtc_stream,
+                context.start_timestamp(),
+            )?;
+
+            messages.push(Box::new(event) as Box<dyn IntoPublishableMessage + Send>);
+        }
+
+        context
+            .metrics()
+            .request_duration
+            .rtc_delete
+            .observe_timestamp(context.start_timestamp());
+
+        Ok(Box::new(stream::from_iter(messages)))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ConnectIntent {
     Read,
@@ -290,6 +483,10 @@ pub struct ConnectRequest {
     id: db::rtc::Id,
     #[serde(default = "ConnectRequest::default_intent")]
     intent: ConnectIntent,
+    #[serde(default)]
+    kind: db::janus_rtc_stream::Kind,
+    /// Preferred backend region for the room if it's not set yet.
+    region: Option<String>,
 }
 
 impl ConnectRequest {
@@ -313,11 +510,30 @@ impl RequestHandler for ConnectHandler {
         context.add_logger_tags(o!(
             "rtc_id" => payload.id.to_string(),
             "intent" => payload.intent.to_string(),
+            "kind" => payload.kind.to_string(),
         ));
         let conn = context.get_conn().await?;
         let payload_id = payload.id;
+        let kind = payload.kind;
+        let intent = payload.intent;
         let room = task::spawn_blocking(move || {
-            helpers::find_room_by_rtc_id(payload_id, helpers::RoomTimeRequirement::Open, &conn)
+            let room = helpers::find_room_by_rtc_id(
+                payload_id,
+                helpers::RoomTimeRequirement::Open,
+                &conn,
+            )?;
+
+            let is_deleted = db::rtc::FindQuery::new()
+                .id(payload_id)
+                .execute(&conn)?
+                .map(|rtc| rtc.is_deleted())
+                .unwrap_or(true);
+
+            if is_deleted {
+                return Err(anyhow!("RTC not found")).error(AppErrorKind::RtcNotFound);
+            }
+
+            Ok::<_, AppError>(room)
         })
         .await?;
         helpers::add_room_logger_tags(context, &room);
@@ -354,6 +570,35 @@ impl RequestHandler for ConnectHandler {
             }
         }
 
+        // Agents granted to speak by a moderator bypass the local room role check for the rest
+        // of their session. Listeners are not allowed to publish otherwise.
+        if payload.intent == ConnectIntent::Write {
+            let conn = context.get_conn().await?;
+
+            task::spawn_blocking({
+                let room = room.clone();
+                let agent_id = reqp.as_agent_id().clone();
+                let allowed_roles = &[RoomRole::Moderator, RoomRole::Speaker];
+
+                move || {
+                    let is_speak_granted = agent::ListQuery::new()
+                        .room_id(room.id())
+                        .agent_id(&agent_id)
+                        .execute(&conn)?
+                        .first()
+                        .map(|agent| agent.speak_granted())
+                        .unwrap_or(false);
+
+                    if !is_speak_granted {
+                        helpers::check_room_role(&room, &agent_id, allowed_roles, &conn)?;
+                    }
+
+                    Ok::<_, AppError>(())
+                }
+            })
+            .await?;
+        }
+
         let rtc_id = payload.id.to_string();
         let room_id = room.id().to_string();
         let object = vec!["rooms", &room_id, "rtcs", &rtc_id];
@@ -368,18 +613,26 @@ impl RequestHandler for ConnectHandler {
             .authorize(room.audience(), reqp, object, action)
             .await?;
         context.metrics().observe_auth(authz_time);
+
         // Choose backend to connect.
         let group = context.config().janus_group.clone();
+        let balancer_config = context.config().balancer.clone();
+        let balancer = context.balancer().clone();
         let conn = context.get_conn().await?;
         let logger = context.logger().clone();
         let room_id = room.id();
+        // The room's default configs are being applied after the backend is selected.
+        let defaults_room = room.clone();
         let backend = task::spawn_blocking(move || {
             // There are 3 cases:
             // 1. Connecting as writer for the first time. There's no `backend_id` in that case.
-            //    Select the most loaded backend that is capable to host the room's reservation.
-            //    If there's no capable backend then select the least loaded and send a warning
-            //    to Sentry. If there are no backends at all then return `no available backends`
-            //    error and also send it to Sentry.
+            //    Select a backend that is capable to host the room's reservation with the
+            //    configured balancer strategy preferring backends of the room's classroom
+            //    if enabled and then the room's region. If there's no capable backend in
+            //    the region then select one in other regions and if there's no capable
+            //    backend at all then select the least loaded. Both cases send a warning to Sentry.
+            //    If there are no backends at all then return `no available backends` error
+            //    and also send it to Sentry.
             // 2. Connecting as reader with existing `backend_id`. Choose it because Janus doesn't
             //    support clustering and it must be the same server that the writer is connected to.
             // 3. Reconnecting as writer with existing `backend_id`. Select it to avoid partitioning
@@ -390,52 +643,40 @@ impl RequestHandler for ConnectHandler {
                     .execute(&conn)?
                     .ok_or_else(|| anyhow!("No backend found for stream"))
                     .error(AppErrorKind::BackendNotFound)?,
-                None => match db::janus_backend::most_loaded(room.id(), group.as_deref(), &conn)? {
-                    Some(backend) => backend,
-                    None => db::janus_backend::least_loaded(room.id(), group.as_deref(), &conn)?
-                        .map(|backend| {
-                            use sentry::protocol::{value::Value, Event, Level};
-                            let backend_id = backend.id().to_string();
-
-                            warn!(logger, "No capable backends to host the reserve; falling back to the least loaded backend: room_id = {}, rtc_id = {}, backend_id = {}", room_id, rtc_id, backend_id);
-
-                            let mut extra = std::collections::BTreeMap::new();
-                            extra.insert(String::from("room_id"), Value::from(room_id.to_string()));
-                            extra.insert(String::from("rtc_id"), Value::from(rtc_id));
-                            extra.insert(String::from("backend_id"), Value::from(backend_id));
-
-                            if let Some(reserve) = room.reserve() {
-                                extra.insert(String::from("reserve"), Value::from(reserve));
-                            }
-
-
-                            sentry::capture_event(Event {
-                                message: Some(String::from("No capable backends to host the reserve; falling back to the least loaded backend")),
-                                level: Level::Warning,
-                                extra,
-                                ..Default::default()
-                            });
-
-                            backend
-                        })
-                        .ok_or_else(|| anyhow!("No available backends"))
-                        .error(AppErrorKind::NoAvailableBackends)?,
-                },
+                None => select_backend(
+                    &balancer,
+                    &balancer_config,
+                    &room,
+                    &rtc_id,
+                    group.as_deref(),
+                    room.region().or_else(|| payload.region.as_deref()),
+                    &logger,
+                    &conn,
+                )?,
             };
 
             // Create recording if a writer connects for the first time.
             if payload.intent == ConnectIntent::Write {
                 conn.transaction::<_, diesel::result::Error, _>(|| {
                     if room.backend_id().is_none() {
+                        // Remember the region inferred from the first writer.
+                        let region = match room.region() {
+                            Some(_) => None,
+                            None => payload.region.as_deref(),
+                        };
+
                         db::room::UpdateQuery::new(room.id())
                             .backend_id(Some(backend.id()))
+                            .region(region)
                             .execute(&conn)?;
                     }
 
-                    let recording = db::recording::FindQuery::new(payload.id).execute(&conn)?;
+                    // Streams of each kind are recorded separately.
+                    let recording =
+                        db::recording::FindQuery::new(payload.id, payload.kind).execute(&conn)?;
 
                     if recording.is_none() {
-                        db::recording::InsertQuery::new(payload.id).execute(&conn)?;
+                        db::recording::InsertQuery::new(payload.id, payload.kind).execute(&conn)?;
                     }
 
                     Ok(())
@@ -453,7 +694,8 @@ impl RequestHandler for ConnectHandler {
             }
 
             Ok::<_, AppError>(backend)
-        }).await?;
+        })
+        .await?;
 
         context.add_logger_tags(o!("backend_id" => backend.id().to_string()));
         let rtc_stream_id = db::janus_rtc_stream::Id::random();
@@ -476,29 +718,68 @@ impl RequestHandler for ConnectHandler {
             "agent_id" => agent_id.to_string(),
         ));
         let handle_id = handle.id;
-        task::spawn_blocking(move || {
-            conn.transaction::<_, AppError, _>(|| {
-                // Find agent in the DB who made the original `rtc.connect` request.
-                let maybe_agent = agent::ListQuery::new()
-                    .agent_id(&agent_id)
-                    .room_id(room_id)
-                    .status(agent::Status::Ready)
-                    .limit(1)
-                    .execute(&conn)?;
-
-                if let Some(agent) = maybe_agent.first() {
-                    // Create agent connection in the DB.
-                    agent_connection::UpsertQuery::new(*agent.id(), payload_id, handle_id)
+        let (rtc_writer_configs_with_rtcs, rtc_reader_configs_with_rtcs) =
+            task::spawn_blocking(move || {
+                conn.transaction::<_, AppError, _>(|| {
+                    // Find agent in the DB who made the original `rtc.connect` request.
+                    let maybe_agent = agent::ListQuery::new()
+                        .agent_id(&agent_id)
+                        .room_id(room_id)
+                        .statuses(&[agent::Status::Ready])
+                        .limit(1)
                         .execute(&conn)?;
 
-                    Ok(())
-                } else {
-                    // Agent may be already gone.
-                    Err(anyhow!("Agent not found")).error(AppErrorKind::AgentNotEnteredTheRoom)
-                }
+                    if let Some(agent) = maybe_agent.first() {
+                        // Create agent connection in the DB.
+                        // Each stream kind gets its own handle so that an agent is able to
+                        // publish or read e.g. camera and screen of the same RTC simultaneously.
+                        agent_connection::UpsertQuery::new(*agent.id(), payload_id, handle_id)
+                            .kind(kind)
+                            .execute(&conn)?;
+
+                        // Configs of newcomers start from the room defaults.
+                        match intent {
+                            ConnectIntent::Write => create_default_writer_config(
+                                &defaults_room,
+                                payload_id,
+                                kind,
+                                &agent_id,
+                                &conn,
+                            )
+                            .map(|configs| (configs, vec![])),
+                            ConnectIntent::Read => create_default_reader_config(
+                                &defaults_room,
+                                payload_id,
+                                &agent_id,
+                                &conn,
+                            )
+                            .map(|configs| (vec![], configs)),
+                        }
+                    } else {
+                        // Agent may be already gone.
+                        Err(anyhow!("Agent not found")).error(AppErrorKind::AgentNotEnteredTheRoom)
+                    }
+                })
             })
-        })
-        .await?;
+            .await?;
+
+        if !rtc_writer_configs_with_rtcs.is_empty() {
+            endpoint::agent_writer_config::update_janus(
+                context,
+                &backend,
+                &rtc_writer_configs_with_rtcs,
+            )
+            .await?;
+        }
+
+        if !rtc_reader_configs_with_rtcs.is_empty() {
+            endpoint::agent_reader_config::update_janus(
+                context,
+                &backend,
+                &rtc_reader_configs_with_rtcs,
+            )
+            .await?;
+        }
 
         // Returning Real-Time connection handle
         let resp = endpoint::rtc::ConnectResponse::unicast(
@@ -527,6 +808,160 @@ impl RequestHandler for ConnectHandler {
     }
 }
 
+fn select_backend(
+    balancer: &Balancer,
+    config: &BalancerConfig,
+    room: &db::room::Object,
+    rtc_id: &str,
+    group: Option<&str>,
+    region: Option<&str>,
+    logger: &slog::Logger,
+    conn: &PgConnection,
+) -> StdResult<db::janus_backend::Object, AppError> {
+    let strategy_kind = config.strategy_kind(group, room.audience());
+    let backends = helpers::load_backends(group, config, conn)?;
+    let preferred = helpers::classroom_backend_ids(room, config, conn)?;
+
+    let reserve = room.reserve().unwrap_or(1);
+
+    let selection =
+        balancer.select_preferred(strategy_kind, &backends, reserve, region, &preferred);
+
+    let (backend, warning) = match selection {
+        Some(Selection::Capable(backend)) => (backend, None),
+        Some(Selection::OutOfRegion(backend)) => (
+            backend,
+            Some("No capable backends in the room's region; falling back to other regions"),
+        ),
+        Some(Selection::Fallback(backend)) => (
+            backend,
+            Some(
+                "No capable backends to host the reserve; falling back to the least loaded backend",
+            ),
+        ),
+        None => {
+            return Err(anyhow!("No available backends")).error(AppErrorKind::NoAvailableBackends)
+        }
+    };
+
+    if let Some(message) = warning {
+        use sentry::protocol::{value::Value, Event, Level};
+        let room_id = room.id().to_string();
+        let backend_id = backend.id().to_string();
+
+        warn!(
+            logger,
+            "{}: room_id = {}, rtc_id = {}, backend_id = {}", message, room_id, rtc_id, backend_id
+        );
+
+        let mut extra = std::collections::BTreeMap::new();
+        extra.insert(String::from("room_id"), Value::from(room_id));
+        extra.insert(String::from("rtc_id"), Value::from(rtc_id));
+        extra.insert(String::from("backend_id"), Value::from(backend_id));
+
+        if let Some(reserve) = room.reserve() {
+            extra.insert(String::from("reserve"), Value::from(reserve));
+        }
+
+        if let Some(region) = region {
+            extra.insert(String::from("region"), Value::from(region));
+        }
+
+        sentry::capture_event(Event {
+            message: Some(String::from(message)),
+            level: Level::Warning,
+            extra,
+            ..Default::default()
+        });
+    }
+
+    db::janus_backend::FindQuery::new()
+        .id(backend.id())
+        .execute(conn)?
+        .ok_or_else(|| anyhow!("No available backends"))
+        .error(AppErrorKind::NoAvailableBackends)
+}
+
+fn create_default_writer_config(
+    room: &db::room::Object,
+    rtc_id: db::rtc::Id,
+    kind: StreamKind,
+    agent_id: &AgentId,
+    conn: &PgConnection,
+) -> StdResult<Vec<(RtcWriterConfig, db::rtc::Object)>, AppError> {
+    let maybe_defaults = room
+        .default_writer_config()
+        .context("Failed to parse room default writer config")
+        .error(AppErrorKind::MessageParsingFailed)?;
+
+    let defaults = match maybe_defaults {
+        Some(defaults) if room.rtc_sharing_policy() == RtcSharingPolicy::Owned => defaults,
+        _ => return Ok(vec![]),
+    };
+
+    let is_new = db::rtc_writer_config::FindQuery::new(rtc_id, kind)
+        .execute(conn)?
+        .is_none();
+
+    if !is_new || !helpers::are_room_defaults_applicable(room, agent_id, conn)? {
+        return Ok(vec![]);
+    }
+
+    db::rtc_writer_config::UpsertQuery::new(rtc_id)
+        .kind(kind)
+        .defaults(&defaults)
+        .execute(conn)?;
+
+    let rtc_writer_configs_with_rtcs =
+        db::rtc_writer_config::ListWithRtcQuery::new(room.id()).execute(conn)?;
+
+    Ok(rtc_writer_configs_with_rtcs)
+}
+
+fn create_default_reader_config(
+    room: &db::room::Object,
+    rtc_id: db::rtc::Id,
+    agent_id: &AgentId,
+    conn: &PgConnection,
+) -> StdResult<Vec<(RtcReaderConfig, db::rtc::Object)>, AppError> {
+    let maybe_defaults = room
+        .default_reader_config()
+        .context("Failed to parse room default reader config")
+        .error(AppErrorKind::MessageParsingFailed)?;
+
+    let defaults = match maybe_defaults {
+        Some(defaults) if room.rtc_sharing_policy() == RtcSharingPolicy::Owned => defaults,
+        _ => return Ok(vec![]),
+    };
+
+    let rtc = db::rtc::FindQuery::new()
+        .id(rtc_id)
+        .execute(conn)?
+        .ok_or_else(|| anyhow!("RTC not found"))
+        .error(AppErrorKind::RtcNotFound)?;
+
+    let is_new = db::rtc_reader_config::FindQuery::new(rtc_id, agent_id)
+        .execute(conn)?
+        .is_none();
+
+    if !is_new
+        || rtc.created_by() == agent_id
+        || !helpers::are_room_defaults_applicable(room, agent_id, conn)?
+        || !helpers::are_room_defaults_applicable(room, rtc.created_by(), conn)?
+    {
+        return Ok(vec![]);
+    }
+
+    db::rtc_reader_config::UpsertQuery::new(rtc_id, agent_id)
+        .defaults(&defaults)
+        .execute(conn)?;
+
+    let rtc_reader_configs_with_rtcs =
+        db::rtc_reader_config::ListWithRtcQuery::new(room.id(), agent_id).execute(conn)?;
+
+    Ok(rtc_reader_configs_with_rtcs)
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 #[cfg(test)]
@@ -857,22 +1292,281 @@ mod test {
             let mut context = TestContext::new(db, authz);
             let payload = ReadRequest { id: rtc.id() };
 
-            let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
-                .await
-                .expect("RTC reading failed");
+            let messages = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                .await
+                .expect("RTC reading failed");
+
+            // Assert response.
+            let (resp_rtc, respp, _) = find_response::<Rtc>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(resp_rtc.room_id(), rtc.room_id());
+        }
+
+        #[async_std::test]
+        async fn read_rtc_not_authorized() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let rtc = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                shared_helpers::insert_rtc(&conn)
+            };
+
+            let mut context = TestContext::new(db, TestAuthz::new());
+            let payload = ReadRequest { id: rtc.id() };
+
+            let err = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on rtc reading");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
+        }
+
+        #[async_std::test]
+        async fn read_rtc_missing() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut context = TestContext::new(db, TestAuthz::new());
+            let payload = ReadRequest {
+                id: db::rtc::Id::random(),
+            };
+
+            let err = handle_request::<ReadHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on rtc reading");
+
+            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
+            assert_eq!(err.kind(), "room_not_found");
+        }
+    }
+
+    mod delete {
+        use serde_json::Value as JsonValue;
+
+        use crate::test_helpers::{find_event_by_predicate, prelude::*, test_deps::LocalDeps};
+
+        use super::super::*;
+
+        #[async_std::test]
+        async fn delete_own_rtc_keeping_recording() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let rtc = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Create the agent's rtc with a recording.
+                let room = shared_helpers::insert_room_with_owned(&conn);
+
+                let rtc = factory::Rtc::new(room.id())
+                    .created_by(agent.agent_id().to_owned())
+                    .insert(&conn);
+
+                shared_helpers::insert_recording(&conn, &rtc);
+                rtc
+            };
+
+            // Make rtc.delete request. No authorization is needed for the creator.
+            let mut context = TestContext::new(db.clone(), TestAuthz::new());
+
+            let payload = DeleteRequest {
+                id: rtc.id(),
+                keep_recording: true,
+            };
+
+            let messages = handle_request::<DeleteHandler>(&mut context, &agent, payload)
+                .await
+                .expect("RTC deletion failed");
+
+            // Assert response.
+            let (_, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+
+            // Assert notification.
+            let (_, evp, topic) = find_event::<JsonValue>(messages.as_slice());
+            assert_eq!(evp.label(), "rtc.delete");
+            assert_eq!(topic, format!("rooms/{}/events", rtc.room_id()));
+
+            // Assert the rtc is hidden but the recording remains.
+            let conn = db
+                .connection_pool()
+                .get()
+                .expect("Failed to get DB connection");
+
+            let rtcs = db::rtc::ListQuery::new()
+                .room_id(rtc.room_id())
+                .execute(&conn)
+                .expect("Failed to list rtcs");
+
+            assert!(rtcs.is_empty());
+
+            let recording = db::recording::FindQuery::new(rtc.id(), StreamKind::Camera)
+                .execute(&conn)
+                .expect("Failed to find recording");
+
+            assert!(recording.is_some());
+        }
+
+        #[async_std::test]
+        async fn delete_rtc_by_room_updater() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "moderator", USR_AUDIENCE);
+
+            let rtc = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                let rtc = shared_helpers::insert_rtc(&conn);
+                shared_helpers::insert_recording(&conn, &rtc);
+                rtc
+            };
+
+            // Allow agent to update the room.
+            let mut authz = TestAuthz::new();
+            let room_id = rtc.room_id().to_string();
+            authz.allow(agent.account_id(), vec!["rooms", &room_id], "update");
+
+            // Make rtc.delete request.
+            let mut context = TestContext::new(db.clone(), authz);
+
+            let payload = DeleteRequest {
+                id: rtc.id(),
+                keep_recording: false,
+            };
+
+            handle_request::<DeleteHandler>(&mut context, &agent, payload)
+                .await
+                .expect("RTC deletion failed");
+
+            // Assert the rtc is gone along with its recording.
+            let conn = db
+                .connection_pool()
+                .get()
+                .expect("Failed to get DB connection");
+
+            let maybe_rtc = db::rtc::FindQuery::new()
+                .id(rtc.id())
+                .execute(&conn)
+                .expect("Failed to find rtc");
+
+            assert!(maybe_rtc.is_none());
+
+            let recording = db::recording::FindQuery::new(rtc.id(), StreamKind::Camera)
+                .execute(&conn)
+                .expect("Failed to find recording");
+
+            assert!(recording.is_none());
+        }
+
+        #[async_std::test]
+        async fn delete_rtc_with_active_stream() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let janus = local_deps.run_janus();
+            let db = TestDb::with_local_postgres(&postgres);
+            let (session_id, handle_id) = shared_helpers::init_janus(&janus.url).await;
+            let writer = TestAgent::new("web", "writer", USR_AUDIENCE);
+            let reader = TestAgent::new("web", "reader", USR_AUDIENCE);
+
+            let (rtc, backend) = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Insert an rtc with an active stream and a reader connected to it.
+                let backend =
+                    shared_helpers::insert_janus_backend(&conn, &janus.url, session_id, handle_id);
+
+                let room = shared_helpers::insert_room_with_backend_id(&conn, backend.id());
+
+                let rtc = factory::Rtc::new(room.id())
+                    .created_by(writer.agent_id().to_owned())
+                    .insert(&conn);
+
+                let rtc_stream = factory::JanusRtcStream::new(USR_AUDIENCE)
+                    .backend(&backend)
+                    .rtc(&rtc)
+                    .sent_by(writer.agent_id())
+                    .insert(&conn);
+
+                db::janus_rtc_stream::start(rtc_stream.id(), &conn)
+                    .expect("Failed to start rtc stream");
+
+                shared_helpers::insert_agent(&conn, writer.agent_id(), room.id());
+                shared_helpers::insert_connected_agent(
+                    &conn,
+                    reader.agent_id(),
+                    room.id(),
+                    rtc.id(),
+                );
+                (rtc, backend)
+            };
+
+            // Make rtc.delete request.
+            let mut context = TestContext::new(db.clone(), TestAuthz::new());
+            let (tx, _) = async_std::channel::unbounded();
+            context.with_janus(tx);
+
+            let payload = DeleteRequest {
+                id: rtc.id(),
+                keep_recording: true,
+            };
+
+            let messages = handle_request::<DeleteHandler>(&mut context, &writer, payload)
+                .await
+                .expect("RTC deletion failed");
+
+            context.janus_clients().remove_client(backend.id());
+
+            // Assert response.
+            let (_, respp, _) = find_response::<JsonValue>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+
+            // Assert the stream has been stopped.
+            find_event_by_predicate::<JsonValue, _>(messages.as_slice(), |evp, _, _| {
+                evp.label() == "rtc_stream.update"
+            })
+            .expect("Failed to find rtc_stream.update event");
+
+            // Assert the reader has been disconnected.
+            let conn = db
+                .connection_pool()
+                .get()
+                .expect("Failed to get DB connection");
+
+            let connections = agent_connection::ListQuery::new(reader.agent_id(), rtc.id())
+                .execute(&conn)
+                .expect("Failed to list agent connections");
 
-            // Assert response.
-            let (resp_rtc, respp, _) = find_response::<Rtc>(messages.as_slice());
-            assert_eq!(respp.status(), ResponseStatus::OK);
-            assert_eq!(resp_rtc.room_id(), rtc.room_id());
+            assert!(connections.is_empty());
         }
 
         #[async_std::test]
-        async fn read_rtc_not_authorized() {
+        async fn delete_rtc_not_authorized() {
             let local_deps = LocalDeps::new();
             let postgres = local_deps.run_postgres();
             let db = TestDb::with_local_postgres(&postgres);
-
             let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
 
             let rtc = {
@@ -885,34 +1579,18 @@ mod test {
             };
 
             let mut context = TestContext::new(db, TestAuthz::new());
-            let payload = ReadRequest { id: rtc.id() };
-
-            let err = handle_request::<ReadHandler>(&mut context, &agent, payload)
-                .await
-                .expect_err("Unexpected success on rtc reading");
 
-            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
-            assert_eq!(err.kind(), "access_denied");
-        }
-
-        #[async_std::test]
-        async fn read_rtc_missing() {
-            let local_deps = LocalDeps::new();
-            let postgres = local_deps.run_postgres();
-            let db = TestDb::with_local_postgres(&postgres);
-
-            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
-            let mut context = TestContext::new(db, TestAuthz::new());
-            let payload = ReadRequest {
-                id: db::rtc::Id::random(),
+            let payload = DeleteRequest {
+                id: rtc.id(),
+                keep_recording: false,
             };
 
-            let err = handle_request::<ReadHandler>(&mut context, &agent, payload)
+            let err = handle_request::<DeleteHandler>(&mut context, &agent, payload)
                 .await
-                .expect_err("Unexpected success on rtc reading");
+                .expect_err("Unexpected success on rtc deletion");
 
-            assert_eq!(err.status(), ResponseStatus::NOT_FOUND);
-            assert_eq!(err.kind(), "room_not_found");
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
         }
     }
 
@@ -1120,6 +1798,8 @@ mod test {
             let payload = ConnectRequest {
                 id: rtc.id(),
                 intent: ConnectIntent::Read,
+                kind: StreamKind::Camera,
+                region: None,
             };
 
             let messages = handle_request::<ConnectHandler>(&mut context, &agent, payload)
@@ -1179,6 +1859,8 @@ mod test {
             let payload = ConnectRequest {
                 id: rtc.id(),
                 intent: ConnectIntent::Read,
+                kind: StreamKind::Camera,
+                region: None,
             };
 
             let messages = handle_request::<ConnectHandler>(&mut context, &agent, payload)
@@ -1280,6 +1962,8 @@ mod test {
             let payload = ConnectRequest {
                 id: rtc.id(),
                 intent: ConnectIntent::Read,
+                kind: StreamKind::Camera,
+                region: None,
             };
 
             let messages = handle_request::<ConnectHandler>(&mut context, &agent, payload)
@@ -1384,6 +2068,8 @@ mod test {
             let payload = ConnectRequest {
                 id: rtc2.id(),
                 intent: ConnectIntent::Read,
+                kind: StreamKind::Camera,
+                region: None,
             };
 
             // Should be ok since we disregard reserves.
@@ -1407,6 +2093,8 @@ mod test {
             let payload = ConnectRequest {
                 id: rtc1.id(),
                 intent: ConnectIntent::Read,
+                kind: StreamKind::Camera,
+                region: None,
             };
 
             // Expect success.
@@ -1477,6 +2165,8 @@ mod test {
             let payload = ConnectRequest {
                 id: rtc.id(),
                 intent: ConnectIntent::Read,
+                kind: StreamKind::Camera,
+                region: None,
             };
 
             handle_request::<ConnectHandler>(&mut context, &reader, payload)
@@ -1560,6 +2250,8 @@ mod test {
             let payload = ConnectRequest {
                 id: rtc.id(),
                 intent: ConnectIntent::Read,
+                kind: StreamKind::Camera,
+                region: None,
             };
 
             let err = handle_request::<ConnectHandler>(&mut context, &reader2, payload)
@@ -1631,6 +2323,8 @@ mod test {
             let payload = ConnectRequest {
                 id: rtc.id(),
                 intent: ConnectIntent::Write,
+                kind: StreamKind::Camera,
+                region: None,
             };
 
             handle_request::<ConnectHandler>(&mut context, &writer, payload)
@@ -1790,6 +2484,8 @@ mod test {
             let payload = ConnectRequest {
                 id: rtc.id(),
                 intent: ConnectIntent::Write,
+                kind: StreamKind::Camera,
+                region: None,
             };
 
             let messages = handle_request::<ConnectHandler>(&mut context, &new_writer, payload)
@@ -1941,6 +2637,8 @@ mod test {
                 let payload = ConnectRequest {
                     id: rtc.id(),
                     intent: ConnectIntent::Read,
+                    kind: StreamKind::Camera,
+                    region: None,
                 };
 
                 // Make an rtc.connect request.
@@ -1960,6 +2658,8 @@ mod test {
             let payload = ConnectRequest {
                 id: rtcs[2].id(),
                 intent: ConnectIntent::Read,
+                kind: StreamKind::Camera,
+                region: None,
             };
 
             // Last room has NO reserve AND there is free capacity BUT it was exhausted by first two rooms
@@ -2026,6 +2726,8 @@ mod test {
             let payload = ConnectRequest {
                 id: rtc.id(),
                 intent: ConnectIntent::Write,
+                kind: StreamKind::Camera,
+                region: None,
             };
 
             handle_request::<ConnectHandler>(&mut context, &agent, payload)
@@ -2085,6 +2787,8 @@ mod test {
             let payload = ConnectRequest {
                 id: rtc.id(),
                 intent: ConnectIntent::Write,
+                kind: StreamKind::Camera,
+                region: None,
             };
 
             let err = handle_request::<ConnectHandler>(&mut context, &agent, payload)
@@ -2148,6 +2852,8 @@ mod test {
             let payload = ConnectRequest {
                 id: rtc.id(),
                 intent: ConnectIntent::Read,
+                kind: StreamKind::Camera,
+                region: None,
             };
 
             handle_request::<ConnectHandler>(&mut context, &agent, payload)
@@ -2230,6 +2936,8 @@ mod test {
             let payload = ConnectRequest {
                 id: rtc.id(),
                 intent: ConnectIntent::Read,
+                kind: StreamKind::Camera,
+                region: None,
             };
 
             let messages = handle_request::<ConnectHandler>(&mut context, &agent, payload)
@@ -2245,6 +2953,194 @@ mod test {
             assert_ne!(resp.handle_id.janus_handle_id(), handle_id);
         }
 
+        #[async_std::test]
+        async fn connect_to_rtc_in_room_region() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let janus = local_deps.run_janus();
+            let db = TestDb::with_local_postgres(&postgres);
+            let (session_id, handle_id) = shared_helpers::init_janus(&janus.url).await;
+            let mut authz = TestAuthz::new();
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let (rtc, backend) = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Insert two backends in different regions.
+                let backend1_agent = TestAgent::new("alpha", "janus", SVC_AUDIENCE);
+
+                let backend1 = factory::JanusBackend::new(
+                    backend1_agent.agent_id().to_owned(),
+                    handle_id,
+                    session_id,
+                    janus.url.clone(),
+                )
+                .region("eu")
+                .insert(&conn);
+
+                let backend2_agent = TestAgent::new("beta", "janus", SVC_AUDIENCE);
+
+                let backend2 = factory::JanusBackend::new(
+                    backend2_agent.agent_id().to_owned(),
+                    handle_id,
+                    session_id,
+                    janus.url.clone(),
+                )
+                .region("us")
+                .insert(&conn);
+
+                // Add some load to the first backend so it would be chosen otherwise.
+                let room1 = shared_helpers::insert_room_with_backend_id(&conn, backend1.id());
+                let rtc1 = shared_helpers::insert_rtc_with_room(&conn, &room1);
+                let someone = TestAgent::new("web", "user456", USR_AUDIENCE);
+
+                shared_helpers::insert_connected_agent(
+                    &conn,
+                    someone.agent_id(),
+                    rtc1.room_id(),
+                    rtc1.id(),
+                );
+
+                // Insert an RTC to connect to in a room preferring the second region.
+                let room2 = factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((Bound::Included(Utc::now()), Bound::Unbounded))
+                    .rtc_sharing_policy(RtcSharingPolicy::Shared)
+                    .region("us")
+                    .insert(&conn);
+
+                let rtc2 = shared_helpers::insert_rtc_with_room(&conn, &room2);
+                shared_helpers::insert_agent(&conn, agent.agent_id(), room2.id());
+                (rtc2, backend2)
+            };
+
+            // Allow agent to read the RTC.
+            let room_id = rtc.room_id().to_string();
+            let rtc_id = rtc.id().to_string();
+            let object = vec!["rooms", &room_id, "rtcs", &rtc_id];
+            authz.allow(agent.account_id(), object, "read");
+
+            let mut context = TestContext::new(db, authz);
+            let (tx, _) = async_std::channel::unbounded();
+            context.with_janus(tx);
+
+            // Make rtc.connect request.
+            let payload = ConnectRequest {
+                id: rtc.id(),
+                intent: ConnectIntent::Read,
+                kind: StreamKind::Camera,
+                region: None,
+            };
+
+            let messages = handle_request::<ConnectHandler>(&mut context, &agent, payload)
+                .await
+                .expect("RTC connect failed");
+            let (resp, respp, _topic) = find_response::<ConnectResponseData>(messages.as_slice());
+            context.janus_clients().remove_client(backend.id());
+
+            assert_eq!(respp.status(), StatusCode::OK);
+            assert_eq!(resp.handle_id.backend_id(), backend.id());
+        }
+
+        #[async_std::test]
+        async fn connect_to_rtc_in_classroom() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let janus = local_deps.run_janus();
+            let db = TestDb::with_local_postgres(&postgres);
+            let (session_id, handle_id) = shared_helpers::init_janus(&janus.url).await;
+            let mut authz = TestAuthz::new();
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let classroom_id = uuid::Uuid::new_v4();
+
+            let (rtc, backend) = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Insert two backends.
+                let backend1_agent = TestAgent::new("alpha", "janus", SVC_AUDIENCE);
+
+                let backend1 = factory::JanusBackend::new(
+                    backend1_agent.agent_id().to_owned(),
+                    handle_id,
+                    session_id,
+                    janus.url.clone(),
+                )
+                .capacity(100)
+                .insert(&conn);
+
+                let backend2_agent = TestAgent::new("beta", "janus", SVC_AUDIENCE);
+
+                factory::JanusBackend::new(
+                    backend2_agent.agent_id().to_owned(),
+                    handle_id,
+                    session_id,
+                    janus.url.clone(),
+                )
+                .capacity(100)
+                .insert(&conn);
+
+                // Another room of the classroom on the first backend makes it more loaded.
+                factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((Bound::Included(Utc::now()), Bound::Unbounded))
+                    .rtc_sharing_policy(RtcSharingPolicy::Shared)
+                    .reserve(10)
+                    .classroom_id(classroom_id)
+                    .backend_id(backend1.id())
+                    .insert(&conn);
+
+                // Insert an RTC to connect to in a room of the same classroom.
+                let room = factory::Room::new()
+                    .audience(USR_AUDIENCE)
+                    .time((Bound::Included(Utc::now()), Bound::Unbounded))
+                    .rtc_sharing_policy(RtcSharingPolicy::Shared)
+                    .reserve(10)
+                    .classroom_id(classroom_id)
+                    .insert(&conn);
+
+                let rtc = shared_helpers::insert_rtc_with_room(&conn, &room);
+                shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                (rtc, backend1)
+            };
+
+            // Allow agent to read the RTC.
+            let room_id = rtc.room_id().to_string();
+            let rtc_id = rtc.id().to_string();
+            let object = vec!["rooms", &room_id, "rtcs", &rtc_id];
+            authz.allow(agent.account_id(), object, "read");
+
+            // The least loaded backend would be chosen without classroom stickiness.
+            let mut context = TestContext::new(db, authz);
+            context.config_mut().balancer.strategy =
+                crate::config::BalancerStrategyKind::LeastLoaded;
+            context.config_mut().balancer.classroom_sticky = true;
+            let (tx, _) = async_std::channel::unbounded();
+            context.with_janus(tx);
+
+            // Make rtc.connect request.
+            let payload = ConnectRequest {
+                id: rtc.id(),
+                intent: ConnectIntent::Read,
+                kind: StreamKind::Camera,
+                region: None,
+            };
+
+            let messages = handle_request::<ConnectHandler>(&mut context, &agent, payload)
+                .await
+                .expect("RTC connect failed");
+            let (resp, respp, _topic) = find_response::<ConnectResponseData>(messages.as_slice());
+            context.janus_clients().remove_client(backend.id());
+
+            assert_eq!(respp.status(), StatusCode::OK);
+            assert_eq!(resp.handle_id.backend_id(), backend.id());
+        }
+
         #[async_std::test]
         async fn connect_to_rtc_not_authorized() {
             let local_deps = LocalDeps::new();
@@ -2266,6 +3162,110 @@ mod test {
             let payload = ConnectRequest {
                 id: rtc.id(),
                 intent: ConnectIntent::Read,
+                kind: StreamKind::Camera,
+                region: None,
+            };
+
+            let err = handle_request::<ConnectHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on rtc connecting");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
+        }
+
+        #[async_std::test]
+        async fn connect_to_rtc_as_listener_for_writing() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let rtc = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Create an RTC and assign listener role to the agent.
+                let rtc = shared_helpers::insert_rtc(&conn);
+
+                db::room_role::UpsertQuery::new(
+                    rtc.room_id(),
+                    agent.account_id(),
+                    RoomRole::Listener,
+                )
+                .execute(&conn)
+                .expect("Failed to insert room role");
+
+                rtc
+            };
+
+            // Allow agent to update the RTC so the denial comes from the role.
+            let mut authz = TestAuthz::new();
+            let room_id = rtc.room_id().to_string();
+            let rtc_id = rtc.id().to_string();
+            let object = vec!["rooms", &room_id, "rtcs", &rtc_id];
+            authz.allow(agent.account_id(), object, "update");
+
+            let mut context = TestContext::new(db, authz);
+
+            let payload = ConnectRequest {
+                id: rtc.id(),
+                intent: ConnectIntent::Write,
+                kind: StreamKind::Camera,
+                region: None,
+            };
+
+            let err = handle_request::<ConnectHandler>(&mut context, &agent, payload)
+                .await
+                .expect_err("Unexpected success on rtc connecting");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
+        }
+
+        #[async_std::test]
+        async fn connect_to_rtc_speak_granted_not_authorized() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let rtc = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Create an RTC, assign listener role to the agent and grant it speak.
+                let rtc = shared_helpers::insert_rtc(&conn);
+                shared_helpers::insert_agent(&conn, agent.agent_id(), rtc.room_id());
+
+                db::room_role::UpsertQuery::new(
+                    rtc.room_id(),
+                    agent.account_id(),
+                    RoomRole::Listener,
+                )
+                .execute(&conn)
+                .expect("Failed to insert room role");
+
+                agent::UpdateQuery::new(agent.agent_id(), rtc.room_id())
+                    .speak_granted(true)
+                    .execute(&conn)
+                    .expect("Failed to grant speak");
+
+                rtc
+            };
+
+            // The speak grant bypasses the room role but not svc-authz.
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let payload = ConnectRequest {
+                id: rtc.id(),
+                intent: ConnectIntent::Write,
+                kind: StreamKind::Camera,
+                region: None,
             };
 
             let err = handle_request::<ConnectHandler>(&mut context, &agent, payload)
@@ -2288,6 +3288,8 @@ mod test {
             let payload = ConnectRequest {
                 id: db::rtc::Id::random(),
                 intent: ConnectIntent::Read,
+                kind: StreamKind::Camera,
+                region: None,
             };
 
             let err = handle_request::<ConnectHandler>(&mut context, &agent, payload)
@@ -83,7 +83,7 @@ impl RequestHandler for CreateHandler {
 
         // Validate RTC and room presence.
         let conn = context.get_conn().await?;
-        let (room, rtc, backend) = task::spawn_blocking({
+        let (room, rtc, backend, stream_kind) = task::spawn_blocking({
             let agent_id = reqp.as_agent_id().clone();
             let handle_id = payload.handle_id.clone();
             move ||{
@@ -91,6 +91,7 @@ impl RequestHandler for CreateHandler {
             let rtc = db::rtc::FindQuery::new()
                 .id(handle_id.rtc_id())
                 .execute(&conn)?
+                .filter(|rtc| !rtc.is_deleted())
                 .ok_or_else(|| anyhow!("RTC not found"))
                 .error(AppErrorKind::RtcNotFound)?;
 
@@ -123,21 +124,26 @@ impl RequestHandler for CreateHandler {
                     .error(AppErrorKind::InvalidHandleId)?;
             }
 
-            // Validate agent connection and handle id.
-            let agent_connection =
-                db::agent_connection::FindQuery::new(&agent_id, rtc.id())
-                    .execute(&conn)?
-                    .ok_or_else(|| anyhow!("Agent not connected"))
-                    .error(AppErrorKind::AgentNotConnected)?;
+            // Validate agent connection and handle id. The agent may have a connection per
+            // stream kind so the handle determines which one of them is being signalled.
+            let agent_connections =
+                db::agent_connection::ListQuery::new(&agent_id, rtc.id()).execute(&conn)?;
 
-            if handle_id.janus_handle_id() != agent_connection.handle_id() {
-                return Err(anyhow!("Janus handle ID specified in the handle ID doesn't match the one from the agent connection"))
-                    .error(AppErrorKind::InvalidHandleId)?;
+            if agent_connections.is_empty() {
+                return Err(anyhow!("Agent not connected"))
+                    .error(AppErrorKind::AgentNotConnected)?;
             }
 
-            Ok::<_, AppError>((room, rtc, janus_backend))
+            let agent_connection = agent_connections
+                .iter()
+                .find(|c| c.handle_id() == handle_id.janus_handle_id())
+                .ok_or_else(|| anyhow!("Janus handle ID specified in the handle ID doesn't match any of the agent connections"))
+                .error(AppErrorKind::InvalidHandleId)?;
+
+            Ok::<_, AppError>((room, rtc, janus_backend, agent_connection.kind()))
         }}).await?;
         helpers::add_room_logger_tags(context, &room);
+        context.add_logger_tags(o!("rtc_stream_kind" => stream_kind.to_string()));
 
         match payload.jsep {
             Jsep::OfferOrAnswer { kind, ref sdp } => {
@@ -158,6 +164,7 @@ impl RequestHandler for CreateHandler {
                                 body: ReadStreamRequestBody::new(
                                     payload.handle_id.rtc_id(),
                                     reqp.as_agent_id().clone(),
+                                    stream_kind,
                                 ),
                                 handle_id: payload.handle_id.janus_handle_id(),
                                 session_id: payload.handle_id.janus_session_id(),
@@ -210,6 +217,7 @@ impl RequestHandler for CreateHandler {
                                         handle_id.backend_id(),
                                         &label,
                                         &agent_id,
+                                        stream_kind,
                                     )
                                     .execute(&conn)
                                 }
@@ -221,6 +229,7 @@ impl RequestHandler for CreateHandler {
                                 body: CreateStreamRequestBody::new(
                                     payload.handle_id.rtc_id(),
                                     agent_id,
+                                    stream_kind,
                                 ),
                                 handle_id: payload.handle_id.janus_handle_id(),
                                 session_id: payload.handle_id.janus_session_id(),
@@ -23,6 +23,7 @@ const MAX_LIMIT: i64 = 25;
 pub struct ListRequest {
     room_id: db::room::Id,
     rtc_id: Option<db::rtc::Id>,
+    kind: Option<db::janus_rtc_stream::Kind>,
     #[serde(default)]
     #[serde(with = "crate::serde::ts_seconds_option_bound_tuple")]
     time: Option<db::room::Time>,
@@ -79,6 +80,10 @@ impl RequestHandler for ListHandler {
                 query = query.rtc_id(rtc_id);
             }
 
+            if let Some(kind) = payload.kind {
+                query = query.kind(kind);
+            }
+
             if let Some(time) = payload.time {
                 query = query.time(time);
             }
@@ -185,6 +190,7 @@ mod test {
             let payload = ListRequest {
                 room_id: rtc.room_id(),
                 rtc_id: Some(rtc.id()),
+                kind: None,
                 time: None,
                 offset: None,
                 limit: None,
@@ -216,6 +222,63 @@ mod test {
             );
         }
 
+        #[async_std::test]
+        async fn list_rtc_streams_by_kind() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let mut authz = TestAuthz::new();
+
+            let (rtc, screen_stream) = db
+                .connection_pool()
+                .get()
+                .map(|conn| {
+                    // Insert camera and screen streams to the same rtc.
+                    let rtc = shared_helpers::insert_rtc(&conn);
+
+                    factory::JanusRtcStream::new(USR_AUDIENCE)
+                        .rtc(&rtc)
+                        .insert(&conn);
+
+                    let screen_stream = factory::JanusRtcStream::new(USR_AUDIENCE)
+                        .rtc(&rtc)
+                        .kind(db::janus_rtc_stream::Kind::Screen)
+                        .insert(&conn);
+
+                    (rtc, screen_stream)
+                })
+                .expect("Failed to create rtc streams");
+
+            // Allow user to list rtcs in the room.
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let room_id = rtc.room_id().to_string();
+            let object = vec!["rooms", &room_id];
+            authz.allow(agent.account_id(), object, "read");
+
+            // Make rtc_stream.list request.
+            let mut context = TestContext::new(db, authz);
+
+            let payload = ListRequest {
+                room_id: rtc.room_id(),
+                rtc_id: Some(rtc.id()),
+                kind: Some(db::janus_rtc_stream::Kind::Screen),
+                time: None,
+                offset: None,
+                limit: None,
+            };
+
+            let messages = handle_request::<ListHandler>(&mut context, &agent, payload)
+                .await
+                .expect("Rtc streams listing failed");
+
+            // Assert response.
+            let (streams, respp, _) = find_response::<Vec<JanusRtcStream>>(messages.as_slice());
+            assert_eq!(respp.status(), ResponseStatus::OK);
+            assert_eq!(streams.len(), 1);
+            assert_eq!(streams[0].id(), screen_stream.id());
+            assert_eq!(streams[0].kind(), db::janus_rtc_stream::Kind::Screen);
+        }
+
         #[async_std::test]
         async fn list_rtc_streams_not_authorized() {
             let local_deps = LocalDeps::new();
@@ -237,6 +300,7 @@ mod test {
             let payload = ListRequest {
                 room_id: room.id(),
                 rtc_id: None,
+                kind: None,
                 time: None,
                 offset: None,
                 limit: None,
@@ -262,6 +326,7 @@ mod test {
             let payload = ListRequest {
                 room_id: db::room::Id::random(),
                 rtc_id: None,
+                kind: None,
                 time: None,
                 offset: None,
                 limit: None,
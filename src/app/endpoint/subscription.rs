@@ -4,7 +4,7 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use slog::o;
-use std::result::Result as StdResult;
+use std::{collections::HashSet, result::Result as StdResult};
 use svc_agent::{
     mqtt::{
         IncomingEventProperties, IncomingRequestProperties, IncomingResponseProperties,
@@ -14,9 +14,10 @@ use svc_agent::{
 };
 
 use crate::{
-    app::{context::Context, endpoint::prelude::*, metrics::HistogramExt},
+    app::{context::Context, endpoint, endpoint::prelude::*, metrics::HistogramExt},
     backend::janus::client::agent_leave::{AgentLeaveRequest, AgentLeaveRequestBody},
     db,
+    db::room_role::Role as RoomRole,
 };
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -38,15 +39,42 @@ impl CorrelationDataPayload {
     }
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaveReason {
+    Kicked,
+    Banned,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct RoomEnterLeaveEvent {
     id: db::room::Id,
     agent_id: AgentId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reason: Option<LeaveReason>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    role: Option<RoomRole>,
 }
 
 impl RoomEnterLeaveEvent {
     pub fn new(id: db::room::Id, agent_id: AgentId) -> Self {
-        Self { id, agent_id }
+        Self {
+            id,
+            agent_id,
+            reason: None,
+            role: None,
+        }
+    }
+
+    pub fn reason(self, reason: LeaveReason) -> Self {
+        Self {
+            reason: Some(reason),
+            ..self
+        }
+    }
+
+    pub fn role(self, role: Option<RoomRole>) -> Self {
+        Self { role, ..self }
     }
 }
 
@@ -80,15 +108,21 @@ impl ResponseHandler for CreateResponseHandler {
         let room_id = try_room_id(&corr_data.object)?;
         let conn = context.get_conn().await?;
         let subject = corr_data.subject.clone();
-        let room = task::spawn_blocking(move || {
+        let (room, maybe_role) = task::spawn_blocking(move || {
             let room =
                 helpers::find_room_by_id(room_id, helpers::RoomTimeRequirement::NotClosed, &conn)?;
 
-            // Update agent state to `ready`.
-            db::agent::UpdateQuery::new(&subject, room_id)
+            // Update agent state to `ready` and start its attendance session.
+            let maybe_agent = db::agent::UpdateQuery::new(&subject, room_id)
                 .status(db::agent::Status::Ready)
                 .execute(&conn)?;
-            Ok::<_, AppError>(room)
+
+            if maybe_agent.is_some() {
+                db::agent_session::InsertQuery::new(room_id, &subject).execute(&conn)?;
+            }
+
+            let maybe_role = helpers::find_room_role(&room, &subject, &conn)?;
+            Ok::<_, AppError>((room, maybe_role))
         })
         .await?;
         helpers::add_room_logger_tags(context, &room);
@@ -105,7 +139,7 @@ impl ResponseHandler for CreateResponseHandler {
         let notification = helpers::build_notification(
             "room.enter",
             &format!("rooms/{}/events", room_id),
-            RoomEnterLeaveEvent::new(room_id, corr_data.subject.to_owned()),
+            RoomEnterLeaveEvent::new(room_id, corr_data.subject.to_owned()).role(maybe_role),
             &corr_data.reqp,
             context.start_timestamp(),
         );
@@ -136,8 +170,9 @@ impl ResponseHandler for DeleteResponseHandler {
     ) -> Result {
         ensure_broker(context, respp)?;
         let room_id = try_room_id(&corr_data.object)?;
-        let maybe_left = leave_room(context, &corr_data.subject, room_id).await?;
-        if maybe_left {
+        let leave_reason = db::agent_session::LeaveReason::Request;
+        let maybe_left = leave_room(context, &corr_data.subject, room_id, leave_reason).await?;
+        if let Some(mut messages) = maybe_left {
             let response = helpers::build_response(
                 ResponseStatus::OK,
                 json!({}),
@@ -159,13 +194,96 @@ impl ResponseHandler for DeleteResponseHandler {
                 .subscription_delete_response
                 .observe_timestamp(context.start_timestamp());
 
-            Ok(Box::new(stream::from_iter(vec![response, notification])))
+            messages.push(response);
+            messages.push(notification);
+            Ok(Box::new(stream::from_iter(messages)))
         } else {
             Err(anyhow!("The agent is not found")).error(AppErrorKind::AgentNotEnteredTheRoom)
         }
     }
 }
 
+/// Handles broker's response to `subscription.delete` sent on `agent.kick` or `agent.ban`.
+/// The agent has already been removed from the room at that moment so there's nothing to answer.
+pub struct KickResponseHandler;
+
+#[async_trait]
+impl ResponseHandler for KickResponseHandler {
+    type Payload = CreateDeleteResponsePayload;
+    type CorrelationData = CorrelationDataPayload;
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        _payload: Self::Payload,
+        respp: &IncomingResponseProperties,
+        corr_data: &Self::CorrelationData,
+    ) -> Result {
+        ensure_broker(context, respp)?;
+        let room_id = try_room_id(&corr_data.object)?;
+        context.add_logger_tags(o!("room_id" => room_id.to_string()));
+
+        context
+            .metrics()
+            .request_duration
+            .subscription_kick_response
+            .observe_timestamp(context.start_timestamp());
+
+        Ok(Box::new(stream::empty()))
+    }
+}
+
+/// Handles broker's response to `subscription.create` sent on `system.sweep` to check
+/// whether a `ready` agent is still connected. The subscription already exists for connected
+/// agents so the broker confirms it while it rejects the request for a disconnected one.
+/// The agent gets removed from the room in the latter case.
+pub struct SweepResponseHandler;
+
+#[async_trait]
+impl ResponseHandler for SweepResponseHandler {
+    type Payload = CreateDeleteResponsePayload;
+    type CorrelationData = CorrelationDataPayload;
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        _payload: Self::Payload,
+        respp: &IncomingResponseProperties,
+        corr_data: &Self::CorrelationData,
+    ) -> Result {
+        ensure_broker(context, respp)?;
+        let room_id = try_room_id(&corr_data.object)?;
+        context.add_logger_tags(o!("room_id" => room_id.to_string()));
+
+        context
+            .metrics()
+            .request_duration
+            .subscription_sweep_response
+            .observe_timestamp(context.start_timestamp());
+
+        if respp.status().is_success() {
+            return Ok(Box::new(stream::empty()));
+        }
+
+        let leave_reason = db::agent_session::LeaveReason::Sweep;
+
+        if let Some(mut messages) =
+            leave_room(context, &corr_data.subject, room_id, leave_reason).await?
+        {
+            let notification = helpers::build_notification(
+                "room.leave",
+                &format!("rooms/{}/events", room_id),
+                RoomEnterLeaveEvent::new(room_id, corr_data.subject.to_owned()),
+                &corr_data.reqp,
+                context.start_timestamp(),
+            );
+
+            messages.push(notification);
+            Ok(Box::new(stream::from_iter(messages)))
+        } else {
+            Ok(Box::new(stream::empty()))
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct DeleteEventPayload {
     subject: AgentId,
@@ -185,7 +303,11 @@ impl EventHandler for DeleteEventHandler {
     ) -> Result {
         ensure_broker(context, evp)?;
         let room_id = try_room_id(&payload.object)?;
-        if leave_room(context, &payload.subject, room_id).await? {
+        let leave_reason = db::agent_session::LeaveReason::SubscriptionDelete;
+
+        if let Some(mut messages) =
+            leave_room(context, &payload.subject, room_id, leave_reason).await?
+        {
             let outgoing_event_payload =
                 RoomEnterLeaveEvent::new(room_id, payload.subject.to_owned());
             let short_term_timing = ShortTermTimingProperties::until_now(context.start_timestamp());
@@ -199,7 +321,8 @@ impl EventHandler for DeleteEventHandler {
                 .subscription_delete_event
                 .observe_timestamp(context.start_timestamp());
 
-            Ok(Box::new(stream::once(notification)))
+            messages.push(notification);
+            Ok(Box::new(stream::from_iter(messages)))
         } else {
             Ok(Box::new(stream::empty()))
         }
@@ -239,16 +362,19 @@ fn try_room_id(object: &[String]) -> StdResult<db::room::Id, AppError> {
     .error(AppErrorKind::InvalidSubscriptionObject)
 }
 
-async fn leave_room<C: Context>(
+/// Removes the agent from the room and its streams from the backends. Returns `None` if the agent
+/// is not in the room or error responses to pending unicast requests to the agent otherwise.
+pub(super) async fn leave_room<C: Context>(
     context: &mut C,
     agent_id: &AgentId,
     room_id: db::room::Id,
-) -> StdResult<bool, AppError> {
+    leave_reason: db::agent_session::LeaveReason,
+) -> StdResult<Option<Vec<Box<dyn IntoPublishableMessage + Send>>>, AppError> {
     // Delete agent from the DB.
     context.add_logger_tags(o!("room_id" => room_id.to_string()));
 
     let conn = context.get_conn().await?;
-    let backends = task::spawn_blocking({
+    let maybe_left = task::spawn_blocking({
         let agent_id = agent_id.clone();
 
         move || {
@@ -261,6 +387,11 @@ async fn leave_room<C: Context>(
                 return Ok::<_, AppError>(None);
             }
 
+            db::agent_session::CloseQuery::new(room_id, &agent_id, leave_reason).execute(&conn)?;
+
+            // Drop the agent's raised hand if any.
+            db::hand_raise::DeleteQuery::new(room_id, &agent_id).execute(&conn)?;
+
             // `agent.leave` requests to Janus instances that host active streams in this room.
             let streams = db::janus_rtc_stream::ListQuery::new()
                 .room_id(room_id)
@@ -284,23 +415,27 @@ async fn leave_room<C: Context>(
             }
 
             // Send agent.leave requests to those backends where the agent is connected to.
-            let mut backend_ids = streams
+            let backend_ids = streams
                 .iter()
                 .map(|stream| stream.backend_id())
+                .collect::<HashSet<&AgentId>>()
+                .into_iter()
                 .collect::<Vec<&AgentId>>();
 
-            backend_ids.dedup();
-
             let backends = db::janus_backend::ListQuery::new()
                 .ids(&backend_ids[..])
                 .execute(&conn)?;
-            Ok::<_, AppError>(Some(backends))
+
+            let pending_unicasts =
+                db::pending_unicast::delete_by_target(room_id, &agent_id, &conn)?;
+
+            Ok::<_, AppError>(Some((backends, pending_unicasts)))
         }
     })
     .await?;
 
-    match backends {
-        Some(backends) => {
+    match maybe_left {
+        Some((backends, pending_unicasts)) => {
             let mut leave_tasks = Vec::new();
             for backend in backends {
                 let request = AgentLeaveRequest {
@@ -318,9 +453,10 @@ async fn leave_room<C: Context>(
                 .await
                 .error(AppErrorKind::BackendRequestFailed)?;
 
-            Ok(true)
+            let messages = endpoint::message::fail_pending_unicasts(context, &pending_unicasts);
+            Ok(Some(messages))
         }
-        None => Ok(false),
+        None => Ok(None),
     }
 }
 
@@ -424,6 +560,15 @@ mod tests {
 
             let db_agent = db_agents.first().expect("Missing agent in the DB");
             assert_eq!(db_agent.status(), AgentStatus::Ready);
+
+            // Assert attendance session started.
+            let sessions = db::agent_session::ListQuery::new(room.id())
+                .execute(&conn)
+                .expect("Failed to execute agent session list query");
+
+            assert_eq!(sessions.len(), 1);
+            assert_eq!(sessions[0].agent_id(), agent.agent_id());
+            assert!(sessions[0].left_at().is_none());
         }
 
         #[async_std::test]
@@ -528,6 +673,11 @@ mod tests {
 
                 let room = shared_helpers::insert_room(&conn);
                 shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+
+                db::agent_session::InsertQuery::new(room.id(), agent.agent_id())
+                    .execute(&conn)
+                    .expect("Failed to insert agent session");
+
                 room
             };
 
@@ -589,6 +739,19 @@ mod tests {
                 .expect("Failed to execute agent list query");
 
             assert_eq!(db_agents.len(), 0);
+
+            // Assert attendance session closed.
+            let sessions = db::agent_session::ListQuery::new(room.id())
+                .execute(&conn)
+                .expect("Failed to execute agent session list query");
+
+            assert_eq!(sessions.len(), 1);
+            assert!(sessions[0].left_at().is_some());
+
+            assert_eq!(
+                sessions[0].leave_reason(),
+                Some(db::agent_session::LeaveReason::Request)
+            );
         }
 
         #[async_std::test]
@@ -765,4 +928,148 @@ mod tests {
             assert!(messages.is_empty());
         }
     }
+
+    mod sweep_response {
+        use svc_agent::mqtt::ResponseStatus;
+
+        use crate::{
+            db::agent::ListQuery as AgentListQuery,
+            test_helpers::{outgoing_envelope::OutgoingEnvelope, prelude::*, test_deps::LocalDeps},
+        };
+
+        use super::super::*;
+
+        async fn handle_sweep_response(
+            context: &mut TestContext,
+            agent: &TestAgent,
+            room: &db::room::Object,
+            status: ResponseStatus,
+        ) -> Vec<OutgoingEnvelope> {
+            let corr_data = CorrelationDataPayload {
+                reqp: build_reqp(agent.agent_id(), "system.sweep"),
+                subject: agent.agent_id().to_owned(),
+                object: vec![
+                    "rooms".to_string(),
+                    room.id().to_string(),
+                    "events".to_string(),
+                ],
+            };
+
+            let broker_account_label = context.config().broker_id.label();
+            let broker = TestAgent::new("alpha", broker_account_label, SVC_AUDIENCE);
+            let respp = build_respp_with_status(broker.agent_id(), status);
+
+            let messages = SweepResponseHandler::handle(
+                context,
+                CreateDeleteResponsePayload {},
+                &respp,
+                &corr_data,
+            )
+            .await
+            .expect("Subscription sweep failed");
+
+            parse_messages(messages).await
+        }
+
+        #[async_std::test]
+        async fn sweep_connected_agent() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                let room = shared_helpers::insert_room(&conn);
+                shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                room
+            };
+
+            // The broker confirms the subscription.
+            let mut context = TestContext::new(db, TestAuthz::new());
+            let messages =
+                handle_sweep_response(&mut context, &agent, &room, ResponseStatus::OK).await;
+
+            assert!(messages.is_empty());
+
+            // Assert the agent remains in the room.
+            let conn = context
+                .get_conn()
+                .await
+                .expect("Failed to get DB connection");
+
+            let db_agents = AgentListQuery::new()
+                .agent_id(agent.agent_id())
+                .room_id(room.id())
+                .execute(&conn)
+                .expect("Failed to execute agent list query");
+
+            assert_eq!(db_agents.len(), 1);
+        }
+
+        #[async_std::test]
+        async fn sweep_disconnected_agent() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                let room = shared_helpers::insert_room(&conn);
+                shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+
+                db::agent_session::InsertQuery::new(room.id(), agent.agent_id())
+                    .execute(&conn)
+                    .expect("Failed to insert agent session");
+
+                room
+            };
+
+            // The broker rejects the subscription.
+            let mut context = TestContext::new(db, TestAuthz::new());
+
+            let messages =
+                handle_sweep_response(&mut context, &agent, &room, ResponseStatus::NOT_FOUND).await;
+
+            // Assert notification.
+            let (payload, evp, topic) = find_event::<RoomEnterLeaveEvent>(messages.as_slice());
+            assert!(topic.ends_with(&format!("/rooms/{}/events", room.id())));
+            assert_eq!(evp.label(), "room.leave");
+            assert_eq!(&payload.agent_id, agent.agent_id());
+
+            // Assert the agent has been removed and the session closed by the sweep.
+            let conn = context
+                .get_conn()
+                .await
+                .expect("Failed to get DB connection");
+
+            let db_agents = AgentListQuery::new()
+                .agent_id(agent.agent_id())
+                .room_id(room.id())
+                .execute(&conn)
+                .expect("Failed to execute agent list query");
+
+            assert!(db_agents.is_empty());
+
+            let sessions = db::agent_session::ListQuery::new(room.id())
+                .execute(&conn)
+                .expect("Failed to execute agent session list query");
+
+            assert_eq!(sessions.len(), 1);
+
+            assert_eq!(
+                sessions[0].leave_reason(),
+                Some(db::agent_session::LeaveReason::Sweep)
+            );
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, Context as AnyhowContext};
 use async_std::{stream, task};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
@@ -6,21 +6,32 @@ use serde::{Deserialize, Serialize};
 use std::{ops::Bound, result::Result as StdResult};
 use svc_agent::{
     mqtt::{
-        IncomingRequestProperties, OutgoingEvent, OutgoingEventProperties, OutgoingMessage,
-        ShortTermTimingProperties,
+        IncomingRequestProperties, IntoPublishableMessage, OutgoingEvent, OutgoingEventProperties,
+        OutgoingMessage, OutgoingRequest, ShortTermTimingProperties, SubscriptionTopic,
     },
-    AgentId,
+    AgentId, Subscription,
 };
 use svc_authn::Authenticable;
 
 use crate::{
-    app::{context::Context, endpoint::prelude::*, error::Error as AppError},
+    app::{
+        context::Context,
+        endpoint::{
+            prelude::*,
+            room::SubscriptionRequest,
+            subscription::{self, CorrelationDataPayload, RoomEnterLeaveEvent},
+        },
+        error::Error as AppError,
+        metrics::HistogramExt,
+        API_VERSION,
+    },
     backend::janus::client::upload_stream::{
         UploadStreamRequest, UploadStreamRequestBody, UploadStreamTransaction,
     },
     config::UploadConfig,
     db,
     db::{
+        janus_rtc_stream::Kind as StreamKind,
         recording::{Object as Recording, Status as RecordingStatus},
         room::Object as Room,
         rtc::SharingPolicy,
@@ -38,6 +49,7 @@ pub struct RoomUploadEventData {
 #[derive(Debug, Serialize)]
 struct RtcUploadEventData {
     id: db::rtc::Id,
+    kind: StreamKind,
     status: RecordingStatus,
     #[serde(
         serialize_with = "crate::serde::milliseconds_bound_tuples_option",
@@ -107,6 +119,7 @@ impl RequestHandler for VacuumHandler {
             let request = UploadStreamRequest {
                 body: UploadStreamRequestBody::new(
                     recording.rtc_id(),
+                    recording.kind(),
                     &config.backend,
                     &config.bucket,
                     &record_name(&recording, &room),
@@ -116,6 +129,7 @@ impl RequestHandler for VacuumHandler {
             };
             let transaction = UploadStreamTransaction {
                 rtc_id: recording.rtc_id(),
+                kind: recording.kind(),
                 start_timestamp: context.start_timestamp(),
             };
             // TODO: Send the error as an event to "app/${APP}/audiences/${AUD}" topic
@@ -145,6 +159,148 @@ impl RequestHandler for VacuumHandler {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+const SWEEP_BATCH_SIZE: i64 = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct SweepRequest {}
+
+pub struct SweepHandler;
+
+#[async_trait]
+impl RequestHandler for SweepHandler {
+    type Payload = SweepRequest;
+    const ERROR_TITLE: &'static str = "Failed to sweep agents";
+
+    async fn handle<C: Context>(
+        context: &mut C,
+        _payload: Self::Payload,
+        reqp: &IncomingRequestProperties,
+    ) -> Result {
+        // Authorization: only trusted subjects are allowed to perform operations with the system
+        let audience = context.agent_id().as_account_id().audience();
+
+        context
+            .authz()
+            .authorize(audience, reqp, vec!["system"], "update")
+            .await?;
+
+        let agent_timeout = context.config().sweeper.agent_timeout;
+        let threshold = Utc::now() - chrono::Duration::seconds(agent_timeout.as_secs() as i64);
+        let mut messages = Vec::new();
+
+        // The broker has never confirmed the subscription for these ones. Removed agents
+        // disappear from the query so take the first batch until there's nothing left.
+        loop {
+            let conn = context.get_conn().await?;
+
+            let stale_agents = task::spawn_blocking(move || {
+                db::agent::ListQuery::new()
+                    .statuses(&[db::agent::Status::InProgress])
+                    .created_before(threshold)
+                    .limit(SWEEP_BATCH_SIZE)
+                    .execute(&conn)
+            })
+            .await?;
+
+            if stale_agents.is_empty() {
+                break;
+            }
+
+            for agent in stale_agents {
+                let leave_reason = db::agent_session::LeaveReason::Sweep;
+
+                if let Some(unicast_errors) = subscription::leave_room(
+                    context,
+                    agent.agent_id(),
+                    agent.room_id(),
+                    leave_reason,
+                )
+                .await?
+                {
+                    messages.extend(unicast_errors);
+
+                    messages.push(helpers::build_notification(
+                        "room.leave",
+                        &format!("rooms/{}/events", agent.room_id()),
+                        RoomEnterLeaveEvent::new(agent.room_id(), agent.agent_id().to_owned()),
+                        reqp,
+                        context.start_timestamp(),
+                    ));
+                }
+            }
+        }
+
+        // The broker may have dropped subscriptions of `ready` agents without notifying us,
+        // e.g. while we were disconnected. Creating a subscription that already exists is a no-op
+        // while the broker rejects it for a disconnected agent. The responses are being handled
+        // by `subscription::SweepResponseHandler` which removes the rejected agents.
+        let broker_id = AgentId::new("nevermind", context.config().broker_id.to_owned());
+
+        let response_topic = Subscription::unicast_responses_from(&broker_id)
+            .subscription_topic(context.agent_id(), API_VERSION)
+            .context("Failed to build response topic")
+            .error(AppErrorKind::BrokerRequestFailed)?;
+
+        let mut offset = 0;
+
+        loop {
+            let conn = context.get_conn().await?;
+
+            let ready_agents = task::spawn_blocking(move || {
+                db::agent::ListQuery::new()
+                    .created_before(threshold)
+                    .offset(offset)
+                    .limit(SWEEP_BATCH_SIZE)
+                    .execute(&conn)
+            })
+            .await?;
+
+            let batch_size = ready_agents.len() as i64;
+            offset += batch_size;
+
+            for agent in ready_agents {
+                let subject = agent.agent_id().to_owned();
+
+                let object = vec![
+                    String::from("rooms"),
+                    agent.room_id().to_string(),
+                    String::from("events"),
+                ];
+
+                let payload = SubscriptionRequest::new(subject.clone(), object.clone());
+                let corr_data_payload =
+                    CorrelationDataPayload::new(reqp.to_owned(), subject, object);
+
+                let corr_data = CorrelationData::SubscriptionSweep(corr_data_payload)
+                    .dump()
+                    .context("Failed to dump correlation data")
+                    .error(AppErrorKind::BrokerRequestFailed)?;
+
+                let timing = ShortTermTimingProperties::until_now(context.start_timestamp());
+                let props =
+                    reqp.to_request("subscription.create", &response_topic, &corr_data, timing);
+                let to = &context.config().broker_id;
+                let request = OutgoingRequest::multicast(payload, props, to, API_VERSION);
+                messages.push(Box::new(request) as Box<dyn IntoPublishableMessage + Send>);
+            }
+
+            if batch_size < SWEEP_BATCH_SIZE {
+                break;
+            }
+        }
+
+        context
+            .metrics()
+            .request_duration
+            .system_sweep
+            .observe_timestamp(context.start_timestamp());
+
+        Ok(Box::new(stream::from_iter(messages)))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 pub fn upload_event<C: Context, I>(
     context: &C,
     room: &db::room::Object,
@@ -175,6 +331,7 @@ where
 
         let entry = RtcUploadEventData {
             id: recording.rtc_id(),
+            kind: recording.kind(),
             status: recording.status().to_owned(),
             uri,
             segments: recording.segments().to_owned(),
@@ -231,7 +388,11 @@ fn record_name(recording: &Recording, room: &Room) -> String {
         _ => String::from(""),
     };
 
-    format!("{}{}.source.webm", prefix, recording.rtc_id())
+    // Camera recordings keep the name they had before the kinds were introduced.
+    match recording.kind() {
+        StreamKind::Camera => format!("{}{}.source.webm", prefix, recording.rtc_id()),
+        kind => format!("{}{}.{}.source.webm", prefix, recording.rtc_id(), kind),
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -320,6 +481,7 @@ mod test {
                         transaction:
                             Transaction::UploadStream(UploadStreamTransaction {
                                 rtc_id,
+                                kind: StreamKind::Camera,
                                 start_timestamp: _start_timestamp,
                             }),
                         ..
@@ -354,4 +516,168 @@ mod test {
             assert_eq!(err.kind(), "access_denied");
         }
     }
+
+    mod sweep {
+        use serde::Deserialize;
+        use svc_agent::mqtt::ResponseStatus;
+
+        use crate::{
+            app::endpoint::subscription::RoomEnterLeaveEvent,
+            db::agent::{ListQuery as AgentListQuery, Status as AgentStatus},
+            test_helpers::{prelude::*, test_deps::LocalDeps},
+        };
+
+        use super::super::*;
+
+        #[derive(Deserialize)]
+        struct DynSubRequest {
+            subject: AgentId,
+            object: Vec<String>,
+        }
+
+        #[async_std::test]
+        async fn sweep_agents() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let mut authz = TestAuthz::new();
+            authz.set_audience(SVC_AUDIENCE);
+            let stale_agent = TestAgent::new("web", "user1", USR_AUDIENCE);
+            let ready_agent = TestAgent::new("web", "user2", USR_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Insert an agent that has never got the broker's confirmation and a ready one.
+                let room = shared_helpers::insert_room(&conn);
+
+                db::agent::InsertQuery::new(stale_agent.agent_id(), room.id())
+                    .execute(&conn)
+                    .expect("Failed to insert agent");
+
+                shared_helpers::insert_agent(&conn, ready_agent.agent_id(), room.id());
+                room
+            };
+
+            // Allow cron to perform sweep.
+            let agent = TestAgent::new("alpha", "cron", SVC_AUDIENCE);
+            authz.allow(agent.account_id(), vec!["system"], "update");
+
+            // Make system.sweep request.
+            let mut context = TestContext::new(db.clone(), authz);
+
+            let messages = handle_request::<SweepHandler>(&mut context, &agent, SweepRequest {})
+                .await
+                .expect("System sweep failed");
+
+            // Assert the stale agent has left the room.
+            let (event, evp, topic) = find_event::<RoomEnterLeaveEvent>(messages.as_slice());
+            assert_eq!(evp.label(), "room.leave");
+            assert_eq!(topic, format!("rooms/{}/events", room.id()));
+
+            let event = serde_json::to_value(event).expect("Failed to serialize event");
+            assert_eq!(event["agent_id"], stale_agent.agent_id().to_string());
+
+            // Assert the ready agent is being checked against the broker.
+            let (payload, reqp, _) = find_request::<DynSubRequest>(messages.as_slice());
+            assert_eq!(reqp.method(), "subscription.create");
+            assert_eq!(&payload.subject, ready_agent.agent_id());
+            let room_id = room.id().to_string();
+            assert_eq!(payload.object, vec!["rooms", &room_id, "events"]);
+
+            // Assert only the ready agent remains in the DB.
+            let conn = db
+                .connection_pool()
+                .get()
+                .expect("Failed to get DB connection");
+
+            let agents = AgentListQuery::new()
+                .room_id(room.id())
+                .statuses(&[AgentStatus::InProgress, AgentStatus::Ready])
+                .execute(&conn)
+                .expect("Failed to execute agent list query");
+
+            assert_eq!(agents.len(), 1);
+            assert_eq!(agents[0].agent_id(), ready_agent.agent_id());
+        }
+
+        #[async_std::test]
+        async fn sweep_agents_in_batches() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let mut authz = TestAuthz::new();
+            authz.set_audience(SVC_AUDIENCE);
+
+            let room = {
+                let conn = db
+                    .connection_pool()
+                    .get()
+                    .expect("Failed to get DB connection");
+
+                // Insert more stale agents than fit into a single batch.
+                let room = shared_helpers::insert_room(&conn);
+
+                for i in 0..=SWEEP_BATCH_SIZE {
+                    let agent = TestAgent::new("web", &format!("user{}", i), USR_AUDIENCE);
+
+                    db::agent::InsertQuery::new(agent.agent_id(), room.id())
+                        .execute(&conn)
+                        .expect("Failed to insert agent");
+                }
+
+                room
+            };
+
+            // Allow cron to perform sweep.
+            let agent = TestAgent::new("alpha", "cron", SVC_AUDIENCE);
+            authz.allow(agent.account_id(), vec!["system"], "update");
+
+            // Make system.sweep request.
+            let mut context = TestContext::new(db.clone(), authz);
+
+            let messages = handle_request::<SweepHandler>(&mut context, &agent, SweepRequest {})
+                .await
+                .expect("System sweep failed");
+
+            // Assert all the stale agents have left the room.
+            assert_eq!(messages.len() as i64, SWEEP_BATCH_SIZE + 1);
+
+            let conn = db
+                .connection_pool()
+                .get()
+                .expect("Failed to get DB connection");
+
+            let agents = AgentListQuery::new()
+                .room_id(room.id())
+                .statuses(&[AgentStatus::InProgress])
+                .execute(&conn)
+                .expect("Failed to execute agent list query");
+
+            assert!(agents.is_empty());
+        }
+
+        #[async_std::test]
+        async fn sweep_agents_unauthorized() {
+            let local_deps = LocalDeps::new();
+            let postgres = local_deps.run_postgres();
+            let db = TestDb::with_local_postgres(&postgres);
+            let mut authz = TestAuthz::new();
+            authz.set_audience(SVC_AUDIENCE);
+
+            // Make system.sweep request.
+            let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+            let mut context = TestContext::new(db, authz);
+
+            let err = handle_request::<SweepHandler>(&mut context, &agent, SweepRequest {})
+                .await
+                .expect_err("Unexpected success on system sweep");
+
+            assert_eq!(err.status(), ResponseStatus::FORBIDDEN);
+            assert_eq!(err.kind(), "access_denied");
+        }
+    }
 }
@@ -16,6 +16,7 @@ struct ErrorKindProperties {
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, IntoEnumIterator)]
 pub enum ErrorKind {
     AccessDenied,
+    AgentBanned,
     AgentNotConnected,
     AgentNotEnteredTheRoom,
     AuthorizationFailed,
@@ -37,6 +38,7 @@ pub enum ErrorKind {
     InvalidPayload,
     MessageBuildingFailed,
     MessageHandlingFailed,
+    MessageNotFound,
     MessageParsingFailed,
     NoAvailableBackends,
     NotImplemented,
@@ -46,6 +48,10 @@ pub enum ErrorKind {
     RoomNotFound,
     RoomTimeChangingForbidden,
     RtcNotFound,
+    TooManyRequests,
+    UnicastTimeout,
+    UnknownMethod,
+    WriterConfigLocked,
 }
 
 impl ErrorKind {
@@ -86,6 +92,12 @@ impl From<ErrorKind> for ErrorKindProperties {
                 title: "Access denied",
                 is_notify_sentry: false,
             },
+            ErrorKind::AgentBanned => ErrorKindProperties {
+                status: ResponseStatus::FORBIDDEN,
+                kind: "agent_banned",
+                title: "Agent banned from the room",
+                is_notify_sentry: false,
+            },
             ErrorKind::AgentNotConnected => ErrorKindProperties {
                 status: ResponseStatus::UNPROCESSABLE_ENTITY,
                 kind: "agent_not_connected",
@@ -212,6 +224,12 @@ impl From<ErrorKind> for ErrorKindProperties {
                 title: "Message handling failed",
                 is_notify_sentry: true,
             },
+            ErrorKind::MessageNotFound => ErrorKindProperties {
+                status: ResponseStatus::NOT_FOUND,
+                kind: "message_not_found",
+                title: "Message not found",
+                is_notify_sentry: false,
+            },
             ErrorKind::MessageParsingFailed => ErrorKindProperties {
                 status: ResponseStatus::BAD_REQUEST,
                 kind: "message_parsing_failed",
@@ -266,6 +284,30 @@ impl From<ErrorKind> for ErrorKindProperties {
                 title: "RTC not found",
                 is_notify_sentry: false,
             },
+            ErrorKind::TooManyRequests => ErrorKindProperties {
+                status: ResponseStatus::TOO_MANY_REQUESTS,
+                kind: "too_many_requests",
+                title: "Too many requests",
+                is_notify_sentry: false,
+            },
+            ErrorKind::UnicastTimeout => ErrorKindProperties {
+                status: ResponseStatus::GATEWAY_TIMEOUT,
+                kind: "unicast_timeout",
+                title: "Unicast request timed out",
+                is_notify_sentry: false,
+            },
+            ErrorKind::UnknownMethod => ErrorKindProperties {
+                status: ResponseStatus::NOT_FOUND,
+                kind: "unknown_method",
+                title: "Unknown method",
+                is_notify_sentry: false,
+            },
+            ErrorKind::WriterConfigLocked => ErrorKindProperties {
+                status: ResponseStatus::FORBIDDEN,
+                kind: "writer_config_locked",
+                title: "Writer config locked",
+                is_notify_sentry: false,
+            },
         }
     }
 }
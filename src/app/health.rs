@@ -0,0 +1,154 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_std::task;
+use serde::Serialize;
+use svc_authz::cache::ConnectionPool as RedisConnectionPool;
+
+use crate::{backend::janus::client_pool::Clients, db::ConnectionPool as Db};
+
+// Pools wait for a connection up to their configured timeout which is too long for a probe.
+const CHECKOUT_TIMEOUT: Duration = Duration::from_secs(1);
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Dependencies state for the readiness probe.
+#[derive(Clone)]
+pub struct Health {
+    db: Db,
+    redis_pool: Option<RedisConnectionPool>,
+    janus_clients: Clients,
+    mqtt_connected: Arc<AtomicBool>,
+    is_stopped: Arc<AtomicBool>,
+}
+
+impl Health {
+    pub fn new(
+        db: Db,
+        redis_pool: Option<RedisConnectionPool>,
+        janus_clients: Clients,
+        is_stopped: Arc<AtomicBool>,
+    ) -> Self {
+        Self {
+            db,
+            redis_pool,
+            janus_clients,
+            mqtt_connected: Arc::new(AtomicBool::new(false)),
+            is_stopped,
+        }
+    }
+
+    pub fn set_mqtt_connected(&self, is_connected: bool) {
+        self.mqtt_connected.store(is_connected, Ordering::SeqCst);
+    }
+
+    pub async fn readiness(&self) -> Readiness {
+        let stopping = self.is_stopped.load(Ordering::SeqCst);
+        let mqtt = self.mqtt_connected.load(Ordering::SeqCst);
+
+        let db = {
+            let db = self.db.clone();
+            task::spawn_blocking(move || db.get_timeout(CHECKOUT_TIMEOUT).is_ok()).await
+        };
+
+        // The connection is being checked with PING on checkout.
+        let redis = match self.redis_pool {
+            Some(ref pool) => {
+                let pool = pool.clone();
+                Some(task::spawn_blocking(move || pool.get_timeout(CHECKOUT_TIMEOUT).is_ok()).await)
+            }
+            None => None,
+        };
+
+        Readiness {
+            ready: !stopping && db && mqtt && redis.unwrap_or(true),
+            stopping,
+            db,
+            mqtt,
+            redis,
+            janus_backends: self.janus_clients.count(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub stopping: bool,
+    pub db: bool,
+    pub mqtt: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redis: Option<bool>,
+    /// Backends having a client. Clients are dropped when the backend goes offline
+    /// or polling its session fails.
+    pub janus_backends: usize,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_helpers::{prelude::*, test_deps::LocalDeps};
+
+    use super::*;
+
+    fn build_health(db: &TestDb, is_stopped: bool) -> Health {
+        let (tx, _) = async_std::channel::unbounded();
+
+        Health::new(
+            db.connection_pool().clone(),
+            None,
+            Clients::new(tx, None),
+            Arc::new(AtomicBool::new(is_stopped)),
+        )
+    }
+
+    #[async_std::test]
+    async fn ready() {
+        let local_deps = LocalDeps::new();
+        let postgres = local_deps.run_postgres();
+        let db = TestDb::with_local_postgres(&postgres);
+
+        let health = build_health(&db, false);
+        health.set_mqtt_connected(true);
+
+        let readiness = health.readiness().await;
+        assert!(readiness.ready);
+        assert!(readiness.db);
+        assert_eq!(readiness.redis, None);
+    }
+
+    #[async_std::test]
+    async fn not_ready_when_stopped() {
+        let local_deps = LocalDeps::new();
+        let postgres = local_deps.run_postgres();
+        let db = TestDb::with_local_postgres(&postgres);
+
+        let health = build_health(&db, true);
+        health.set_mqtt_connected(true);
+
+        let readiness = health.readiness().await;
+        assert!(!readiness.ready);
+        assert!(readiness.stopping);
+        assert!(readiness.db);
+    }
+
+    #[async_std::test]
+    async fn not_ready_when_mqtt_disconnected() {
+        let local_deps = LocalDeps::new();
+        let postgres = local_deps.run_postgres();
+        let db = TestDb::with_local_postgres(&postgres);
+
+        let health = build_health(&db, false);
+        health.set_mqtt_connected(true);
+        health.set_mqtt_connected(false);
+
+        let readiness = health.readiness().await;
+        assert!(!readiness.ready);
+        assert!(!readiness.mqtt);
+        assert!(readiness.db);
+    }
+}
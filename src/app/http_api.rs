@@ -0,0 +1,460 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context as AnyhowContext, Result};
+use async_std::prelude::*;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use slog::{error, o};
+use svc_agent::{
+    mqtt::{Address, IncomingRequestProperties, PublishableMessage, ResponseStatus},
+    AccountId, AgentId,
+};
+use svc_authn::{jose::ConfigMap as AuthnConfig, token::jws_compact::extract};
+use svc_error::Error as SvcError;
+use uuid::Uuid;
+
+use crate::{
+    app::{
+        context::{AppContext, AppMessageContext, Context},
+        endpoint,
+        error::ErrorKind as AppErrorKind,
+        message_handler::{MessageHandler, MessageStream},
+        API_VERSION,
+    },
+    config::HttpApiConfig,
+};
+
+// Agent label of the requests made through the HTTP API.
+const AGENT_LABEL: &str = "http";
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone)]
+struct State {
+    message_handler: Arc<MessageHandler<AppContext>>,
+    authn: Arc<AuthnConfig>,
+}
+
+/// Serves a subset of request handlers over HTTP for ops tooling which can't speak MQTT.
+///
+/// `POST /api/v1/:method` with a bearer token calls the handler of the method with the JSON
+/// request body as its payload. The handler authorizes the token's account the same way
+/// as for MQTT requests. The response payload is being returned in the HTTP response
+/// while notifications are being published to the broker as usual.
+pub async fn start(
+    message_handler: Arc<MessageHandler<AppContext>>,
+    config: HttpApiConfig,
+) -> async_std::io::Result<()> {
+    let state = State {
+        message_handler,
+        authn: Arc::new(config.authn),
+    };
+
+    let mut app = tide::with_state(state);
+    app.at("/api/v1/:method").post(handle_request);
+    app.listen(config.bind_address).await
+}
+
+async fn handle_request(mut req: tide::Request<State>) -> tide::Result {
+    let start_timestamp = Utc::now();
+
+    let authorization = req
+        .header("Authorization")
+        .map(|header| header.last().as_str().to_owned());
+
+    let method = req.param("method")?.to_owned();
+    let body = req.body_string().await?;
+    let state = req.state().clone();
+    let global_context = state.message_handler.global_context();
+    let mut context = AppMessageContext::new(global_context, start_timestamp);
+
+    let (response, notifications) = call_method(
+        &mut context,
+        &state.authn,
+        authorization.as_deref(),
+        &method,
+        &body,
+    )
+    .await?;
+
+    let mut agent = state.message_handler.agent().to_owned();
+
+    for notification in notifications {
+        agent
+            .publish_dump(notification)
+            .map_err(|err| anyhow!("Failed to publish message: {}", err))?;
+    }
+
+    Ok(response)
+}
+
+// Returns the HTTP response and the messages to publish to the broker.
+async fn call_method<C: Context>(
+    context: &mut C,
+    authn: &AuthnConfig,
+    authorization: Option<&str>,
+    method: &str,
+    body: &str,
+) -> Result<(tide::Response, Vec<PublishableMessage>)> {
+    let account_id = match authenticate(authorization, authn) {
+        Ok(account_id) => account_id,
+        Err(err) => {
+            let response = error_response(
+                ResponseStatus::UNAUTHORIZED,
+                "authentication_failed",
+                "Authentication failed",
+                &err.to_string(),
+            );
+
+            return Ok((response, vec![]));
+        }
+    };
+
+    // Methods without parameters may be called with an empty body.
+    let payload = match body.trim() {
+        "" => "{}",
+        body => body,
+    };
+
+    let agent_id = AgentId::new(AGENT_LABEL, account_id);
+    let reqp = build_reqp(
+        &agent_id,
+        context.agent_id(),
+        method,
+        context.start_timestamp(),
+    )?;
+
+    context.add_logger_tags(o!(
+        "agent_label" => agent_id.label().to_owned(),
+        "account_id" => agent_id.as_account_id().label().to_owned(),
+        "audience" => agent_id.as_account_id().audience().to_owned(),
+        "method" => method.to_owned(),
+    ));
+
+    match endpoint::route_http_request(context, payload, &reqp).await {
+        Some(Ok(messages)) => {
+            let address = Address::new(context.agent_id().to_owned(), API_VERSION);
+            split_messages(messages, &address).await
+        }
+        Some(Err(app_error)) => {
+            context.add_logger_tags(o!(
+                "status" => app_error.status().as_u16(),
+                "kind" => app_error.kind().to_owned(),
+            ));
+
+            error!(
+                context.logger(),
+                "Failed to handle HTTP request: {:?}",
+                app_error.source(),
+            );
+
+            app_error.notify_sentry(context.logger());
+
+            let response = error_response(
+                app_error.status(),
+                app_error.kind(),
+                app_error.title(),
+                &app_error.source().to_string(),
+            );
+
+            Ok((response, vec![]))
+        }
+        None => {
+            let kind = AppErrorKind::UnknownMethod;
+
+            let response = error_response(
+                kind.status(),
+                kind.kind(),
+                kind.title(),
+                &format!("Method '{}' is not available over HTTP", method),
+            );
+
+            Ok((response, vec![]))
+        }
+    }
+}
+
+fn authenticate(authorization: Option<&str>, authn: &AuthnConfig) -> Result<AccountId> {
+    let token = authorization
+        .ok_or_else(|| anyhow!("Missing Authorization header"))?
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| anyhow!("Unsupported authorization scheme"))?;
+
+    let claims = extract::decode_jws_compact_with_config::<String>(token, authn)
+        .map_err(|err| anyhow!("Invalid token: {}", err))?
+        .claims;
+
+    Ok(AccountId::new(claims.subject(), claims.audience()))
+}
+
+// Handlers expect properties of an MQTT request so build them the same way the broker does.
+fn build_reqp(
+    agent_id: &AgentId,
+    service_agent_id: &AgentId,
+    method: &str,
+    start_timestamp: DateTime<Utc>,
+) -> Result<IncomingRequestProperties> {
+    let now = start_timestamp.timestamp_millis().to_string();
+
+    let reqp_json = json!({
+        "type": "request",
+        "correlation_data": Uuid::new_v4().to_string(),
+        "agent_id": agent_id,
+        "connection_mode": "default",
+        "connection_version": "v2",
+        "method": method,
+        "response_topic": format!(
+            "agents/{}/api/{}/in/{}",
+            agent_id,
+            API_VERSION,
+            service_agent_id.as_account_id()
+        ),
+        "broker_agent_id": service_agent_id,
+        "broker_timestamp": now,
+        "broker_processing_timestamp": now,
+        "broker_initial_processing_timestamp": now,
+        "tracking_id": format!("{}.{}.{}", Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()),
+        "session_tracking_label": format!(
+            "{}.{} {}.{}",
+            Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()
+        ),
+    });
+
+    serde_json::from_value::<IncomingRequestProperties>(reqp_json)
+        .context("Failed to build request properties")
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// The response payload comes serialized into the envelope along with its properties.
+#[derive(Deserialize)]
+struct ResponseEnvelope {
+    payload: String,
+    properties: ResponseEnvelopeProperties,
+}
+
+#[derive(Deserialize)]
+struct ResponseEnvelopeProperties {
+    status: String,
+}
+
+// Takes the handler's response as the HTTP response and leaves other messages for publishing.
+async fn split_messages(
+    mut messages: MessageStream,
+    address: &Address,
+) -> Result<(tide::Response, Vec<PublishableMessage>)> {
+    let mut response = tide::Response::new(204);
+    let mut notifications = vec![];
+
+    while let Some(message) = messages.next().await {
+        let dump = message
+            .into_dump(address)
+            .map_err(|err| anyhow!("Failed to dump message: {}", err))?;
+
+        match dump {
+            PublishableMessage::Response(_) => {
+                let envelope = serde_json::from_str::<ResponseEnvelope>(dump.payload())
+                    .context("Failed to parse response")?;
+
+                let status = envelope.properties.status;
+
+                let status = status
+                    .parse::<u16>()
+                    .with_context(|| format!("Invalid response status '{}'", status))?;
+
+                response = json_response(status, envelope.payload);
+            }
+            PublishableMessage::Event(_) | PublishableMessage::Request(_) => {
+                notifications.push(dump)
+            }
+        }
+    }
+
+    Ok((response, notifications))
+}
+
+fn error_response(status: ResponseStatus, kind: &str, title: &str, detail: &str) -> tide::Response {
+    let err = SvcError::builder()
+        .status(status)
+        .kind(kind, title)
+        .detail(detail)
+        .build();
+
+    let body = serde_json::to_string(&err).unwrap_or_default();
+    json_response(status.as_u16(), body)
+}
+
+fn json_response(status: u16, body: String) -> tide::Response {
+    tide::Response::builder(status)
+        .body(body)
+        .content_type(tide::http::mime::JSON)
+        .build()
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{json, Value as JsonValue};
+    use svc_authn::{jose::Algorithm, token::jws_compact::TokenBuilder};
+
+    use crate::test_helpers::{prelude::*, test_deps::LocalDeps};
+
+    use super::*;
+
+    fn build_authn() -> AuthnConfig {
+        let authn = json!({
+            SVC_AUDIENCE: {
+                "audience": [USR_AUDIENCE],
+                "algorithm": "ES256",
+                "key": "data/keys/svc.public_key.p8.der.sample",
+            }
+        });
+
+        serde_json::from_value::<AuthnConfig>(authn).expect("Failed to parse authn config")
+    }
+
+    fn build_authorization(agent: &TestAgent) -> String {
+        let key = std::fs::read("data/keys/svc.private_key.p8.der.sample")
+            .expect("Failed to read private key");
+
+        let token = TokenBuilder::new()
+            .issuer(SVC_AUDIENCE)
+            .subject(agent.account_id())
+            .key(Algorithm::ES256, key.as_slice())
+            .build()
+            .expect("Failed to build token");
+
+        format!("Bearer {}", token)
+    }
+
+    async fn parse_body(response: &mut tide::Response) -> JsonValue {
+        let body = response
+            .take_body()
+            .into_string()
+            .await
+            .expect("Failed to read body");
+
+        serde_json::from_str::<JsonValue>(&body).expect("Failed to parse body")
+    }
+
+    #[async_std::test]
+    async fn call_method_unauthenticated() {
+        let local_deps = LocalDeps::new();
+        let postgres = local_deps.run_postgres();
+        let db = TestDb::with_local_postgres(&postgres);
+        let mut context = TestContext::new(db, TestAuthz::new());
+        let authn = build_authn();
+
+        for authorization in &[None, Some("Basic foo"), Some("Bearer invalid")] {
+            let (mut response, notifications) =
+                call_method(&mut context, &authn, *authorization, "rtc.list", "")
+                    .await
+                    .expect("Failed to call method");
+
+            assert_eq!(response.status(), 401);
+            assert!(notifications.is_empty());
+
+            let body = parse_body(&mut response).await;
+            assert_eq!(body["type"], "authentication_failed");
+        }
+    }
+
+    #[async_std::test]
+    async fn call_unknown_method() {
+        let local_deps = LocalDeps::new();
+        let postgres = local_deps.run_postgres();
+        let db = TestDb::with_local_postgres(&postgres);
+        let mut context = TestContext::new(db, TestAuthz::new());
+        let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+        let authorization = build_authorization(&agent);
+
+        // MQTT-only methods are unknown for the HTTP API too.
+        for method in &["rtc.unknown", "rtc.connect"] {
+            let (mut response, notifications) = call_method(
+                &mut context,
+                &build_authn(),
+                Some(&authorization),
+                method,
+                "{}",
+            )
+            .await
+            .expect("Failed to call method");
+
+            assert_eq!(response.status(), 404);
+            assert!(notifications.is_empty());
+
+            let body = response
+                .take_body()
+                .into_string()
+                .await
+                .expect("Failed to read response body");
+
+            let body = serde_json::from_str::<JsonValue>(&body).expect("Failed to parse body");
+            assert_eq!(body["type"], "unknown_method");
+        }
+    }
+
+    #[async_std::test]
+    async fn call_method_response() {
+        let local_deps = LocalDeps::new();
+        let postgres = local_deps.run_postgres();
+        let db = TestDb::with_local_postgres(&postgres);
+
+        let rtc = {
+            let conn = db
+                .connection_pool()
+                .get()
+                .expect("Failed to get DB connection");
+
+            shared_helpers::insert_rtc(&conn)
+        };
+
+        // Allow the agent to read the rtc.
+        let agent = TestAgent::new("web", "user123", USR_AUDIENCE);
+        let mut authz = TestAuthz::new();
+        let room_id = rtc.room_id().to_string();
+        let rtc_id = rtc.id().to_string();
+        let object = vec!["rooms", &room_id, "rtcs", &rtc_id];
+        authz.allow(agent.account_id(), object, "read");
+
+        let mut context = TestContext::new(db, authz);
+        let authorization = build_authorization(&agent);
+        let body = json!({ "id": rtc.id() }).to_string();
+
+        let (mut response, notifications) = call_method(
+            &mut context,
+            &build_authn(),
+            Some(&authorization),
+            "rtc.read",
+            &body,
+        )
+        .await
+        .expect("Failed to call method");
+
+        // Assert the handler's response payload in the body.
+        assert_eq!(response.status(), 200);
+        assert!(notifications.is_empty());
+
+        let body = parse_body(&mut response).await;
+        assert_eq!(body["id"], rtc.id().to_string());
+        assert_eq!(body["room_id"], rtc.room_id().to_string());
+
+        // Assert the handler's error.
+        let body = json!({ "id": crate::db::rtc::Id::random() }).to_string();
+
+        let (mut response, _) = call_method(
+            &mut context,
+            &build_authn(),
+            Some(&authorization),
+            "rtc.read",
+            &body,
+        )
+        .await
+        .expect("Failed to call method");
+
+        assert_eq!(response.status(), 404);
+
+        let body = parse_body(&mut response).await;
+        assert_eq!(body["type"], "rtc_not_found");
+    }
+}
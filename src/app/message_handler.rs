@@ -76,6 +76,19 @@ impl<C: GlobalContext + Sync> MessageHandler<C> {
         }
     }
 
+    pub async fn handle_pending_unicasts(&self) {
+        let mut msg_context = AppMessageContext::new(&self.global_context, Utc::now());
+
+        let messages = endpoint::check_pending_unicasts(&mut msg_context).await;
+
+        if let Err(err) = self.publish_outgoing_messages(messages).await {
+            warn!(
+                msg_context.logger(),
+                "Pending unicasts check error: {:?}", err
+            );
+        }
+    }
+
     async fn report_error(
         msg_context: &mut AppMessageContext<'_, C>,
         message: &Result<IncomingMessage<String>, String>,
@@ -213,6 +226,18 @@ fn error_response(
     reqp: &IncomingRequestProperties,
     start_timestamp: DateTime<Utc>,
 ) -> MessageStream {
+    let boxed_resp = build_error_response(status, kind, title, detail, reqp, start_timestamp);
+    Box::new(stream::once(boxed_resp))
+}
+
+pub fn build_error_response(
+    status: ResponseStatus,
+    kind: &str,
+    title: &str,
+    detail: &str,
+    reqp: &IncomingRequestProperties,
+    start_timestamp: DateTime<Utc>,
+) -> Box<dyn IntoPublishableMessage + Send> {
     let err = SvcError::builder()
         .status(status)
         .kind(kind, title)
@@ -222,8 +247,7 @@ fn error_response(
     let timing = ShortTermTimingProperties::until_now(start_timestamp);
     let props = reqp.to_response(status, timing);
     let resp = OutgoingResponse::unicast(err, props, reqp, API_VERSION);
-    let boxed_resp = Box::new(resp) as Box<dyn IntoPublishableMessage + Send>;
-    Box::new(stream::once(boxed_resp))
+    Box::new(resp) as Box<dyn IntoPublishableMessage + Send>
 }
 
 pub fn publish_message(
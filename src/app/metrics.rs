@@ -31,22 +31,40 @@ fn duration_to_seconds(d: Duration) -> f64 {
 make_static_metric! {
     struct RequestDuration: Histogram {
         "method" => {
+            agent_attendance,
+            agent_ban,
+            agent_grant_speak,
+            agent_kick,
             agent_list,
+            agent_lower_hand,
+            agent_raise_hand,
+            agent_set_role,
             agent_reader_config_read,
             agent_reader_config_update,
             agent_writer_config_read,
             agent_writer_config_update,
+            backend_drain,
+            backend_list,
+            backend_read,
             message_broadcast,
             message_callback,
+            message_delete,
+            message_list,
+            message_multicast,
             message_unicast_request,
             message_unicast_response,
+            room_admit,
+            room_apply_defaults,
+            room_close_breakouts,
             room_create,
+            room_create_breakouts,
             room_enter,
             room_leave,
             room_read,
             room_update,
             rtc_connect,
             rtc_create,
+            rtc_delete,
             rtc_list,
             rtc_read,
             rtc_signal_create,
@@ -57,6 +75,9 @@ make_static_metric! {
             subscription_create,
             subscription_delete_event,
             subscription_delete_response,
+            subscription_kick_response,
+            subscription_sweep_response,
+            system_sweep,
         },
     }
 }
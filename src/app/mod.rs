@@ -19,6 +19,7 @@ use async_std::task;
 use chrono::Utc;
 use context::{AppContext, GlobalContext, JanusTopics};
 use futures::StreamExt;
+use health::Health;
 use message_handler::MessageHandler;
 use prometheus::{Encoder, Registry, TextEncoder};
 use serde_json::json;
@@ -105,16 +106,26 @@ pub async fn run(
         let collect_interval = config.metrics.janus_metrics_collect_interval;
         move || janus_metrics.start_collector(db.clone(), collect_interval)
     });
-    task::spawn(start_metrics_collector(
-        metrics_registry,
-        config.metrics.http.bind_address,
-    ));
 
     // Subscribe to topics
     let janus_topics = subscribe(&mut agent, &agent_id, &config)?;
 
     let (ev_tx, mut ev_rx) = async_std::channel::unbounded();
     let clients = Clients::new(ev_tx, config.janus_group.clone());
+
+    let health = Health::new(
+        db.clone(),
+        redis_pool.clone(),
+        clients.clone(),
+        is_stopped.clone(),
+    );
+
+    task::spawn(start_metrics_collector(
+        metrics_registry,
+        health.clone(),
+        config.metrics.http.bind_address,
+    ));
+
     // Context
     let context = AppContext::new(
         config.clone(),
@@ -133,6 +144,11 @@ pub async fn run(
     // Message handler
     let message_handler = Arc::new(MessageHandler::new(agent.clone(), context));
 
+    // HTTP API
+    if let Some(http_api_config) = config.http_api.clone() {
+        task::spawn(http_api::start(message_handler.clone(), http_api_config));
+    }
+
     let events_task = {
         let message_handler = message_handler.clone();
         async_std::task::spawn(async move {
@@ -151,12 +167,25 @@ pub async fn run(
     let messages_task = async_std::task::spawn({
         let message_handler = message_handler.clone();
         let is_stopped = is_stopped.clone();
+        let health = health.clone();
         async move {
             loop {
                 if is_stopped.load(Ordering::SeqCst) {
                     break;
                 }
                 let message = mq_rx.recv().await.expect("Messages sender must be alive");
+
+                // Track the connection state before spawning to keep the order of notifications.
+                match message {
+                    AgentNotification::Connack(_) | AgentNotification::Reconnection => {
+                        health.set_mqtt_connected(true)
+                    }
+                    AgentNotification::Disconnect | AgentNotification::ConnectionError => {
+                        health.set_mqtt_connected(false)
+                    }
+                    _ => (),
+                }
+
                 let metric_handle = message_handler.global_context().metrics().request_started();
                 let message_handler = message_handler.clone();
                 task::spawn(async move {
@@ -201,9 +230,21 @@ pub async fn run(
         }
     });
 
+    let pending_unicasts_task = async_std::task::spawn({
+        let message_handler = message_handler.clone();
+        let check_interval = config.unicast.check_interval;
+
+        async move {
+            loop {
+                task::sleep(check_interval).await;
+                message_handler.handle_pending_unicasts().await;
+            }
+        }
+    });
+
     let mut signals_stream = signal_hook_async_std::Signals::new(TERM_SIGNALS)?.fuse();
     let signals = signals_stream.next();
-    let app = futures::future::join_all([events_task, messages_task]);
+    let app = futures::future::join_all([events_task, messages_task, pending_unicasts_task]);
     futures::future::select(app, signals).await;
     is_stopped.store(true, Ordering::SeqCst);
     message_handler.global_context().janus_clients().clear();
@@ -282,14 +323,21 @@ fn resubscribe(agent: &mut Agent, agent_id: &AgentId, config: &Config) {
     }
 }
 
+#[derive(Clone)]
+struct MetricsState {
+    registry: Registry,
+    health: Health,
+}
+
 async fn start_metrics_collector(
     registry: Registry,
+    health: Health,
     bind_addr: SocketAddr,
 ) -> async_std::io::Result<()> {
-    let mut app = tide::with_state(registry);
+    let mut app = tide::with_state(MetricsState { registry, health });
     app.at("/metrics")
-        .get(|req: tide::Request<Registry>| async move {
-            let registry = req.state();
+        .get(|req: tide::Request<MetricsState>| async move {
+            let registry = &req.state().registry;
             let mut buffer = vec![];
             let encoder = TextEncoder::new();
             let metric_families = registry.gather();
@@ -305,12 +353,29 @@ async fn start_metrics_collector(
                 }
             }
         });
+    app.at("/healthz")
+        .get(|_req: tide::Request<MetricsState>| async move { Ok(tide::Response::new(200)) });
+    app.at("/readyz")
+        .get(|req: tide::Request<MetricsState>| async move {
+            let readiness = req.state().health.readiness().await;
+            let status: u16 = if readiness.ready { 200 } else { 503 };
+
+            let response = tide::Response::builder(status)
+                .body(tide::Body::from_json(&readiness)?)
+                .build();
+
+            Ok(response)
+        });
     app.listen(bind_addr).await
 }
 
+pub mod balancer;
 pub mod context;
 pub mod endpoint;
 pub mod error;
 pub mod handle_id;
+pub mod health;
+pub mod http_api;
 pub mod message_handler;
 pub mod metrics;
+pub mod rate_limiter;
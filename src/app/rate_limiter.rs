@@ -0,0 +1,260 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
+use svc_agent::AgentId;
+
+use crate::{
+    config::{RateLimitBucketConfig, RateLimitConfig},
+    db,
+};
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum BucketKey {
+    Agent(String, AgentId),
+    Room(String, db::room::Id),
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    updated_at: DateTime<Utc>,
+}
+
+impl TokenBucket {
+    fn new(config: &RateLimitBucketConfig, now: DateTime<Utc>) -> Self {
+        Self {
+            tokens: config.capacity as f64,
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, config: &RateLimitBucketConfig, now: DateTime<Utc>) {
+        let elapsed = (now - self.updated_at).to_std().unwrap_or_default();
+        let period = config.period.as_secs_f64();
+
+        let refilled = if period > 0.0 {
+            config.capacity as f64 * elapsed.as_secs_f64() / period
+        } else {
+            config.capacity as f64
+        };
+
+        self.tokens = (self.tokens + refilled).min(config.capacity as f64);
+        self.updated_at = now;
+    }
+}
+
+/// Which of the limits has been exceeded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Exceeded {
+    Agent,
+    Room,
+}
+
+/// Token buckets for rate limiting requests per agent and per room keyed by method.
+/// Limits themselves are taken from the config on each check.
+///
+/// Buckets live in the memory of each process and are not shared between replicas
+/// so the effective limit is the configured one multiplied by the number of replicas.
+#[derive(Clone, Debug, Default)]
+pub struct RateLimiter {
+    inner: Arc<Mutex<Buckets>>,
+}
+
+#[derive(Debug, Default)]
+struct Buckets {
+    buckets: HashMap<BucketKey, TokenBucket>,
+    purged_at: Option<DateTime<Utc>>,
+}
+
+impl RateLimiter {
+    /// Takes a token from the agent's and the room's buckets for the method if they're configured.
+    /// Nothing gets taken if any of the buckets is empty.
+    pub fn check(
+        &self,
+        config: &RateLimitConfig,
+        method: &str,
+        agent_id: &AgentId,
+        room_id: Option<db::room::Id>,
+        now: DateTime<Utc>,
+    ) -> Result<(), Exceeded> {
+        let method_config = match config.get(method) {
+            Some(method_config) => method_config,
+            None => return Ok(()),
+        };
+
+        let mut guard = self.inner.lock().expect("Must not panic");
+        guard.purge(config, now);
+
+        let mut limits = vec![];
+
+        if let Some(agent_config) = &method_config.agent {
+            let key = BucketKey::Agent(method.to_owned(), agent_id.to_owned());
+            limits.push((key, agent_config, Exceeded::Agent));
+        }
+
+        if let (Some(room_config), Some(room_id)) = (&method_config.room, room_id) {
+            let key = BucketKey::Room(method.to_owned(), room_id);
+            limits.push((key, room_config, Exceeded::Room));
+        }
+
+        for (key, bucket_config, exceeded) in limits.iter() {
+            let bucket = guard
+                .buckets
+                .entry(key.to_owned())
+                .or_insert_with(|| TokenBucket::new(bucket_config, now));
+
+            bucket.refill(bucket_config, now);
+
+            if bucket.tokens < 1.0 {
+                return Err(*exceeded);
+            }
+        }
+
+        for (key, _, _) in limits.iter() {
+            if let Some(bucket) = guard.buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Buckets {
+    /// Drops buckets that have been idle long enough to get full again
+    /// so they don't pile up for agents and rooms that are gone.
+    fn purge(&mut self, config: &RateLimitConfig, now: DateTime<Utc>) {
+        let max_period = config
+            .values()
+            .flat_map(|method_config| vec![&method_config.agent, &method_config.room])
+            .filter_map(|bucket_config| bucket_config.as_ref().map(|c| c.period))
+            .max()
+            .unwrap_or_default();
+
+        let max_period = chrono::Duration::from_std(max_period)
+            .unwrap_or_else(|_| chrono::Duration::max_value());
+
+        match self.purged_at {
+            Some(purged_at) if now - purged_at < max_period => return,
+            _ => self.purged_at = Some(now),
+        }
+
+        self.buckets
+            .retain(|_, bucket| now - bucket.updated_at < max_period);
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use svc_agent::AccountId;
+
+    use crate::{config::RateLimitMethodConfig, test_helpers::USR_AUDIENCE};
+
+    use super::*;
+
+    fn build_config(agent_capacity: u32, room_capacity: u32) -> RateLimitConfig {
+        let mut config = RateLimitConfig::new();
+
+        config.insert(
+            String::from("message.broadcast"),
+            RateLimitMethodConfig {
+                agent: Some(RateLimitBucketConfig {
+                    capacity: agent_capacity,
+                    period: Duration::from_secs(1),
+                }),
+                room: Some(RateLimitBucketConfig {
+                    capacity: room_capacity,
+                    period: Duration::from_secs(1),
+                }),
+            },
+        );
+
+        config
+    }
+
+    #[test]
+    fn agent_limit() {
+        let config = build_config(2, 100);
+        let limiter = RateLimiter::default();
+        let agent_id = AgentId::new("web", AccountId::new("user123", USR_AUDIENCE));
+        let room_id = db::room::Id::random();
+        let now = Utc::now();
+
+        for _ in 0..2 {
+            limiter
+                .check(&config, "message.broadcast", &agent_id, Some(room_id), now)
+                .expect("Rate limit exceeded");
+        }
+
+        let result = limiter.check(&config, "message.broadcast", &agent_id, Some(room_id), now);
+        assert_eq!(result, Err(Exceeded::Agent));
+
+        // Other methods are not limited.
+        limiter
+            .check(&config, "message.unicast", &agent_id, Some(room_id), now)
+            .expect("Rate limit exceeded");
+
+        // The bucket gets refilled over time.
+        let later = now + chrono::Duration::milliseconds(500);
+
+        limiter
+            .check(
+                &config,
+                "message.broadcast",
+                &agent_id,
+                Some(room_id),
+                later,
+            )
+            .expect("Rate limit exceeded");
+
+        let result = limiter.check(
+            &config,
+            "message.broadcast",
+            &agent_id,
+            Some(room_id),
+            later,
+        );
+        assert_eq!(result, Err(Exceeded::Agent));
+    }
+
+    #[test]
+    fn room_limit() {
+        let config = build_config(100, 2);
+        let limiter = RateLimiter::default();
+        let room_id = db::room::Id::random();
+        let other_room_id = db::room::Id::random();
+        let now = Utc::now();
+
+        for label in &["web1", "web2"] {
+            let agent_id = AgentId::new(label, AccountId::new("user123", USR_AUDIENCE));
+
+            limiter
+                .check(&config, "message.broadcast", &agent_id, Some(room_id), now)
+                .expect("Rate limit exceeded");
+        }
+
+        let agent_id = AgentId::new("web3", AccountId::new("user123", USR_AUDIENCE));
+        let result = limiter.check(&config, "message.broadcast", &agent_id, Some(room_id), now);
+        assert_eq!(result, Err(Exceeded::Room));
+
+        // Other rooms are not affected.
+        limiter
+            .check(
+                &config,
+                "message.broadcast",
+                &agent_id,
+                Some(other_room_id),
+                now,
+            )
+            .expect("Rate limit exceeded");
+    }
+}
@@ -7,6 +7,7 @@ use self::{
     create_handle::{CreateHandleRequest, CreateHandleResponse},
     create_session::CreateSessionResponse,
     create_stream::{CreateStreamRequest, CreateStreamTransaction},
+    detach_handle::DetachHandleRequest,
     events::{
         DetachedEvent, EventResponse, HangUpEvent, MediaEvent, SlowLinkEvent, TimeoutEvent,
         WebRtcUpEvent,
@@ -34,6 +35,7 @@ pub mod agent_leave;
 pub mod create_handle;
 pub mod create_session;
 pub mod create_stream;
+pub mod detach_handle;
 pub mod events;
 pub mod read_stream;
 pub mod transactions;
@@ -132,6 +134,11 @@ impl JanusClient {
         Ok(response.data)
     }
 
+    pub async fn detach_handle(&self, request: DetachHandleRequest) -> anyhow::Result<()> {
+        let _response: SuccessResponse = self.send_request(detach_handle(request)).await?;
+        Ok(())
+    }
+
     pub async fn create_session(&self) -> anyhow::Result<CreateSessionResponse> {
         let response: JanusResponse<CreateSessionResponse> =
             self.send_request(create_session()).await?;
@@ -264,6 +271,11 @@ enum Success {
     Success,
 }
 
+#[derive(Deserialize, Debug)]
+struct SuccessResponse {
+    janus: Success,
+}
+
 #[derive(Deserialize, Debug)]
 struct JanusResponse<T> {
     data: T,
@@ -298,6 +310,15 @@ fn create_handle(request: CreateHandleRequest) -> JanusRequest<CreateHandleReque
     }
 }
 
+fn detach_handle(request: DetachHandleRequest) -> JanusRequest<DetachHandleRequest> {
+    JanusRequest {
+        transaction: Uuid::new_v4().to_string(),
+        janus: "detach",
+        plugin: None,
+        data: request,
+    }
+}
+
 fn trickle(request: TrickleRequest) -> JanusRequest<TrickleRequest> {
     JanusRequest {
         transaction: Uuid::new_v4().to_string(),
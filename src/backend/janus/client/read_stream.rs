@@ -25,14 +25,16 @@ pub struct ReadStreamRequestBody {
     method: &'static str,
     id: db::rtc::Id,
     agent_id: AgentId,
+    kind: db::janus_rtc_stream::Kind,
 }
 
 impl ReadStreamRequestBody {
-    pub fn new(id: db::rtc::Id, agent_id: AgentId) -> Self {
+    pub fn new(id: db::rtc::Id, agent_id: AgentId, kind: db::janus_rtc_stream::Kind) -> Self {
         Self {
             method: "stream.read",
             id,
             agent_id,
+            kind,
         }
     }
 }
@@ -28,8 +28,17 @@ impl UpdateWriterConfigRequestBody {
 #[derive(Debug, Serialize)]
 pub struct UpdateWriterConfigRequestBodyConfigItem {
     pub stream_id: db::rtc::Id,
+    pub kind: db::janus_rtc_stream::Kind,
     pub send_video: bool,
     pub send_audio: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub video_remb: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_remb: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_framerate: Option<u32>,
 }
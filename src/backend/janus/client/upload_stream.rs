@@ -14,6 +14,9 @@ pub struct UploadStreamRequest {
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct UploadStreamTransaction {
     pub rtc_id: db::rtc::Id,
+    // Transactions sent before recordings got split by kind are camera ones.
+    #[serde(default)]
+    pub kind: db::janus_rtc_stream::Kind,
     pub start_timestamp: DateTime<Utc>,
 }
 
@@ -21,16 +24,24 @@ pub struct UploadStreamTransaction {
 pub struct UploadStreamRequestBody {
     method: &'static str,
     id: db::rtc::Id,
+    kind: db::janus_rtc_stream::Kind,
     backend: String,
     bucket: String,
     object: String,
 }
 
 impl UploadStreamRequestBody {
-    pub fn new(id: db::rtc::Id, backend: &str, bucket: &str, object: &str) -> Self {
+    pub fn new(
+        id: db::rtc::Id,
+        kind: db::janus_rtc_stream::Kind,
+        backend: &str,
+        bucket: &str,
+        object: &str,
+    ) -> Self {
         Self {
             method: "stream.upload",
             id,
+            kind,
             backend: backend.to_owned(),
             bucket: bucket.to_owned(),
             object: object.to_owned(),
@@ -79,6 +79,10 @@ impl Clients {
         }
     }
 
+    pub fn count(&self) -> usize {
+        self.clients.read().expect("Must not panic").len()
+    }
+
     pub fn clear(&self) {
         let mut guard = self.clients.write().expect("Must not panic");
         for (_, handle) in guard.drain() {
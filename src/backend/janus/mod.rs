@@ -1,7 +1,7 @@
 use anyhow::{anyhow, Context as AnyhowContext, Result};
 use async_std::{stream, task};
 use chrono::{DateTime, NaiveDateTime, Utc};
-use slog::{error, o};
+use slog::{error, o, warn};
 use std::ops::Bound;
 use svc_agent::{
     mqtt::{
@@ -32,7 +32,13 @@ use self::client::{transactions::Transaction, IncomingEvent};
 
 ////////////////////////////////////////////////////////////////////////////////
 
-pub const JANUS_API_VERSION: &str = "v1";
+/// Version of the janus-conference plugin API. Backends of other versions are neither selected
+/// for new rooms nor vacuumed.
+///
+/// v2: streams of different kinds per RTC. `stream.create`, `stream.read`,
+/// `writer_config.update` and `stream.upload` requests carry the stream `kind`
+/// and the plugin records and uploads each kind of the RTC separately.
+pub const JANUS_API_VERSION: &str = "v2";
 
 const ALREADY_RUNNING_STATE: &str = "already_running";
 
@@ -231,6 +237,7 @@ async fn handle_event_impl<C: Context>(
                 Transaction::UploadStream(ref tn) => {
                     context.add_logger_tags(o!(
                         "rtc_id" => tn.rtc_id.to_string(),
+                        "kind" => tn.kind.to_string(),
                     ));
                     // TODO: improve error handling
                     let plugin_data = resp
@@ -252,8 +259,9 @@ async fn handle_event_impl<C: Context>(
                             val if val == "404" => {
                                 let conn = context.get_conn().await?;
                                 let rtc_id = tn.rtc_id;
+                                let kind = tn.kind;
                                 task::spawn_blocking(move || {
-                                    recording::UpdateQuery::new(rtc_id)
+                                    recording::UpdateQuery::new(rtc_id, kind)
                                         .status(recording::Status::Missing)
                                         .execute(&conn)
                                 })
@@ -332,8 +340,9 @@ async fn handle_event_impl<C: Context>(
                             Vec<(rtc::Object, Option<recording::Object>)>,
                         ) = {
                             let conn = context.get_conn().await?;
+                            let kind = tn.kind;
                             task::spawn_blocking(move || {
-                                recording::UpdateQuery::new(rtc_id)
+                                recording::UpdateQuery::new(rtc_id, kind)
                                     .status(recording::Status::Ready)
                                     .started_at(started_at)
                                     .segments(segments)
@@ -375,6 +384,15 @@ async fn handle_event_impl<C: Context>(
                             return Ok(Box::new(stream::empty()) as MessageStream);
                         }
 
+                        // Wait for the rest of the recordings, e.g. other kinds of the same RTC.
+                        let is_any_in_progress = recs_with_rtcs.iter().any(|(recording, _)| {
+                            *recording.status() == recording::Status::InProgress
+                        });
+
+                        if is_any_in_progress {
+                            return Ok(Box::new(stream::empty()) as MessageStream);
+                        }
+
                         // Send room.upload event.
                         let event = endpoint::system::upload_event(
                             context,
@@ -448,17 +466,20 @@ pub async fn handle_status_event<C: Context>(
     context: &mut C,
     event: &MQTTIncomingEvent<String>,
 ) -> MessageStream {
-    handle_status_event_impl(context, event)
-        .await
-        .unwrap_or_else(|app_error| {
-            error!(
-                context.logger(),
-                "Failed to handle a status event from janus: {:?}", app_error
-            );
-
-            app_error.notify_sentry(context.logger());
-            Box::new(stream::empty())
-        })
+    let result = match event.properties().label() {
+        Some("backend.stats") => handle_stats_event_impl(context, event).await,
+        _ => handle_status_event_impl(context, event).await,
+    };
+
+    result.unwrap_or_else(|app_error| {
+        error!(
+            context.logger(),
+            "Failed to handle a status event from janus: {:?}", app_error
+        );
+
+        app_error.notify_sentry(context.logger());
+        Box::new(stream::empty())
+    })
 }
 
 // Janus Gateway online/offline status.
@@ -469,6 +490,11 @@ pub struct StatusEvent {
     pub balancer_capacity: Option<i32>,
     pub group: Option<String>,
     pub janus_url: Option<String>,
+    pub region: Option<String>,
+    /// Measured egress bitrate in bits per second.
+    pub egress_bitrate: Option<i64>,
+    /// Measured CPU usage from 0.0 to 1.0.
+    pub cpu_usage: Option<f64>,
 }
 
 async fn handle_status_event_impl<C: Context>(
@@ -505,6 +531,7 @@ async fn handle_status_event_impl<C: Context>(
             .context("Create first handle")
             .error(AppErrorKind::BackendRequestFailed)?;
         let backend_id = evp.as_agent_id().clone();
+        let reported_at = context.start_timestamp();
         let conn = context.get_conn().await?;
 
         let backend = task::spawn_blocking(move || {
@@ -523,6 +550,14 @@ async fn handle_status_event_impl<C: Context>(
                 q = q.group(group);
             }
 
+            if let Some(region) = payload.region.as_deref() {
+                q = q.region(region);
+            }
+
+            if payload.egress_bitrate.is_some() || payload.cpu_usage.is_some() {
+                q = q.stats(payload.egress_bitrate, payload.cpu_usage, reported_at);
+            }
+
             q.execute(&conn)
         })
         .await?;
@@ -569,6 +604,49 @@ async fn handle_status_event_impl<C: Context>(
     }
 }
 
+// Janus Gateway usage measured periodically while it's online.
+#[derive(Debug, Deserialize)]
+pub struct StatsEvent {
+    /// Egress bitrate in bits per second.
+    pub egress_bitrate: Option<i64>,
+    /// CPU usage from 0.0 to 1.0.
+    pub cpu_usage: Option<f64>,
+}
+
+async fn handle_stats_event_impl<C: Context>(
+    context: &mut C,
+    event: &MQTTIncomingEvent<String>,
+) -> Result<MessageStream, AppError> {
+    let payload = MQTTIncomingEvent::convert_payload::<StatsEvent>(&event)
+        .map_err(|err| anyhow!("Failed to parse event: {}", err))
+        .error(AppErrorKind::MessageParsingFailed)?;
+
+    let backend_id = event.properties().as_agent_id().clone();
+    let reported_at = context.start_timestamp();
+    let conn = context.get_conn().await?;
+
+    let maybe_backend = task::spawn_blocking(move || {
+        let mut q = janus_backend::UpdateStatsQuery::new(&backend_id, reported_at);
+
+        if let Some(egress_bitrate) = payload.egress_bitrate {
+            q = q.egress_bitrate(egress_bitrate);
+        }
+
+        if let Some(cpu_usage) = payload.cpu_usage {
+            q = q.cpu_usage(cpu_usage);
+        }
+
+        q.execute(&conn)
+    })
+    .await?;
+
+    if maybe_backend.is_none() {
+        warn!(context.logger(), "Stats received from an offline backend");
+    }
+
+    Ok(Box::new(stream::empty()))
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 pub mod client;
 pub mod client_pool;
@@ -22,6 +22,15 @@ pub struct Config {
     #[serde(default)]
     pub kruonis: KruonisConfig,
     pub metrics: MetricsConfig,
+    pub http_api: Option<HttpApiConfig>,
+    #[serde(default)]
+    pub sweeper: SweeperConfig,
+    #[serde(default)]
+    pub unicast: UnicastConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub balancer: BalancerConfig,
     pub max_room_duration: Option<i64>,
     pub janus_group: Option<String>,
 }
@@ -84,3 +93,129 @@ pub struct MetricsConfig {
 pub struct MetricsHttpConfig {
     pub bind_address: std::net::SocketAddr,
 }
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct HttpApiConfig {
+    pub bind_address: std::net::SocketAddr,
+    /// Issuers and keys to verify bearer tokens of the HTTP API requests.
+    pub authn: svc_authn::jose::ConfigMap,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SweeperConfig {
+    /// Agents stuck in `in_progress` status for longer are removed on `system.sweep`.
+    /// `ready` agents older than that are being checked against the broker.
+    #[serde(with = "humantime_serde")]
+    pub agent_timeout: Duration,
+}
+
+impl Default for SweeperConfig {
+    fn default() -> Self {
+        Self {
+            agent_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct UnicastConfig {
+    /// `message.unicast` requests the target agent hasn't responded to in time
+    /// are answered with `unicast_timeout` error.
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+    /// How often pending requests are being checked for timeout and the target agent presence.
+    #[serde(with = "humantime_serde")]
+    pub check_interval: Duration,
+}
+
+impl Default for UnicastConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            check_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Rate limits keyed by request method. Limits apply to each replica separately.
+pub type RateLimitConfig = HashMap<String, RateLimitMethodConfig>;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RateLimitMethodConfig {
+    /// Limit for each agent.
+    pub agent: Option<RateLimitBucketConfig>,
+    /// Limit for each room. Applies to methods having `room_id` or `handle_id` in the payload
+    /// sent by agents who are in the room.
+    pub room: Option<RateLimitBucketConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RateLimitBucketConfig {
+    /// Maximum number of requests in a burst.
+    pub capacity: u32,
+    /// Time to refill the whole capacity.
+    #[serde(with = "humantime_serde")]
+    pub period: Duration,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct BalancerConfig {
+    /// Strategy used unless overridden for the room's audience or the instance's janus group.
+    #[serde(default)]
+    pub strategy: BalancerStrategyKind,
+    #[serde(default)]
+    pub groups: HashMap<String, BalancerStrategyKind>,
+    #[serde(default)]
+    pub audiences: HashMap<String, BalancerStrategyKind>,
+    /// Assign backends to rooms with a reserve on `room.create`/`room.update`
+    /// instead of the first writer's `rtc.connect`.
+    #[serde(default)]
+    pub reserve_ahead: bool,
+    /// Prefer backends already hosting other not closed rooms with the same `classroom_id`
+    /// if they are capable to host the room's reserve.
+    #[serde(default)]
+    pub classroom_sticky: bool,
+    /// Blend stats measured by backends into their loads. Estimates only are used if not set.
+    #[serde(default)]
+    pub stats: Option<BalancerStatsConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct BalancerStatsConfig {
+    /// Load units per 1 Mbps of measured egress bitrate.
+    pub bitrate_weight: f64,
+    /// Share of the backend's capacity considered taken at 100% CPU usage.
+    pub cpu_weight: f64,
+    /// Stats reported earlier are ignored.
+    #[serde(with = "humantime_serde")]
+    pub max_age: Duration,
+}
+
+impl BalancerConfig {
+    pub fn strategy_kind(&self, group: Option<&str>, audience: &str) -> BalancerStrategyKind {
+        self.audiences
+            .get(audience)
+            .or_else(|| group.and_then(|group| self.groups.get(group)))
+            .copied()
+            .unwrap_or(self.strategy)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BalancerStrategyKind {
+    /// The most loaded backend capable to host the room's reserve.
+    BinPacking,
+    /// The backend with the most free capacity.
+    LeastLoaded,
+    /// Capable backends in turn.
+    RoundRobin,
+    /// A random capable backend with chances proportional to its free capacity.
+    WeightedRandom,
+}
+
+impl Default for BalancerStrategyKind {
+    fn default() -> Self {
+        Self::BinPacking
+    }
+}
@@ -25,6 +25,7 @@ pub enum Status {
     #[serde(rename = "in_progress")]
     InProgress,
     Ready,
+    Waiting,
 }
 
 #[derive(Debug, Serialize, Deserialize, Identifiable, Queryable, QueryableByName, Associations)]
@@ -37,21 +38,36 @@ pub struct Object {
     #[serde(with = "ts_seconds")]
     created_at: DateTime<Utc>,
     status: Status,
+    speak_granted: bool,
 }
 
-#[cfg(test)]
 impl Object {
+    pub fn agent_id(&self) -> &AgentId {
+        &self.agent_id
+    }
+
+    pub fn room_id(&self) -> db::room::Id {
+        self.room_id
+    }
+
     pub fn status(&self) -> Status {
         self.status
     }
+
+    pub fn speak_granted(&self) -> bool {
+        self.speak_granted
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
 
 pub struct ListQuery<'a> {
     agent_id: Option<&'a AgentId>,
+    agent_ids: Option<&'a [&'a AgentId]>,
     room_id: Option<db::room::Id>,
-    status: Option<Status>,
+    room_ids: Option<&'a [db::room::Id]>,
+    statuses: Option<&'a [Status]>,
+    created_before: Option<DateTime<Utc>>,
     offset: Option<i64>,
     limit: Option<i64>,
 }
@@ -60,8 +76,11 @@ impl<'a> ListQuery<'a> {
     pub fn new() -> Self {
         Self {
             agent_id: None,
+            agent_ids: None,
             room_id: None,
-            status: None,
+            room_ids: None,
+            statuses: None,
+            created_before: None,
             offset: None,
             limit: None,
         }
@@ -74,6 +93,13 @@ impl<'a> ListQuery<'a> {
         }
     }
 
+    pub fn agent_ids(self, agent_ids: &'a [&'a AgentId]) -> Self {
+        Self {
+            agent_ids: Some(agent_ids),
+            ..self
+        }
+    }
+
     pub fn room_id(self, room_id: db::room::Id) -> Self {
         Self {
             room_id: Some(room_id),
@@ -81,9 +107,24 @@ impl<'a> ListQuery<'a> {
         }
     }
 
-    pub fn status(self, status: Status) -> Self {
+    pub fn room_ids(self, room_ids: &'a [db::room::Id]) -> Self {
         Self {
-            status: Some(status),
+            room_ids: Some(room_ids),
+            ..self
+        }
+    }
+
+    /// Overrides the default filtering by `ready` status.
+    pub fn statuses(self, statuses: &'a [Status]) -> Self {
+        Self {
+            statuses: Some(statuses),
+            ..self
+        }
+    }
+
+    pub fn created_before(self, created_before: DateTime<Utc>) -> Self {
+        Self {
+            created_before: Some(created_before),
             ..self
         }
     }
@@ -105,20 +146,31 @@ impl<'a> ListQuery<'a> {
     pub fn execute(&self, conn: &PgConnection) -> Result<Vec<Object>, Error> {
         use diesel::prelude::*;
 
-        let mut q = agent::table
-            .into_boxed()
-            .filter(agent::status.eq(Status::Ready));
+        let mut q = agent::table.into_boxed();
+
+        q = match self.statuses {
+            Some(statuses) => q.filter(agent::status.eq_any(statuses)),
+            None => q.filter(agent::status.eq(Status::Ready)),
+        };
 
         if let Some(agent_id) = self.agent_id {
             q = q.filter(agent::agent_id.eq(agent_id));
         }
 
+        if let Some(agent_ids) = self.agent_ids {
+            q = q.filter(agent::agent_id.eq_any(agent_ids));
+        }
+
         if let Some(room_id) = self.room_id {
             q = q.filter(agent::room_id.eq(room_id));
         }
 
-        if let Some(status) = self.status {
-            q = q.filter(agent::status.eq(status));
+        if let Some(room_ids) = self.room_ids {
+            q = q.filter(agent::room_id.eq_any(room_ids));
+        }
+
+        if let Some(created_before) = self.created_before {
+            q = q.filter(agent::created_at.lt(created_before));
         }
 
         if let Some(offset) = self.offset {
@@ -129,7 +181,9 @@ impl<'a> ListQuery<'a> {
             q = q.limit(limit);
         }
 
-        q.order_by(agent::created_at.desc()).get_results(conn)
+        q.order_by(agent::created_at.desc())
+            .then_order_by(agent::id)
+            .get_results(conn)
     }
 }
 
@@ -154,7 +208,6 @@ impl<'a> InsertQuery<'a> {
         }
     }
 
-    #[cfg(test)]
     pub fn status(self, status: Status) -> Self {
         Self { status, ..self }
     }
@@ -167,7 +220,11 @@ impl<'a> InsertQuery<'a> {
             .values(self)
             .on_conflict((agent_id, room_id))
             .do_update()
-            .set(status.eq(Status::InProgress))
+            .set((
+                status.eq(self.status),
+                speak_granted.eq(false),
+                created_at.eq(Utc::now()),
+            ))
             .get_result(conn)
     }
 }
@@ -180,6 +237,7 @@ pub struct UpdateQuery<'a> {
     agent_id: &'a AgentId,
     room_id: db::room::Id,
     status: Option<Status>,
+    speak_granted: Option<bool>,
 }
 
 impl<'a> UpdateQuery<'a> {
@@ -188,6 +246,7 @@ impl<'a> UpdateQuery<'a> {
             agent_id,
             room_id,
             status: None,
+            speak_granted: None,
         }
     }
 
@@ -198,6 +257,13 @@ impl<'a> UpdateQuery<'a> {
         }
     }
 
+    pub fn speak_granted(self, speak_granted: bool) -> Self {
+        Self {
+            speak_granted: Some(speak_granted),
+            ..self
+        }
+    }
+
     pub fn execute(&self, conn: &PgConnection) -> Result<Option<Object>, Error> {
         use diesel::prelude::*;
 
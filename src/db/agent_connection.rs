@@ -7,6 +7,7 @@ use crate::{
     db::{
         self,
         agent::{Object as Agent, Status as AgentStatus},
+        janus_rtc_stream::Kind,
     },
     schema::{agent, agent_connection},
 };
@@ -16,6 +17,7 @@ type AllColumns = (
     agent_connection::handle_id,
     agent_connection::created_at,
     agent_connection::rtc_id,
+    agent_connection::kind,
 );
 
 const ALL_COLUMNS: AllColumns = (
@@ -23,6 +25,7 @@ const ALL_COLUMNS: AllColumns = (
     agent_connection::handle_id,
     agent_connection::created_at,
     agent_connection::rtc_id,
+    agent_connection::kind,
 );
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -36,27 +39,33 @@ pub struct Object {
     handle_id: HandleId,
     created_at: DateTime<Utc>,
     rtc_id: db::rtc::Id,
+    kind: Kind,
 }
 
 impl Object {
     pub fn handle_id(&self) -> HandleId {
         self.handle_id
     }
+
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////
 
-pub struct FindQuery<'a> {
+/// Lists connections of the agent to the RTC, one per stream kind.
+pub struct ListQuery<'a> {
     agent_id: &'a AgentId,
     rtc_id: db::rtc::Id,
 }
 
-impl<'a> FindQuery<'a> {
+impl<'a> ListQuery<'a> {
     pub fn new(agent_id: &'a AgentId, rtc_id: db::rtc::Id) -> Self {
         Self { agent_id, rtc_id }
     }
 
-    pub fn execute(&self, conn: &PgConnection) -> Result<Option<Object>, Error> {
+    pub fn execute(&self, conn: &PgConnection) -> Result<Vec<Object>, Error> {
         use diesel::prelude::*;
 
         agent_connection::table
@@ -65,8 +74,7 @@ impl<'a> FindQuery<'a> {
             .filter(agent::agent_id.eq(self.agent_id))
             .filter(agent_connection::rtc_id.eq(self.rtc_id))
             .select(ALL_COLUMNS)
-            .get_result(conn)
-            .optional()
+            .get_results(conn)
     }
 }
 
@@ -97,6 +105,7 @@ pub struct UpsertQuery {
     rtc_id: db::rtc::Id,
     handle_id: HandleId,
     created_at: DateTime<Utc>,
+    kind: Kind,
 }
 
 impl UpsertQuery {
@@ -106,16 +115,21 @@ impl UpsertQuery {
             rtc_id,
             handle_id,
             created_at: Utc::now(),
+            kind: Kind::default(),
         }
     }
 
+    pub fn kind(self, kind: Kind) -> Self {
+        Self { kind, ..self }
+    }
+
     pub fn execute(&self, conn: &PgConnection) -> Result<Object, Error> {
         use crate::schema::agent_connection::dsl::*;
         use diesel::prelude::*;
 
         diesel::insert_into(agent_connection)
             .values(self)
-            .on_conflict((agent_id, rtc_id))
+            .on_conflict((agent_id, rtc_id, kind))
             .do_update()
             .set(self)
             .get_result(conn)
@@ -163,12 +177,13 @@ impl BulkDisconnectByRtcQuery {
         Self { rtc_id }
     }
 
-    pub fn execute(&self, conn: &PgConnection) -> Result<usize, Error> {
+    /// Returns the deleted connections so that their handles could be detached.
+    pub fn execute(&self, conn: &PgConnection) -> Result<Vec<Object>, Error> {
         use diesel::prelude::*;
 
         diesel::delete(agent_connection::table)
             .filter(agent_connection::rtc_id.eq(self.rtc_id))
-            .execute(conn)
+            .get_results(conn)
     }
 }
 
@@ -0,0 +1,161 @@
+use chrono::{DateTime, Utc};
+use diesel::{pg::PgConnection, result::Error};
+use diesel_derive_enum::DbEnum;
+use serde::{Deserialize, Serialize};
+use svc_agent::AgentId;
+use uuid::Uuid;
+
+use crate::{db, db::room::Object as Room, schema::agent_session};
+
+////////////////////////////////////////////////////////////////////////////////
+
+type AllColumns = (
+    agent_session::id,
+    agent_session::room_id,
+    agent_session::agent_id,
+    agent_session::entered_at,
+    agent_session::left_at,
+    agent_session::leave_reason,
+);
+
+const ALL_COLUMNS: AllColumns = (
+    agent_session::id,
+    agent_session::room_id,
+    agent_session::agent_id,
+    agent_session::entered_at,
+    agent_session::left_at,
+    agent_session::leave_reason,
+);
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, DbEnum, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[PgType = "agent_session_leave_reason"]
+#[DieselType = "Agent_session_leave_reason"]
+pub enum LeaveReason {
+    /// The agent has sent `room.leave` request.
+    Request,
+    /// The broker has deleted the agent's subscription, e.g. on disconnect.
+    SubscriptionDelete,
+    Kick,
+    Ban,
+    /// The agent has been removed by `system.sweep` as stale.
+    Sweep,
+}
+
+/// A record of an agent's presence in the room. Rows are never deleted so they remain
+/// after the agent has left the room unlike `agent` rows.
+#[derive(Debug, Identifiable, Queryable, QueryableByName, Associations)]
+#[belongs_to(Room, foreign_key = "room_id")]
+#[table_name = "agent_session"]
+pub struct Object {
+    id: Uuid,
+    room_id: db::room::Id,
+    agent_id: AgentId,
+    entered_at: DateTime<Utc>,
+    left_at: Option<DateTime<Utc>>,
+    leave_reason: Option<LeaveReason>,
+}
+
+impl Object {
+    pub fn agent_id(&self) -> &AgentId {
+        &self.agent_id
+    }
+
+    pub fn entered_at(&self) -> DateTime<Utc> {
+        self.entered_at
+    }
+
+    pub fn left_at(&self) -> Option<DateTime<Utc>> {
+        self.left_at
+    }
+
+    #[cfg(test)]
+    pub fn leave_reason(&self) -> Option<LeaveReason> {
+        self.leave_reason
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub struct ListQuery {
+    room_id: db::room::Id,
+}
+
+impl ListQuery {
+    pub fn new(room_id: db::room::Id) -> Self {
+        Self { room_id }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<Vec<Object>, Error> {
+        use diesel::prelude::*;
+
+        agent_session::table
+            .filter(agent_session::room_id.eq(self.room_id))
+            .select(ALL_COLUMNS)
+            .order_by(agent_session::entered_at.asc())
+            .get_results(conn)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Insertable)]
+#[table_name = "agent_session"]
+pub struct InsertQuery<'a> {
+    room_id: db::room::Id,
+    agent_id: &'a AgentId,
+}
+
+impl<'a> InsertQuery<'a> {
+    pub fn new(room_id: db::room::Id, agent_id: &'a AgentId) -> Self {
+        Self { room_id, agent_id }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<Object, Error> {
+        use diesel::prelude::*;
+
+        diesel::insert_into(agent_session::table)
+            .values(self)
+            .returning(ALL_COLUMNS)
+            .get_result(conn)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Sets leave time and reason for the agent's open session in the room.
+#[derive(Debug)]
+pub struct CloseQuery<'a> {
+    room_id: db::room::Id,
+    agent_id: &'a AgentId,
+    leave_reason: LeaveReason,
+}
+
+impl<'a> CloseQuery<'a> {
+    pub fn new(room_id: db::room::Id, agent_id: &'a AgentId, leave_reason: LeaveReason) -> Self {
+        Self {
+            room_id,
+            agent_id,
+            leave_reason,
+        }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<usize, Error> {
+        use diesel::prelude::*;
+
+        let query = agent_session::table
+            .filter(agent_session::room_id.eq(self.room_id))
+            .filter(agent_session::agent_id.eq(self.agent_id))
+            .filter(agent_session::left_at.is_null());
+
+        diesel::update(query)
+            .set((
+                agent_session::left_at.eq(Utc::now()),
+                agent_session::leave_reason.eq(self.leave_reason),
+            ))
+            .execute(conn)
+    }
+}
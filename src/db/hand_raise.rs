@@ -0,0 +1,114 @@
+use chrono::{serde::ts_seconds, DateTime, Utc};
+use diesel::{pg::PgConnection, result::Error};
+use serde::Serialize;
+use svc_agent::AgentId;
+
+use crate::{db, db::room::Object as Room, schema::hand_raise};
+
+////////////////////////////////////////////////////////////////////////////////
+
+type AllColumns = (
+    hand_raise::room_id,
+    hand_raise::agent_id,
+    hand_raise::created_at,
+);
+
+const ALL_COLUMNS: AllColumns = (
+    hand_raise::room_id,
+    hand_raise::agent_id,
+    hand_raise::created_at,
+);
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, Serialize, Identifiable, Queryable, QueryableByName, Associations)]
+#[belongs_to(Room, foreign_key = "room_id")]
+#[table_name = "hand_raise"]
+#[primary_key(room_id, agent_id)]
+pub struct Object {
+    #[serde(skip)]
+    room_id: db::room::Id,
+    agent_id: AgentId,
+    #[serde(rename = "raised_at", with = "ts_seconds")]
+    created_at: DateTime<Utc>,
+}
+
+impl Object {
+    #[cfg(test)]
+    pub fn agent_id(&self) -> &AgentId {
+        &self.agent_id
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Lists raised hands in the room in the order they were raised.
+#[derive(Debug)]
+pub struct ListQuery {
+    room_id: db::room::Id,
+}
+
+impl ListQuery {
+    pub fn new(room_id: db::room::Id) -> Self {
+        Self { room_id }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<Vec<Object>, Error> {
+        use diesel::prelude::*;
+
+        hand_raise::table
+            .filter(hand_raise::room_id.eq(self.room_id))
+            .select(ALL_COLUMNS)
+            .order_by(hand_raise::created_at.asc())
+            .get_results(conn)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Insertable)]
+#[table_name = "hand_raise"]
+pub struct InsertQuery<'a> {
+    room_id: db::room::Id,
+    agent_id: &'a AgentId,
+}
+
+impl<'a> InsertQuery<'a> {
+    pub fn new(room_id: db::room::Id, agent_id: &'a AgentId) -> Self {
+        Self { room_id, agent_id }
+    }
+
+    /// Returns the number of inserted rows. Raising an already raised hand keeps its position.
+    pub fn execute(&self, conn: &PgConnection) -> Result<usize, Error> {
+        use diesel::prelude::*;
+
+        diesel::insert_into(hand_raise::table)
+            .values(self)
+            .on_conflict_do_nothing()
+            .execute(conn)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub struct DeleteQuery<'a> {
+    room_id: db::room::Id,
+    agent_id: &'a AgentId,
+}
+
+impl<'a> DeleteQuery<'a> {
+    pub fn new(room_id: db::room::Id, agent_id: &'a AgentId) -> Self {
+        Self { room_id, agent_id }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<usize, Error> {
+        use diesel::prelude::*;
+
+        let query = hand_raise::table
+            .filter(hand_raise::room_id.eq(self.room_id))
+            .filter(hand_raise::agent_id.eq(self.agent_id));
+
+        diesel::delete(query).execute(conn)
+    }
+}
@@ -20,6 +20,11 @@ pub type AllColumns = (
     janus_backend::api_version,
     janus_backend::group,
     janus_backend::janus_url,
+    janus_backend::region,
+    janus_backend::egress_bitrate,
+    janus_backend::cpu_usage,
+    janus_backend::stats_updated_at,
+    janus_backend::draining,
 );
 
 pub const ALL_COLUMNS: AllColumns = (
@@ -32,6 +37,11 @@ pub const ALL_COLUMNS: AllColumns = (
     janus_backend::api_version,
     janus_backend::group,
     janus_backend::janus_url,
+    janus_backend::region,
+    janus_backend::egress_bitrate,
+    janus_backend::cpu_usage,
+    janus_backend::stats_updated_at,
+    janus_backend::draining,
 );
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -48,6 +58,11 @@ pub struct Object {
     api_version: String,
     group: Option<String>,
     janus_url: String,
+    region: Option<String>,
+    egress_bitrate: Option<i64>,
+    cpu_usage: Option<f64>,
+    stats_updated_at: Option<DateTime<Utc>>,
+    draining: bool,
 }
 
 impl Object {
@@ -63,14 +78,52 @@ impl Object {
         self.session_id
     }
 
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
     pub fn janus_url(&self) -> &str {
         &self.janus_url
     }
 
+    pub fn capacity(&self) -> Option<i32> {
+        self.capacity
+    }
+
+    pub fn balancer_capacity(&self) -> Option<i32> {
+        self.balancer_capacity
+    }
+
+    pub fn api_version(&self) -> &str {
+        &self.api_version
+    }
+
     /// Get a reference to the object's group.
     pub fn group(&self) -> Option<&str> {
         self.group.as_deref()
     }
+
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    /// Measured egress bitrate in bits per second.
+    pub fn egress_bitrate(&self) -> Option<i64> {
+        self.egress_bitrate
+    }
+
+    /// Measured CPU usage from 0.0 to 1.0.
+    pub fn cpu_usage(&self) -> Option<f64> {
+        self.cpu_usage
+    }
+
+    pub fn stats_updated_at(&self) -> Option<DateTime<Utc>> {
+        self.stats_updated_at
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -97,6 +150,20 @@ impl<'a> ListQuery<'a> {
         }
     }
 
+    pub fn offset(self, offset: i64) -> Self {
+        Self {
+            offset: Some(offset),
+            ..self
+        }
+    }
+
+    pub fn limit(self, limit: i64) -> Self {
+        Self {
+            limit: Some(limit),
+            ..self
+        }
+    }
+
     pub fn execute(&self, conn: &PgConnection) -> Result<Vec<Object>, Error> {
         use diesel::prelude::*;
 
@@ -154,6 +221,10 @@ pub struct UpsertQuery<'a> {
     api_version: String,
     group: Option<&'a str>,
     janus_url: &'a str,
+    region: Option<&'a str>,
+    egress_bitrate: Option<i64>,
+    cpu_usage: Option<f64>,
+    stats_updated_at: Option<DateTime<Utc>>,
 }
 
 impl<'a> UpsertQuery<'a> {
@@ -172,6 +243,10 @@ impl<'a> UpsertQuery<'a> {
             api_version: JANUS_API_VERSION.to_string(),
             group: None,
             janus_url,
+            region: None,
+            egress_bitrate: None,
+            cpu_usage: None,
+            stats_updated_at: None,
         }
     }
 
@@ -196,6 +271,27 @@ impl<'a> UpsertQuery<'a> {
         }
     }
 
+    pub fn region(self, region: &'a str) -> Self {
+        Self {
+            region: Some(region),
+            ..self
+        }
+    }
+
+    pub fn stats(
+        self,
+        egress_bitrate: Option<i64>,
+        cpu_usage: Option<f64>,
+        reported_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            egress_bitrate,
+            cpu_usage,
+            stats_updated_at: Some(reported_at),
+            ..self
+        }
+    }
+
     pub fn execute(&self, conn: &PgConnection) -> Result<Object, Error> {
         use crate::schema::janus_backend::dsl::janus_backend;
         use diesel::RunQueryDsl;
@@ -211,6 +307,72 @@ impl<'a> UpsertQuery<'a> {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+pub struct UpdateStatsQuery<'a> {
+    id: &'a AgentId,
+    egress_bitrate: Option<i64>,
+    cpu_usage: Option<f64>,
+    reported_at: DateTime<Utc>,
+}
+
+impl<'a> UpdateStatsQuery<'a> {
+    pub fn new(id: &'a AgentId, reported_at: DateTime<Utc>) -> Self {
+        Self {
+            id,
+            egress_bitrate: None,
+            cpu_usage: None,
+            reported_at,
+        }
+    }
+
+    pub fn egress_bitrate(self, egress_bitrate: i64) -> Self {
+        Self {
+            egress_bitrate: Some(egress_bitrate),
+            ..self
+        }
+    }
+
+    pub fn cpu_usage(self, cpu_usage: f64) -> Self {
+        Self {
+            cpu_usage: Some(cpu_usage),
+            ..self
+        }
+    }
+
+    /// Returns `None` if the backend is not online.
+    pub fn execute(&self, conn: &PgConnection) -> Result<Option<Object>, Error> {
+        use diesel::prelude::*;
+
+        diesel::update(janus_backend::table.filter(janus_backend::id.eq(self.id)))
+            .set((
+                janus_backend::egress_bitrate.eq(self.egress_bitrate),
+                janus_backend::cpu_usage.eq(self.cpu_usage),
+                janus_backend::stats_updated_at.eq(self.reported_at),
+            ))
+            .get_result(conn)
+            .optional()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Draining backends keep serving rooms already pinned to them
+/// but are not being selected by the balancer for new ones.
+/// Returns `None` if the backend is not online.
+pub fn set_draining(
+    id: &AgentId,
+    draining: bool,
+    conn: &PgConnection,
+) -> Result<Option<Object>, Error> {
+    use diesel::prelude::*;
+
+    diesel::update(janus_backend::table.filter(janus_backend::id.eq(id)))
+        .set(janus_backend::draining.eq(draining))
+        .get_result(conn)
+        .optional()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 pub struct DeleteQuery<'a> {
     id: &'a AgentId,
 }
@@ -229,19 +391,41 @@ impl<'a> DeleteQuery<'a> {
 
 ////////////////////////////////////////////////////////////////////////////////
 
-// Returns the most loaded backend capable to host the room with its reserve considering:
-// - room opening period;
-// - actual number of online agents;
-// - optional backend capacity;
-// - optional room reserve;
-// - writer's bitrate;
-// - possible multiple RTCs in each room.
-const MOST_LOADED_SQL: &str = r#"
-    WITH
-        room_load AS (
+// Video bitrate in bps of a writer without `video_remb` in its writer config.
+// A writer with the default video bitrate takes 1.0 of the backend capacity.
+macro_rules! default_video_remb {
+    () => {
+        "1000000"
+    };
+}
+
+// Audio bitrate in bps of an audio-only writer or a writer with video turned off
+// without `audio_remb` in its writer config.
+macro_rules! default_audio_remb {
+    () => {
+        "64000"
+    };
+}
+
+// Actual load of each room in Mbps summing up bitrates of its writers' connections.
+// Audio-only writers and writers with video turned off take only the audio bitrate.
+macro_rules! room_load_cte {
+    () => {
+        concat!(
+            r#"room_load AS (
             SELECT
                 a.room_id,
-                SUM(COALESCE(rwc.video_remb, 1000000) / 1000000.0) AS taken
+                SUM(
+                    CASE
+                        WHEN ac.kind = 'audio_only' OR rwc.send_video = FALSE
+                            THEN COALESCE(rwc.audio_remb, "#,
+            default_audio_remb!(),
+            r#")
+                        ELSE COALESCE(rwc.video_remb, "#,
+            default_video_remb!(),
+            r#")
+                    END / 1000000.0
+                ) AS taken
             FROM agent AS a
             INNER JOIN agent_connection AS ac
             ON ac.agent_id = a.id
@@ -249,8 +433,52 @@ const MOST_LOADED_SQL: &str = r#"
             ON rtc.id = ac.rtc_id
             LEFT JOIN rtc_writer_config AS rwc
             ON rwc.rtc_id = rtc.id
+            AND rwc.kind = ac.kind
             GROUP BY a.room_id
-        ),
+        )"#
+        )
+    };
+}
+
+// Loads of backends of the current API version in the group joined with `janus_backend_load`.
+// Draining backends are excluded.
+macro_rules! balancer_load_select {
+    () => {
+        r#"
+    SELECT
+        jb.id                                                      AS backend_id,
+        COALESCE(jb.balancer_capacity, jb.capacity, 2147483647)    AS capacity,
+        COALESCE(jbl.load, 0)::FLOAT8                              AS load,
+        COALESCE(jbl.taken, 0)::FLOAT8                             AS taken,
+        jb.region,
+        jb.egress_bitrate,
+        jb.cpu_usage,
+        jb.stats_updated_at
+    FROM janus_backend AS jb
+    LEFT JOIN janus_backend_load AS jbl
+    ON jbl.backend_id = jb.id
+    WHERE jb.api_version = $1
+    AND   ($2 IS NULL OR jb."group" = $2)
+    AND   NOT jb.draining
+"#
+    };
+}
+
+// Returns loads of backends of the current API version in the group for the balancer considering:
+// - room opening period;
+// - actual number of online agents;
+// - optional backend capacity;
+// - optional room reserve;
+// - optional measured stats reported by the backend to blend them in the application;
+// - writer's bitrate;
+// - possible multiple RTCs in each room.
+// Draining backends are excluded.
+const BALANCER_LOAD_SQL: &str = concat!(
+    r#"
+    WITH
+        "#,
+    room_load_cte!(),
+    r#",
         active_room AS (
             SELECT *
             FROM room
@@ -261,7 +489,8 @@ const MOST_LOADED_SQL: &str = r#"
         janus_backend_load AS (
             SELECT
                 backend_id,
-                SUM(GREATEST(taken, reserve)) AS load
+                SUM(GREATEST(taken, reserve)) AS load,
+                SUM(taken) AS taken
             FROM (
                 SELECT DISTINCT ON(backend_id, room_id)
                     ar.backend_id,
@@ -273,129 +502,128 @@ const MOST_LOADED_SQL: &str = r#"
                 ON rl.room_id = ar.id
             ) AS sub
             GROUP BY backend_id
-        )
-    SELECT jb.*
-    FROM janus_backend AS jb
-    LEFT JOIN janus_backend_load AS jbl
-    ON jbl.backend_id = jb.id
-    LEFT JOIN room AS r2
-    ON 1 = 1
-    WHERE r2.id = $1
-    AND   COALESCE(jb.balancer_capacity, jb.capacity, 2147483647) - COALESCE(jbl.load, 0) >= COALESCE(r2.reserve, 1)
-    AND   jb.api_version = $2
-    AND   ($3 IS NULL OR jb."group" = $3)
-    ORDER BY COALESCE(jbl.load, 0) DESC, RANDOM()
-    LIMIT 1
-"#;
+        )"#,
+    balancer_load_select!()
+);
 
-pub fn most_loaded(
-    room_id: db::room::Id,
+// Similar to the previous one but for the period the room is being reserved for.
+// Rooms overlapping the period which already have a backend assigned are being considered
+// except the room being reserved. The load is the peak of the rooms' loads running concurrently
+// within the period. Since a room's load only adds up at its start the peak is being reached
+// at the start of one of them.
+const RESERVE_LOAD_SQL: &str = concat!(
+    r#"
+    WITH
+        "#,
+    room_load_cte!(),
+    r#",
+        scheduled_room AS (
+            SELECT
+                r.backend_id,
+                r.time * $3                                 AS time,
+                COALESCE(rl.taken, 0)                       AS taken,
+                GREATEST(COALESCE(rl.taken, 0), COALESCE(r.reserve, 0)) AS load
+            FROM room AS r
+            LEFT JOIN room_load AS rl
+            ON rl.room_id = r.id
+            WHERE r.backend_id IS NOT NULL
+            AND   r.time && $3
+            AND   r.id <> $4
+        ),
+        concurrent_load AS (
+            SELECT
+                sr.backend_id,
+                SUM(sr.load) AS load,
+                SUM(sr.taken) AS taken
+            FROM (
+                SELECT DISTINCT backend_id, LOWER(time) AS at
+                FROM scheduled_room
+            ) AS p
+            INNER JOIN scheduled_room AS sr
+            ON  sr.backend_id = p.backend_id
+            AND LOWER(sr.time) <= p.at
+            AND (UPPER(sr.time) IS NULL OR UPPER(sr.time) > p.at)
+            GROUP BY sr.backend_id, p.at
+        ),
+        janus_backend_load AS (
+            SELECT
+                backend_id,
+                MAX(load) AS load,
+                MAX(taken) AS taken
+            FROM concurrent_load
+            GROUP BY backend_id
+        )"#,
+    balancer_load_select!()
+);
+
+#[derive(QueryableByName, Debug)]
+pub struct BalancerLoadRow {
+    #[sql_type = "svc_agent::sql::Agent_id"]
+    pub backend_id: AgentId,
+    /// Balancer capacity falling back to the capacity.
+    #[sql_type = "diesel::sql_types::Integer"]
+    pub capacity: i32,
+    /// Sum of rooms' reserves or actual loads whichever is greater
+    /// at the peak within the period for `reserve_load`.
+    #[sql_type = "diesel::sql_types::Double"]
+    pub load: f64,
+    /// Sum of rooms' actual loads at the same point.
+    #[sql_type = "diesel::sql_types::Double"]
+    pub taken: f64,
+    #[sql_type = "diesel::sql_types::Nullable<diesel::sql_types::Text>"]
+    pub region: Option<String>,
+    #[sql_type = "diesel::sql_types::Nullable<diesel::sql_types::BigInt>"]
+    pub egress_bitrate: Option<i64>,
+    #[sql_type = "diesel::sql_types::Nullable<diesel::sql_types::Double>"]
+    pub cpu_usage: Option<f64>,
+    #[sql_type = "diesel::sql_types::Nullable<diesel::sql_types::Timestamptz>"]
+    pub stats_updated_at: Option<DateTime<Utc>>,
+}
+
+pub fn balancer_load(
     group: Option<&str>,
     conn: &PgConnection,
-) -> Result<Option<Object>, Error> {
+) -> Result<Vec<BalancerLoadRow>, Error> {
     use diesel::{
         prelude::*,
-        sql_types::{Nullable, Text, Uuid},
+        sql_types::{Nullable, Text},
     };
 
-    diesel::sql_query(MOST_LOADED_SQL)
-        .bind::<Uuid, _>(room_id)
+    diesel::sql_query(BALANCER_LOAD_SQL)
         .bind::<Text, _>(JANUS_API_VERSION)
         .bind::<Nullable<Text>, _>(group)
-        .get_result(conn)
-        .optional()
+        .get_results(conn)
 }
 
-// The same as above but finds the least loaded backend instead without considering the reserve.
-const LEAST_LOADED_SQL: &str = r#"
-    WITH
-        room_load AS (
-            SELECT
-                a.room_id,
-                SUM(COALESCE(rwc.video_remb, 1000000) / 1000000.0) AS taken
-            FROM agent AS a
-            INNER JOIN agent_connection AS ac
-            ON ac.agent_id = a.id
-            LEFT JOIN rtc
-            ON rtc.id = ac.rtc_id
-            LEFT JOIN rtc_writer_config AS rwc
-            ON rwc.rtc_id = rtc.id
-            GROUP BY a.room_id
-        ),
-        active_room AS (
-            SELECT *
-            FROM room
-            WHERE backend_id IS NOT NULL
-            AND   LOWER(time) <= NOW()
-            AND   (UPPER(time) IS NULL OR UPPER(time) > NOW())
-        ),
-        janus_backend_load AS (
-            SELECT
-                backend_id,
-                SUM(taken) AS load
-            FROM (
-                SELECT DISTINCT ON(backend_id, room_id)
-                    ar.backend_id,
-                    ar.id                 AS room_id,
-                    COALESCE(rl.taken, 0) AS taken
-                FROM active_room AS ar
-                LEFT JOIN room_load AS rl
-                ON rl.room_id = ar.id
-            ) AS sub
-            GROUP BY backend_id
-        )
-    SELECT jb.*
-    FROM janus_backend AS jb
-    LEFT JOIN janus_backend_load AS jbl
-    ON jbl.backend_id = jb.id
-    LEFT JOIN room AS r2
-    ON 1 = 1
-    WHERE r2.id = $1
-    AND   jb.api_version = $2
-    AND   ($3 IS NULL OR jb."group" = $3)
-    ORDER BY
-        COALESCE(jb.balancer_capacity, jb.capacity, 2147483647) - COALESCE(jbl.load, 0) DESC,
-        RANDOM()
-    LIMIT 1
-"#;
-
-pub fn least_loaded(
+pub fn reserve_load(
     room_id: db::room::Id,
+    period: db::room::Time,
     group: Option<&str>,
     conn: &PgConnection,
-) -> Result<Option<Object>, Error> {
+) -> Result<Vec<BalancerLoadRow>, Error> {
     use diesel::{
         prelude::*,
-        sql_types::{Nullable, Text, Uuid},
+        sql_types::{Nullable, Text, Tstzrange, Uuid},
     };
 
-    diesel::sql_query(LEAST_LOADED_SQL)
-        .bind::<Uuid, _>(room_id)
+    diesel::sql_query(RESERVE_LOAD_SQL)
         .bind::<Text, _>(JANUS_API_VERSION)
         .bind::<Nullable<Text>, _>(group)
-        .get_result(conn)
-        .optional()
+        .bind::<Tstzrange, _>(period)
+        .bind::<Uuid, _>(room_id)
+        .get_results(conn)
 }
 
 ////////////////////////////////////////////////////////////////////////////////
 
 // Similar to the previous one but returns the number of free slots for the room on the backend
 // that hosts the active stream for the given RTC.
-const FREE_CAPACITY_SQL: &str = r#"
+const FREE_CAPACITY_SQL: &str = concat!(
+    r#"
     WITH
-        room_load AS (
-            SELECT
-                a.room_id,
-                SUM(COALESCE(rwc.video_remb, 1000000) / 1000000.0) AS taken
-            FROM agent AS a
-            INNER JOIN agent_connection AS ac
-            ON ac.agent_id = a.id
-            LEFT JOIN rtc
-            ON rtc.id = ac.rtc_id
-            LEFT JOIN rtc_writer_config AS rwc
-            ON rwc.rtc_id = rtc.id
-            GROUP BY a.room_id
-        ),
+        "#,
+    room_load_cte!(),
+    r#",
         active_room AS (
             SELECT *
             FROM room
@@ -452,7 +680,8 @@ const FREE_CAPACITY_SQL: &str = r#"
     LEFT JOIN janus_backend_load AS jbl
     ON jbl.backend_id = jb.id
     WHERE rtc.id = $1
-"#;
+"#
+);
 
 #[derive(QueryableByName)]
 struct FreeCapacityQueryRow {
@@ -506,21 +735,12 @@ pub fn reserve_load_for_each_backend(
     diesel::sql_query(LOAD_FOR_EACH_BACKEND).get_results(conn)
 }
 
-const LOAD_FOR_EACH_BACKEND: &str = r#"
+const LOAD_FOR_EACH_BACKEND: &str = concat!(
+    r#"
 WITH
-    room_load AS (
-        SELECT
-            a.room_id,
-            SUM(COALESCE(rwc.video_remb, 1000000) / 1000000.0) AS taken
-        FROM agent AS a
-        INNER JOIN agent_connection AS ac
-        ON ac.agent_id = a.id
-        LEFT JOIN rtc
-        ON rtc.id = ac.rtc_id
-        LEFT JOIN rtc_writer_config AS rwc
-        ON rwc.rtc_id = rtc.id
-        GROUP BY a.room_id
-    ),
+    "#,
+    room_load_cte!(),
+    r#",
     active_room AS (
         SELECT *
         FROM room
@@ -552,16 +772,49 @@ SELECT
 FROM janus_backend jb
 LEFT OUTER JOIN janus_backend_load jbl
 ON jb.id = jbl.backend_id;
+"#
+);
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(QueryableByName, Debug)]
+pub struct ConnectedAgentsRow {
+    #[sql_type = "svc_agent::sql::Agent_id"]
+    pub backend_id: AgentId,
+    #[sql_type = "diesel::sql_types::BigInt"]
+    pub agents: i64,
+}
+
+pub fn connected_agents_for_each_backend(
+    conn: &PgConnection,
+) -> Result<Vec<ConnectedAgentsRow>, Error> {
+    use diesel::prelude::*;
+
+    diesel::sql_query(CONNECTED_AGENTS_FOR_EACH_BACKEND).get_results(conn)
+}
+
+const CONNECTED_AGENTS_FOR_EACH_BACKEND: &str = r#"
+SELECT
+    r.backend_id,
+    COUNT(DISTINCT ac.agent_id)::BIGINT AS agents
+FROM agent_connection AS ac
+INNER JOIN rtc
+ON rtc.id = ac.rtc_id
+INNER JOIN room AS r
+ON r.id = rtc.room_id
+WHERE r.backend_id IS NOT NULL
+GROUP BY r.backend_id;
 "#;
 
 #[cfg(test)]
 mod tests {
     use chrono::{Duration, Utc};
+    use diesel::Identifiable;
     use std::ops::Bound;
 
     use crate::{
         backend::janus::client::{HandleId, SessionId},
-        db::rtc::SharingPolicy as RtcSharingPolicy,
+        db::{janus_rtc_stream::Kind as StreamKind, rtc::SharingPolicy as RtcSharingPolicy},
         test_helpers::{prelude::*, test_deps::LocalDeps},
     };
 
@@ -649,4 +902,135 @@ mod tests {
                 assert_eq!(b.load, *expected_load as i64);
             });
     }
+
+    #[async_std::test]
+    async fn audio_only_taken() {
+        let now = Utc::now();
+
+        let local_deps = LocalDeps::new();
+        let postgres = local_deps.run_postgres();
+        let conn = TestDb::with_local_postgres(&postgres)
+            .connection_pool()
+            .get()
+            .expect("Failed to get db conn");
+
+        // Insert two backends hosting a room each.
+        let camera_backend = shared_helpers::insert_janus_backend(
+            &conn,
+            "test",
+            SessionId::random(),
+            HandleId::random(),
+        );
+
+        let audio_backend = shared_helpers::insert_janus_backend(
+            &conn,
+            "test",
+            SessionId::random(),
+            HandleId::random(),
+        );
+
+        for (backend, kind) in &[
+            (&camera_backend, StreamKind::Camera),
+            (&audio_backend, StreamKind::AudioOnly),
+        ] {
+            let room = factory::Room::new()
+                .audience(USR_AUDIENCE)
+                .time((
+                    Bound::Included(now),
+                    Bound::Excluded(now + Duration::hours(1)),
+                ))
+                .rtc_sharing_policy(RtcSharingPolicy::Owned)
+                .backend_id(backend.id())
+                .insert(&conn);
+
+            // Connect 3 agents with streams of the kind.
+            for i in 0..3 {
+                let agent = TestAgent::new("web", &format!("user{}", i), USR_AUDIENCE);
+                let agent = shared_helpers::insert_agent(&conn, agent.agent_id(), room.id());
+                let rtc = shared_helpers::insert_rtc_with_room(&conn, &room);
+
+                factory::RtcWriterConfig::new(&rtc)
+                    .kind(*kind)
+                    .insert(&conn);
+
+                factory::AgentConnection::new(*agent.id(), rtc.id(), HandleId::random())
+                    .kind(*kind)
+                    .insert(&conn);
+            }
+        }
+
+        // Audio-only writers should take only the audio bitrate.
+        let loads = super::balancer_load(None, &conn).expect("Db query failed");
+
+        let taken = |backend: &super::Object| {
+            loads
+                .iter()
+                .find(|load| load.backend_id == *backend.id())
+                .expect("Failed to find backend in query results")
+                .taken
+        };
+
+        assert!((taken(&camera_backend) - 3.0).abs() < 1e-9);
+        assert!((taken(&audio_backend) - 0.192).abs() < 1e-9);
+    }
+
+    #[async_std::test]
+    async fn reserve_load_peak() {
+        let now = Utc::now();
+
+        let local_deps = LocalDeps::new();
+        let postgres = local_deps.run_postgres();
+        let conn = TestDb::with_local_postgres(&postgres)
+            .connection_pool()
+            .get()
+            .expect("Failed to get db conn");
+
+        let backend = shared_helpers::insert_janus_backend(
+            &conn,
+            "test",
+            SessionId::random(),
+            HandleId::random(),
+        );
+
+        // Two consecutive rooms and one overlapping both of them.
+        for (start, end, reserve) in &[
+            (Duration::hours(1), Duration::hours(2), 60),
+            (Duration::hours(2), Duration::hours(3), 60),
+            (Duration::minutes(90), Duration::minutes(150), 30),
+        ] {
+            factory::Room::new()
+                .audience(USR_AUDIENCE)
+                .time((Bound::Included(now + *start), Bound::Excluded(now + *end)))
+                .rtc_sharing_policy(RtcSharingPolicy::Shared)
+                .reserve(*reserve)
+                .backend_id(backend.id())
+                .insert(&conn);
+        }
+
+        // A room without closing time outside of the period.
+        factory::Room::new()
+            .audience(USR_AUDIENCE)
+            .time((Bound::Included(now + Duration::hours(5)), Bound::Unbounded))
+            .rtc_sharing_policy(RtcSharingPolicy::Shared)
+            .reserve(100)
+            .backend_id(backend.id())
+            .insert(&conn);
+
+        let period = (
+            Bound::Included(now),
+            Bound::Excluded(now + Duration::hours(4)),
+        );
+
+        let loads = super::reserve_load(crate::db::room::Id::random(), period, None, &conn)
+            .expect("Db query failed");
+
+        // The peak of concurrent reserves rather than their sum.
+        let load = loads
+            .iter()
+            .find(|load| load.backend_id == *backend.id())
+            .expect("Failed to find backend in query results")
+            .load;
+
+        assert!((load - 90.0).abs() < 1e-9);
+    }
 }
@@ -12,6 +12,7 @@ use crate::{
     schema::{janus_rtc_stream, rtc},
 };
 use derive_more::{Display, FromStr};
+use diesel_derive_enum::DbEnum;
 use diesel_derive_newtype::DieselNewType;
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -29,6 +30,7 @@ type AllColumns = (
     janus_rtc_stream::sent_by,
     janus_rtc_stream::time,
     janus_rtc_stream::created_at,
+    janus_rtc_stream::kind,
 );
 const ALL_COLUMNS: AllColumns = (
     janus_rtc_stream::id,
@@ -39,6 +41,7 @@ const ALL_COLUMNS: AllColumns = (
     janus_rtc_stream::sent_by,
     janus_rtc_stream::time,
     janus_rtc_stream::created_at,
+    janus_rtc_stream::kind,
 );
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -53,6 +56,34 @@ impl Id {
     }
 }
 
+/// What the stream carries. An agent may publish one stream of each kind to the same RTC
+/// using a separate handle for each.
+#[derive(Clone, Copy, Debug, DbEnum, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+#[PgType = "rtc_stream_kind"]
+#[DieselType = "Rtc_stream_kind"]
+pub enum Kind {
+    Camera,
+    Screen,
+    AudioOnly,
+}
+
+impl Default for Kind {
+    fn default() -> Self {
+        Self::Camera
+    }
+}
+
+impl std::fmt::Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Camera => write!(f, "camera"),
+            Self::Screen => write!(f, "screen"),
+            Self::AudioOnly => write!(f, "audio-only"),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Identifiable, Queryable, QueryableByName, Associations)]
 #[table_name = "janus_rtc_stream"]
 pub struct Object {
@@ -67,6 +98,7 @@ pub struct Object {
     time: Option<Time>,
     #[serde(with = "ts_seconds")]
     created_at: DateTime<Utc>,
+    kind: Kind,
 }
 
 impl Object {
@@ -96,6 +128,10 @@ impl Object {
         &self.sent_by
     }
 
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
     pub fn time(&self) -> Option<Time> {
         self.time
     }
@@ -122,6 +158,7 @@ const ACTIVE_SQL: &str = r#"(
 pub struct ListQuery {
     room_id: Option<db::room::Id>,
     rtc_id: Option<db::rtc::Id>,
+    kind: Option<Kind>,
     time: Option<Time>,
     active: Option<bool>,
     offset: Option<i64>,
@@ -147,6 +184,13 @@ impl ListQuery {
         }
     }
 
+    pub fn kind(self, kind: Kind) -> Self {
+        Self {
+            kind: Some(kind),
+            ..self
+        }
+    }
+
     pub fn time(self, time: Time) -> Self {
         Self {
             time: Some(time),
@@ -182,6 +226,9 @@ impl ListQuery {
         if let Some(rtc_id) = self.rtc_id {
             q = q.filter(janus_rtc_stream::rtc_id.eq(rtc_id));
         }
+        if let Some(kind) = self.kind {
+            q = q.filter(janus_rtc_stream::kind.eq(kind));
+        }
         if let Some(time) = self.time {
             q = q.filter(sql("time && ").bind::<Tstzrange, _>(time));
         }
@@ -266,6 +313,7 @@ pub struct InsertQuery<'a> {
     backend_id: &'a AgentId,
     label: &'a str,
     sent_by: &'a AgentId,
+    kind: Kind,
 }
 
 impl<'a> InsertQuery<'a> {
@@ -276,6 +324,7 @@ impl<'a> InsertQuery<'a> {
         backend_id: &'a AgentId,
         label: &'a str,
         sent_by: &'a AgentId,
+        kind: Kind,
     ) -> Self {
         Self {
             id,
@@ -284,6 +333,7 @@ impl<'a> InsertQuery<'a> {
             backend_id,
             label,
             sent_by,
+            kind,
         }
     }
 
@@ -0,0 +1,222 @@
+use chrono::{serde::ts_milliseconds, DateTime, Utc};
+use diesel::{pg::PgConnection, result::Error};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use svc_agent::AgentId;
+use uuid::Uuid;
+
+use crate::{db, db::room::Object as Room, schema::message};
+
+////////////////////////////////////////////////////////////////////////////////
+
+type AllColumns = (
+    message::id,
+    message::room_id,
+    message::agent_id,
+    message::label,
+    message::data,
+    message::created_at,
+);
+
+const ALL_COLUMNS: AllColumns = (
+    message::id,
+    message::room_id,
+    message::agent_id,
+    message::label,
+    message::data,
+    message::created_at,
+);
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A stored `message.broadcast` for rooms that keep message history.
+#[derive(Debug, Deserialize, Serialize, Identifiable, Queryable, QueryableByName, Associations)]
+#[belongs_to(Room, foreign_key = "room_id")]
+#[table_name = "message"]
+pub struct Object {
+    id: Uuid,
+    room_id: db::room::Id,
+    agent_id: AgentId,
+    label: String,
+    data: JsonValue,
+    #[serde(with = "ts_milliseconds")]
+    created_at: DateTime<Utc>,
+}
+
+impl Object {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn room_id(&self) -> db::room::Id {
+        self.room_id
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    #[cfg(test)]
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    #[cfg(test)]
+    pub fn data(&self) -> &JsonValue {
+        &self.data
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub struct FindQuery {
+    id: Uuid,
+}
+
+impl FindQuery {
+    pub fn new(id: Uuid) -> Self {
+        Self { id }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<Option<Object>, Error> {
+        use diesel::prelude::*;
+
+        message::table
+            .filter(message::id.eq(self.id))
+            .select(ALL_COLUMNS)
+            .get_result(conn)
+            .optional()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Lists the room's messages from the newest to the oldest.
+#[derive(Debug)]
+pub struct ListQuery<'a> {
+    room_id: db::room::Id,
+    label: Option<&'a str>,
+    before: Option<&'a Object>,
+    limit: Option<i64>,
+}
+
+impl<'a> ListQuery<'a> {
+    pub fn new(room_id: db::room::Id) -> Self {
+        Self {
+            room_id,
+            label: None,
+            before: None,
+            limit: None,
+        }
+    }
+
+    pub fn label(self, label: &'a str) -> Self {
+        Self {
+            label: Some(label),
+            ..self
+        }
+    }
+
+    /// Only messages going after the given one in the listing order so the last message
+    /// of the previous page may be used to fetch the next one. Messages created at the same
+    /// time are ordered by id.
+    pub fn before(self, before: &'a Object) -> Self {
+        Self {
+            before: Some(before),
+            ..self
+        }
+    }
+
+    pub fn limit(self, limit: i64) -> Self {
+        Self {
+            limit: Some(limit),
+            ..self
+        }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<Vec<Object>, Error> {
+        use diesel::prelude::*;
+
+        let mut q = message::table
+            .filter(message::room_id.eq(self.room_id))
+            .into_boxed();
+
+        if let Some(label) = self.label {
+            q = q.filter(message::label.eq(label));
+        }
+
+        if let Some(before) = self.before {
+            q = q.filter(
+                message::created_at
+                    .lt(before.created_at)
+                    .or(message::created_at
+                        .eq(before.created_at)
+                        .and(message::id.lt(before.id))),
+            );
+        }
+
+        if let Some(limit) = self.limit {
+            q = q.limit(limit);
+        }
+
+        q.select(ALL_COLUMNS)
+            .order_by((message::created_at.desc(), message::id.desc()))
+            .get_results(conn)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Insertable)]
+#[table_name = "message"]
+pub struct InsertQuery<'a> {
+    room_id: db::room::Id,
+    agent_id: &'a AgentId,
+    label: &'a str,
+    data: &'a JsonValue,
+}
+
+impl<'a> InsertQuery<'a> {
+    pub fn new(
+        room_id: db::room::Id,
+        agent_id: &'a AgentId,
+        label: &'a str,
+        data: &'a JsonValue,
+    ) -> Self {
+        Self {
+            room_id,
+            agent_id,
+            label,
+            data,
+        }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<Object, Error> {
+        use diesel::prelude::*;
+
+        diesel::insert_into(message::table)
+            .values(self)
+            .returning(ALL_COLUMNS)
+            .get_result(conn)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub struct DeleteQuery {
+    id: Uuid,
+}
+
+impl DeleteQuery {
+    pub fn new(id: Uuid) -> Self {
+        Self { id }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<usize, Error> {
+        use diesel::prelude::*;
+
+        diesel::delete(message::table.filter(message::id.eq(self.id))).execute(conn)
+    }
+}
@@ -22,18 +22,25 @@ pub fn create_pool(url: &str, size: u32, idle_size: Option<u32>, timeout: u64) -
 
 pub mod sql {
     pub use super::{
-        agent::Agent_status, recording::Recording_status, room::Room_backend,
-        rtc::Rtc_sharing_policy,
+        agent::Agent_status, agent_session::Agent_session_leave_reason,
+        janus_rtc_stream::Rtc_stream_kind, recording::Recording_status, room::Room_backend,
+        room_role::Agent_role, rtc::Rtc_sharing_policy,
     };
     pub use svc_agent::sql::{Account_id, Agent_id};
 }
 
 pub mod agent;
 pub mod agent_connection;
+pub mod agent_session;
+pub mod hand_raise;
 pub mod janus_backend;
 pub mod janus_rtc_stream;
+pub mod message;
+pub mod pending_unicast;
 pub mod recording;
 pub mod room;
+pub mod room_ban;
+pub mod room_role;
 pub mod rtc;
 pub mod rtc_reader_config;
 pub mod rtc_writer_config;
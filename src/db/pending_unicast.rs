@@ -0,0 +1,134 @@
+use chrono::{DateTime, Utc};
+use diesel::{pg::PgConnection, result::Error};
+use serde_json::Value as JsonValue;
+use svc_agent::{mqtt::IncomingRequestProperties, AgentId};
+use uuid::Uuid;
+
+use crate::{db, db::room::Object as Room, schema::pending_unicast};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A `message.unicast` request forwarded to the target agent and waiting for its response.
+/// Kept in the DB since the response may come to any instance. Whoever deletes the row first
+/// is the one to respond so the sender gets exactly one response.
+#[derive(Debug, Identifiable, Queryable, QueryableByName, Associations)]
+#[belongs_to(Room, foreign_key = "room_id")]
+#[table_name = "pending_unicast"]
+pub struct Object {
+    id: Uuid,
+    room_id: db::room::Id,
+    target: AgentId,
+    reqp: JsonValue,
+    deadline: DateTime<Utc>,
+    created_at: DateTime<Utc>,
+}
+
+impl Object {
+    /// Properties of the sender's request.
+    pub fn reqp(&self) -> Result<IncomingRequestProperties, serde_json::Error> {
+        serde_json::from_value(self.reqp.to_owned())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Insertable)]
+#[table_name = "pending_unicast"]
+pub struct InsertQuery<'a> {
+    id: Uuid,
+    room_id: db::room::Id,
+    target: &'a AgentId,
+    reqp: JsonValue,
+    deadline: DateTime<Utc>,
+}
+
+impl<'a> InsertQuery<'a> {
+    pub fn new(
+        id: Uuid,
+        room_id: db::room::Id,
+        target: &'a AgentId,
+        reqp: JsonValue,
+        deadline: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id,
+            room_id,
+            target,
+            reqp,
+            deadline,
+        }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<Object, Error> {
+        use diesel::prelude::*;
+
+        diesel::insert_into(pending_unicast::table)
+            .values(self)
+            .get_result(conn)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Deletes the pending request. Returns `None` if it has already been answered.
+#[derive(Debug)]
+pub struct DeleteQuery {
+    id: Uuid,
+}
+
+impl DeleteQuery {
+    pub fn new(id: Uuid) -> Self {
+        Self { id }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<Option<Object>, Error> {
+        use diesel::prelude::*;
+
+        diesel::delete(pending_unicast::table.find(self.id))
+            .get_result(conn)
+            .optional()
+    }
+}
+
+/// Deletes pending requests whose deadline has passed.
+pub fn delete_expired(now: DateTime<Utc>, conn: &PgConnection) -> Result<Vec<Object>, Error> {
+    use diesel::prelude::*;
+
+    let query = pending_unicast::table.filter(pending_unicast::deadline.le(now));
+    diesel::delete(query).get_results(conn)
+}
+
+/// Deletes pending requests to the agent in the room.
+pub fn delete_by_target(
+    room_id: db::room::Id,
+    target: &AgentId,
+    conn: &PgConnection,
+) -> Result<Vec<Object>, Error> {
+    use diesel::prelude::*;
+
+    let query = pending_unicast::table
+        .filter(pending_unicast::room_id.eq(room_id))
+        .filter(pending_unicast::target.eq(target));
+
+    diesel::delete(query).get_results(conn)
+}
+
+// Pending requests to agents that are not ready in the room anymore.
+const DELETE_LEFT_SQL: &str = r#"
+    DELETE FROM pending_unicast AS pu
+    WHERE NOT EXISTS (
+        SELECT 1
+        FROM agent AS a
+        WHERE a.room_id = pu.room_id
+        AND   a.agent_id = pu.target
+        AND   a.status = 'ready'
+    )
+    RETURNING pu.*
+"#;
+
+/// Deletes pending requests whose target agent has left the room.
+pub fn delete_left(conn: &PgConnection) -> Result<Vec<Object>, Error> {
+    use diesel::prelude::*;
+
+    diesel::sql_query(DELETE_LEFT_SQL).get_results(conn)
+}
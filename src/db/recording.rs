@@ -1,6 +1,6 @@
 use std::{fmt, ops::Bound};
 
-use super::rtc::Object as Rtc;
+use super::{janus_rtc_stream::Kind, rtc::Object as Rtc};
 use crate::db;
 use crate::schema::recording;
 use chrono::{DateTime, Utc};
@@ -16,6 +16,7 @@ pub type AllColumns = (
     recording::segments,
     recording::status,
     recording::mjr_dumps_uris,
+    recording::kind,
 );
 
 pub const ALL_COLUMNS: AllColumns = (
@@ -24,6 +25,7 @@ pub const ALL_COLUMNS: AllColumns = (
     recording::segments,
     recording::status,
     recording::mjr_dumps_uris,
+    recording::kind,
 );
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -50,9 +52,11 @@ impl fmt::Display for Status {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+/// Recording of the RTC's streams of the kind. Each kind is being recorded and uploaded
+/// by the backend separately.
 #[derive(Debug, Serialize, Identifiable, Associations, Queryable)]
 #[belongs_to(Rtc, foreign_key = "rtc_id")]
-#[primary_key(rtc_id)]
+#[primary_key(rtc_id, kind)]
 #[table_name = "recording"]
 pub struct Object {
     rtc_id: db::rtc::Id,
@@ -61,6 +65,7 @@ pub struct Object {
     segments: Option<Vec<Segment>>,
     status: Status,
     mjr_dumps_uris: Option<Vec<String>>,
+    kind: Kind,
 }
 
 impl Object {
@@ -68,6 +73,10 @@ impl Object {
         self.rtc_id
     }
 
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
     pub fn started_at(&self) -> &Option<DateTime<Utc>> {
         &self.started_at
     }
@@ -91,11 +100,12 @@ impl Object {
 #[derive(Debug)]
 pub struct FindQuery {
     rtc_id: db::rtc::Id,
+    kind: Kind,
 }
 
 impl FindQuery {
-    pub fn new(rtc_id: db::rtc::Id) -> Self {
-        Self { rtc_id }
+    pub fn new(rtc_id: db::rtc::Id, kind: Kind) -> Self {
+        Self { rtc_id, kind }
     }
 
     pub fn execute(self, conn: &PgConnection) -> Result<Option<Object>, Error> {
@@ -103,6 +113,7 @@ impl FindQuery {
 
         recording::table
             .filter(recording::rtc_id.eq(self.rtc_id))
+            .filter(recording::kind.eq(self.kind))
             .get_result(conn)
             .optional()
     }
@@ -114,11 +125,12 @@ impl FindQuery {
 #[table_name = "recording"]
 pub struct InsertQuery {
     rtc_id: db::rtc::Id,
+    kind: Kind,
 }
 
 impl InsertQuery {
-    pub fn new(rtc_id: db::rtc::Id) -> Self {
-        Self { rtc_id }
+    pub fn new(rtc_id: db::rtc::Id, kind: Kind) -> Self {
+        Self { rtc_id, kind }
     }
 
     pub fn execute(self, conn: &PgConnection) -> Result<Object, Error> {
@@ -133,9 +145,10 @@ impl InsertQuery {
 
 #[derive(Debug, Identifiable, AsChangeset)]
 #[table_name = "recording"]
-#[primary_key(rtc_id)]
+#[primary_key(rtc_id, kind)]
 pub struct UpdateQuery {
     rtc_id: db::rtc::Id,
+    kind: Kind,
     status: Option<Status>,
     started_at: Option<DateTime<Utc>>,
     segments: Option<Vec<Segment>>,
@@ -143,9 +156,10 @@ pub struct UpdateQuery {
 }
 
 impl UpdateQuery {
-    pub fn new(rtc_id: db::rtc::Id) -> Self {
+    pub fn new(rtc_id: db::rtc::Id, kind: Kind) -> Self {
         Self {
             rtc_id,
+            kind,
             status: None,
             started_at: None,
             segments: None,
@@ -36,6 +36,12 @@ type AllColumns = (
     room::backend_id,
     room::rtc_sharing_policy,
     room::classroom_id,
+    room::lobby_enabled,
+    room::default_writer_config,
+    room::default_reader_config,
+    room::persistent_message_labels,
+    room::region,
+    room::parent_room_id,
 );
 
 const ALL_COLUMNS: AllColumns = (
@@ -49,6 +55,12 @@ const ALL_COLUMNS: AllColumns = (
     room::backend_id,
     room::rtc_sharing_policy,
     room::classroom_id,
+    room::lobby_enabled,
+    room::default_writer_config,
+    room::default_reader_config,
+    room::persistent_message_labels,
+    room::region,
+    room::parent_room_id,
 );
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -101,6 +113,62 @@ impl From<RoomBackend> for RtcSharingPolicy {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+/// Values that new writer configs in the room start from.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct WriterConfigDefaults {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    send_video: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    send_audio: Option<bool>,
+}
+
+impl WriterConfigDefaults {
+    #[cfg(test)]
+    pub fn new(send_video: Option<bool>, send_audio: Option<bool>) -> Self {
+        Self {
+            send_video,
+            send_audio,
+        }
+    }
+
+    pub fn send_video(&self) -> Option<bool> {
+        self.send_video
+    }
+
+    pub fn send_audio(&self) -> Option<bool> {
+        self.send_audio
+    }
+}
+
+/// Values that new reader configs in the room start from.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ReaderConfigDefaults {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    receive_video: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    receive_audio: Option<bool>,
+}
+
+impl ReaderConfigDefaults {
+    #[cfg(test)]
+    pub fn new(receive_video: Option<bool>, receive_audio: Option<bool>) -> Self {
+        Self {
+            receive_video,
+            receive_audio,
+        }
+    }
+
+    pub fn receive_video(&self) -> Option<bool> {
+        self.receive_video
+    }
+
+    pub fn receive_audio(&self) -> Option<bool> {
+        self.receive_audio
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 #[derive(
     Clone, Debug, Deserialize, Serialize, Identifiable, Queryable, QueryableByName, Associations,
 )]
@@ -121,6 +189,16 @@ pub struct Object {
     backend_id: Option<AgentId>,
     rtc_sharing_policy: RtcSharingPolicy,
     classroom_id: Option<Uuid>,
+    lobby_enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_writer_config: Option<JsonValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_reader_config: Option<JsonValue>,
+    persistent_message_labels: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent_room_id: Option<Id>,
 }
 
 impl Object {
@@ -163,6 +241,37 @@ impl Object {
     pub fn classroom_id(&self) -> Option<Uuid> {
         self.classroom_id
     }
+
+    pub fn lobby_enabled(&self) -> bool {
+        self.lobby_enabled
+    }
+
+    pub fn default_writer_config(&self) -> Result<Option<WriterConfigDefaults>, serde_json::Error> {
+        self.default_writer_config
+            .as_ref()
+            .map(|value| serde_json::from_value(value.to_owned()))
+            .transpose()
+    }
+
+    pub fn default_reader_config(&self) -> Result<Option<ReaderConfigDefaults>, serde_json::Error> {
+        self.default_reader_config
+            .as_ref()
+            .map(|value| serde_json::from_value(value.to_owned()))
+            .transpose()
+    }
+
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    pub fn parent_room_id(&self) -> Option<Id> {
+        self.parent_room_id
+    }
+
+    /// Whether `message.broadcast` with the label gets stored in the room's message history.
+    pub fn is_message_persistent(&self, label: &str) -> bool {
+        self.persistent_message_labels.iter().any(|l| l == label)
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -251,6 +360,64 @@ pub fn finished_with_in_progress_recordings(
 
 ////////////////////////////////////////////////////////////////////////////////
 
+/// Returns backends hosting not yet closed rooms of the classroom except the given room.
+pub fn classroom_backend_ids(
+    classroom_id: Uuid,
+    except_room_id: Id,
+    conn: &PgConnection,
+) -> Result<Vec<AgentId>, Error> {
+    use diesel::{dsl::sql, prelude::*};
+
+    room::table
+        .filter(room::classroom_id.eq(classroom_id))
+        .filter(room::id.ne(except_room_id))
+        .filter(sql(
+            "(upper(\"room\".\"time\") is null or upper(\"room\".\"time\") > now())",
+        ))
+        .select(room::backend_id)
+        .distinct()
+        .get_results::<Option<AgentId>>(conn)
+        .map(|ids| ids.into_iter().flatten().collect())
+}
+
+// Arbitrary key of the advisory lock taken while reserving backends for rooms.
+const RESERVATION_LOCK_KEY: i64 = 0x726f_6f6d_7265_7376;
+
+/// Waits for other transactions reserving backends to finish and blocks new ones until
+/// the current transaction ends. Must be called within a transaction.
+pub fn lock_reservations(conn: &PgConnection) -> Result<(), Error> {
+    use diesel::{prelude::*, sql_types::BigInt};
+
+    diesel::sql_query("SELECT pg_advisory_xact_lock($1)")
+        .bind::<BigInt, _>(RESERVATION_LOCK_KEY)
+        .execute(conn)
+        .map(|_| ())
+}
+
+// Returns ids of rooms that are not closed yet grouped by the backends they are pinned to.
+pub fn pinned_room_ids(
+    backend_ids: &[&AgentId],
+    conn: &PgConnection,
+) -> Result<Vec<(AgentId, Id)>, Error> {
+    use diesel::{dsl::sql, prelude::*};
+
+    room::table
+        .filter(room::backend_id.eq_any(backend_ids))
+        .filter(sql(
+            "(upper(\"room\".\"time\") is null or upper(\"room\".\"time\") > now())",
+        ))
+        .order_by(room::created_at)
+        .select((room::backend_id, room::id))
+        .get_results::<(Option<AgentId>, Id)>(conn)
+        .map(|rows| {
+            rows.into_iter()
+                .filter_map(|(backend_id, room_id)| backend_id.map(|id| (id, room_id)))
+                .collect()
+        })
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Debug, Insertable)]
 #[table_name = "room"]
 pub struct InsertQuery<'a> {
@@ -262,6 +429,12 @@ pub struct InsertQuery<'a> {
     backend_id: Option<&'a AgentId>,
     rtc_sharing_policy: RtcSharingPolicy,
     classroom_id: Option<Uuid>,
+    lobby_enabled: Option<bool>,
+    default_writer_config: Option<JsonValue>,
+    default_reader_config: Option<JsonValue>,
+    persistent_message_labels: Option<&'a [String]>,
+    region: Option<&'a str>,
+    parent_room_id: Option<Id>,
 }
 
 impl<'a> InsertQuery<'a> {
@@ -275,6 +448,12 @@ impl<'a> InsertQuery<'a> {
             backend_id: None,
             rtc_sharing_policy,
             classroom_id: None,
+            lobby_enabled: None,
+            default_writer_config: None,
+            default_reader_config: None,
+            persistent_message_labels: None,
+            region: None,
+            parent_room_id: None,
         }
     }
 
@@ -307,6 +486,48 @@ impl<'a> InsertQuery<'a> {
         }
     }
 
+    pub fn lobby_enabled(self, lobby_enabled: bool) -> Self {
+        Self {
+            lobby_enabled: Some(lobby_enabled),
+            ..self
+        }
+    }
+
+    pub fn default_writer_config(self, default_writer_config: &WriterConfigDefaults) -> Self {
+        Self {
+            default_writer_config: serde_json::to_value(default_writer_config).ok(),
+            ..self
+        }
+    }
+
+    pub fn default_reader_config(self, default_reader_config: &ReaderConfigDefaults) -> Self {
+        Self {
+            default_reader_config: serde_json::to_value(default_reader_config).ok(),
+            ..self
+        }
+    }
+
+    pub fn persistent_message_labels(self, persistent_message_labels: &'a [String]) -> Self {
+        Self {
+            persistent_message_labels: Some(persistent_message_labels),
+            ..self
+        }
+    }
+
+    pub fn region(self, region: &'a str) -> Self {
+        Self {
+            region: Some(region),
+            ..self
+        }
+    }
+
+    pub fn parent_room_id(self, parent_room_id: Id) -> Self {
+        Self {
+            parent_room_id: Some(parent_room_id),
+            ..self
+        }
+    }
+
     pub fn execute(&self, conn: &PgConnection) -> Result<Object, Error> {
         use crate::schema::room::dsl::room;
         use diesel::RunQueryDsl;
@@ -326,6 +547,11 @@ pub struct UpdateQuery<'a> {
     tags: Option<JsonValue>,
     backend_id: Option<&'a AgentId>,
     classroom_id: Option<Uuid>,
+    lobby_enabled: Option<bool>,
+    default_writer_config: Option<Option<JsonValue>>,
+    default_reader_config: Option<Option<JsonValue>>,
+    persistent_message_labels: Option<Vec<String>>,
+    region: Option<&'a str>,
 }
 
 impl<'a> UpdateQuery<'a> {
@@ -337,6 +563,11 @@ impl<'a> UpdateQuery<'a> {
             reserve: Default::default(),
             tags: Default::default(),
             classroom_id: Default::default(),
+            lobby_enabled: Default::default(),
+            default_writer_config: Default::default(),
+            default_reader_config: Default::default(),
+            persistent_message_labels: Default::default(),
+            region: Default::default(),
         }
     }
 
@@ -363,6 +594,46 @@ impl<'a> UpdateQuery<'a> {
         }
     }
 
+    pub fn lobby_enabled(self, lobby_enabled: Option<bool>) -> Self {
+        Self {
+            lobby_enabled,
+            ..self
+        }
+    }
+
+    pub fn default_writer_config(
+        self,
+        default_writer_config: Option<Option<&WriterConfigDefaults>>,
+    ) -> Self {
+        Self {
+            default_writer_config: default_writer_config
+                .map(|c| c.and_then(|c| serde_json::to_value(c).ok())),
+            ..self
+        }
+    }
+
+    pub fn default_reader_config(
+        self,
+        default_reader_config: Option<Option<&ReaderConfigDefaults>>,
+    ) -> Self {
+        Self {
+            default_reader_config: default_reader_config
+                .map(|c| c.and_then(|c| serde_json::to_value(c).ok())),
+            ..self
+        }
+    }
+
+    pub fn persistent_message_labels(self, persistent_message_labels: Option<Vec<String>>) -> Self {
+        Self {
+            persistent_message_labels,
+            ..self
+        }
+    }
+
+    pub fn region(self, region: Option<&'a str>) -> Self {
+        Self { region, ..self }
+    }
+
     pub fn execute(&self, conn: &PgConnection) -> Result<Object, Error> {
         use diesel::prelude::*;
 
@@ -372,6 +643,97 @@ impl<'a> UpdateQuery<'a> {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+// Closes breakout rooms of the parent room that are not closed yet by moving their closing time
+// to now. Rooms not opened yet get a one second range ending now since the time can't be empty.
+const CLOSE_BREAKOUTS_SQL: &str = r#"
+    UPDATE room
+    SET time = (
+        CASE
+            WHEN LOWER_INF(time) OR LOWER(time) < NOW()
+                THEN TSTZRANGE(LOWER(time), NOW())
+            ELSE TSTZRANGE(NOW() - INTERVAL '1 second', NOW())
+        END
+    )
+    WHERE parent_room_id = $1
+    AND   (UPPER(time) IS NULL OR UPPER(time) > NOW())
+    RETURNING *
+"#;
+
+pub fn close_breakouts(parent_room_id: Id, conn: &PgConnection) -> Result<Vec<Object>, Error> {
+    use diesel::{prelude::*, sql_types::Uuid};
+
+    diesel::sql_query(CLOSE_BREAKOUTS_SQL)
+        .bind::<Uuid, _>(parent_room_id)
+        .get_results(conn)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+// Returns currently open rooms with their actual loads the same way as the balancer counts them.
+const OPEN_ROOM_LOAD_SQL: &str = r#"
+    WITH
+        room_load AS (
+            SELECT
+                a.room_id,
+                SUM(
+                    CASE
+                        WHEN ac.kind = 'audio_only' OR rwc.send_video = FALSE
+                            THEN COALESCE(rwc.audio_remb, 64000)
+                        ELSE COALESCE(rwc.video_remb, 1000000)
+                    END / 1000000.0
+                ) AS taken
+            FROM agent AS a
+            INNER JOIN agent_connection AS ac
+            ON ac.agent_id = a.id
+            LEFT JOIN rtc
+            ON rtc.id = ac.rtc_id
+            LEFT JOIN rtc_writer_config AS rwc
+            ON rwc.rtc_id = rtc.id
+            AND rwc.kind = ac.kind
+            GROUP BY a.room_id
+        )
+    SELECT
+        r.id,
+        r.audience,
+        r.reserve,
+        r.backend_id,
+        r.region,
+        r.classroom_id,
+        COALESCE(rl.taken, 0)::FLOAT8 AS taken
+    FROM room AS r
+    LEFT JOIN room_load AS rl
+    ON rl.room_id = r.id
+    WHERE LOWER(r.time) <= NOW()
+    AND   (UPPER(r.time) IS NULL OR UPPER(r.time) > NOW())
+"#;
+
+#[derive(QueryableByName, Debug)]
+pub struct OpenRoomLoadRow {
+    #[sql_type = "diesel::sql_types::Uuid"]
+    pub id: Id,
+    #[sql_type = "diesel::sql_types::Text"]
+    pub audience: String,
+    #[sql_type = "diesel::sql_types::Nullable<diesel::sql_types::Integer>"]
+    pub reserve: Option<i32>,
+    #[sql_type = "diesel::sql_types::Nullable<svc_agent::sql::Agent_id>"]
+    pub backend_id: Option<AgentId>,
+    #[sql_type = "diesel::sql_types::Nullable<diesel::sql_types::Text>"]
+    pub region: Option<String>,
+    #[sql_type = "diesel::sql_types::Nullable<diesel::sql_types::Uuid>"]
+    pub classroom_id: Option<Uuid>,
+    /// Sum of actual loads of the room's agent connections.
+    #[sql_type = "diesel::sql_types::Double"]
+    pub taken: f64,
+}
+
+pub fn open_room_loads(conn: &PgConnection) -> Result<Vec<OpenRoomLoadRow>, Error> {
+    use diesel::prelude::*;
+
+    diesel::sql_query(OPEN_ROOM_LOAD_SQL).get_results(conn)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 #[cfg(test)]
 mod tests {
     mod finished_with_in_progress_recordings {
@@ -0,0 +1,164 @@
+use chrono::{DateTime, Utc};
+use diesel::{pg::PgConnection, result::Error};
+use diesel_derive_enum::DbEnum;
+use serde::{Deserialize, Serialize};
+use svc_agent::AccountId;
+
+use crate::{db, db::room::Object as Room, schema::room_role};
+
+////////////////////////////////////////////////////////////////////////////////
+
+type AllColumns = (
+    room_role::room_id,
+    room_role::account_id,
+    room_role::role,
+    room_role::created_at,
+);
+
+const ALL_COLUMNS: AllColumns = (
+    room_role::room_id,
+    room_role::account_id,
+    room_role::role,
+    room_role::created_at,
+);
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, DbEnum, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+#[PgType = "agent_role"]
+#[DieselType = "Agent_role"]
+pub enum Role {
+    Moderator,
+    Speaker,
+    Listener,
+}
+
+#[derive(Debug, Identifiable, Queryable, QueryableByName, Associations)]
+#[belongs_to(Room, foreign_key = "room_id")]
+#[table_name = "room_role"]
+#[primary_key(room_id, account_id)]
+pub struct Object {
+    room_id: db::room::Id,
+    account_id: AccountId,
+    role: Role,
+    created_at: DateTime<Utc>,
+}
+
+impl Object {
+    pub fn account_id(&self) -> &AccountId {
+        &self.account_id
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub struct FindQuery<'a> {
+    room_id: db::room::Id,
+    account_id: &'a AccountId,
+}
+
+impl<'a> FindQuery<'a> {
+    pub fn new(room_id: db::room::Id, account_id: &'a AccountId) -> Self {
+        Self {
+            room_id,
+            account_id,
+        }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<Option<Object>, Error> {
+        use diesel::prelude::*;
+
+        room_role::table
+            .filter(room_role::room_id.eq(self.room_id))
+            .filter(room_role::account_id.eq(self.account_id))
+            .select(ALL_COLUMNS)
+            .get_result(conn)
+            .optional()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub struct ListQuery {
+    room_id: db::room::Id,
+}
+
+impl ListQuery {
+    pub fn new(room_id: db::room::Id) -> Self {
+        Self { room_id }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<Vec<Object>, Error> {
+        use diesel::prelude::*;
+
+        room_role::table
+            .filter(room_role::room_id.eq(self.room_id))
+            .select(ALL_COLUMNS)
+            .get_results(conn)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Insertable, AsChangeset)]
+#[table_name = "room_role"]
+pub struct UpsertQuery<'a> {
+    room_id: db::room::Id,
+    account_id: &'a AccountId,
+    role: Role,
+}
+
+impl<'a> UpsertQuery<'a> {
+    pub fn new(room_id: db::room::Id, account_id: &'a AccountId, role: Role) -> Self {
+        Self {
+            room_id,
+            account_id,
+            role,
+        }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<Object, Error> {
+        use diesel::prelude::*;
+
+        diesel::insert_into(room_role::table)
+            .values(self)
+            .on_conflict((room_role::room_id, room_role::account_id))
+            .do_update()
+            .set(self)
+            .get_result(conn)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub struct DeleteQuery<'a> {
+    room_id: db::room::Id,
+    account_id: &'a AccountId,
+}
+
+impl<'a> DeleteQuery<'a> {
+    pub fn new(room_id: db::room::Id, account_id: &'a AccountId) -> Self {
+        Self {
+            room_id,
+            account_id,
+        }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<usize, Error> {
+        use diesel::prelude::*;
+
+        let query = room_role::table
+            .filter(room_role::room_id.eq(self.room_id))
+            .filter(room_role::account_id.eq(self.account_id));
+
+        diesel::delete(query).execute(conn)
+    }
+}
@@ -15,9 +15,21 @@ use diesel_derive_newtype::DieselNewType;
 
 ////////////////////////////////////////////////////////////////////////////////
 
-pub type AllColumns = (rtc::id, rtc::room_id, rtc::created_at, rtc::created_by);
-
-pub const ALL_COLUMNS: AllColumns = (rtc::id, rtc::room_id, rtc::created_at, rtc::created_by);
+pub type AllColumns = (
+    rtc::id,
+    rtc::room_id,
+    rtc::created_at,
+    rtc::created_by,
+    rtc::deleted_at,
+);
+
+pub const ALL_COLUMNS: AllColumns = (
+    rtc::id,
+    rtc::room_id,
+    rtc::created_at,
+    rtc::created_by,
+    rtc::deleted_at,
+);
 
 ////////////////////////////////////////////////////////////////////////////////
 
@@ -61,6 +73,8 @@ pub struct Object {
     #[serde(with = "ts_seconds")]
     created_at: DateTime<Utc>,
     created_by: AgentId,
+    #[serde(skip)]
+    deleted_at: Option<DateTime<Utc>>,
 }
 
 impl Object {
@@ -75,6 +89,11 @@ impl Object {
     pub fn created_by(&self) -> &AgentId {
         &self.created_by
     }
+
+    /// Deleted RTCs are kept only to upload their recordings.
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -151,7 +170,7 @@ impl<'a> ListQuery<'a> {
     pub fn execute(&self, conn: &PgConnection) -> Result<Vec<Object>, Error> {
         use diesel::prelude::*;
 
-        let mut q = rtc::table.into_boxed();
+        let mut q = rtc::table.filter(rtc::deleted_at.is_null()).into_boxed();
 
         if let Some(room_id) = self.room_id {
             q = q.filter(rtc::room_id.eq(room_id));
@@ -220,3 +239,45 @@ impl<'a> InsertQuery<'a> {
         diesel::insert_into(rtc).values(self).get_result(conn)
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Hides the RTC while keeping its recording.
+#[derive(Debug)]
+pub struct SoftDeleteQuery {
+    id: Id,
+}
+
+impl SoftDeleteQuery {
+    pub fn new(id: Id) -> Self {
+        Self { id }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<Object, Error> {
+        use diesel::prelude::*;
+
+        diesel::update(rtc::table.find(self.id))
+            .set(rtc::deleted_at.eq(Utc::now()))
+            .get_result(conn)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Deletes the RTC along with its recording, streams, connections and configs.
+#[derive(Debug)]
+pub struct DeleteQuery {
+    id: Id,
+}
+
+impl DeleteQuery {
+    pub fn new(id: Id) -> Self {
+        Self { id }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<usize, Error> {
+        use diesel::prelude::*;
+
+        diesel::delete(rtc::table.find(self.id)).execute(conn)
+    }
+}
@@ -3,7 +3,7 @@ use svc_agent::AgentId;
 
 use crate::{
     db,
-    db::rtc::Object as Rtc,
+    db::{room::ReaderConfigDefaults, rtc::Object as Rtc},
     schema::{rtc, rtc_reader_config},
 };
 
@@ -77,6 +77,31 @@ impl<'a> ListWithRtcQuery<'a> {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+#[derive(Debug)]
+pub struct FindQuery<'a> {
+    rtc_id: db::rtc::Id,
+    reader_id: &'a AgentId,
+}
+
+impl<'a> FindQuery<'a> {
+    pub fn new(rtc_id: db::rtc::Id, reader_id: &'a AgentId) -> Self {
+        Self { rtc_id, reader_id }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<Option<Object>, Error> {
+        use diesel::prelude::*;
+
+        rtc_reader_config::table
+            .filter(rtc_reader_config::rtc_id.eq(self.rtc_id))
+            .filter(rtc_reader_config::reader_id.eq(self.reader_id))
+            .select(ALL_COLUMNS)
+            .get_result(conn)
+            .optional()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Clone, Debug, Insertable, AsChangeset)]
 #[table_name = "rtc_reader_config"]
 pub struct UpsertQuery<'a> {
@@ -110,6 +135,15 @@ impl<'a> UpsertQuery<'a> {
         }
     }
 
+    /// Fills the fields that are not set explicitly with the room defaults.
+    pub fn defaults(self, defaults: &ReaderConfigDefaults) -> Self {
+        Self {
+            receive_video: self.receive_video.or_else(|| defaults.receive_video()),
+            receive_audio: self.receive_audio.or_else(|| defaults.receive_audio()),
+            ..self
+        }
+    }
+
     pub fn execute(&self, conn: &PgConnection) -> Result<Object, Error> {
         use diesel::prelude::*;
 
@@ -131,3 +165,23 @@ impl<'a> UpsertQuery<'a> {
             .get_result(conn)
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub struct BulkDeleteByRtcQuery {
+    rtc_id: db::rtc::Id,
+}
+
+impl BulkDeleteByRtcQuery {
+    pub fn new(rtc_id: db::rtc::Id) -> Self {
+        Self { rtc_id }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<usize, Error> {
+        use diesel::prelude::*;
+
+        diesel::delete(rtc_reader_config::table.filter(rtc_reader_config::rtc_id.eq(self.rtc_id)))
+            .execute(conn)
+    }
+}
@@ -3,7 +3,7 @@ use svc_agent::AgentId;
 
 use crate::{
     db,
-    db::rtc::Object as Rtc,
+    db::{janus_rtc_stream::Kind, room::WriterConfigDefaults, rtc::Object as Rtc},
     schema::{rtc, rtc_writer_config},
 };
 
@@ -15,6 +15,13 @@ type AllColumns = (
     rtc_writer_config::send_audio,
     rtc_writer_config::video_remb,
     rtc_writer_config::send_audio_updated_by,
+    rtc_writer_config::kind,
+    rtc_writer_config::send_audio_locked,
+    rtc_writer_config::send_video_locked,
+    rtc_writer_config::audio_remb,
+    rtc_writer_config::max_height,
+    rtc_writer_config::max_width,
+    rtc_writer_config::max_framerate,
 );
 
 const ALL_COLUMNS: AllColumns = (
@@ -23,6 +30,13 @@ const ALL_COLUMNS: AllColumns = (
     rtc_writer_config::send_audio,
     rtc_writer_config::video_remb,
     rtc_writer_config::send_audio_updated_by,
+    rtc_writer_config::kind,
+    rtc_writer_config::send_audio_locked,
+    rtc_writer_config::send_video_locked,
+    rtc_writer_config::audio_remb,
+    rtc_writer_config::max_height,
+    rtc_writer_config::max_width,
+    rtc_writer_config::max_framerate,
 );
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -30,16 +44,27 @@ const ALL_COLUMNS: AllColumns = (
 #[derive(Debug, Identifiable, Queryable, QueryableByName, Associations)]
 #[belongs_to(Rtc, foreign_key = "rtc_id")]
 #[table_name = "rtc_writer_config"]
-#[primary_key(rtc_id)]
+#[primary_key(rtc_id, kind)]
 pub struct Object {
     rtc_id: db::rtc::Id,
     send_video: bool,
     send_audio: bool,
     video_remb: Option<i64>,
     send_audio_updated_by: Option<AgentId>,
+    kind: Kind,
+    send_audio_locked: bool,
+    send_video_locked: bool,
+    audio_remb: Option<i64>,
+    max_height: Option<i32>,
+    max_width: Option<i32>,
+    max_framerate: Option<i32>,
 }
 
 impl Object {
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
     pub fn send_video(&self) -> bool {
         self.send_video
     }
@@ -55,6 +80,30 @@ impl Object {
     pub fn send_audio_updated_by(&self) -> Option<&AgentId> {
         self.send_audio_updated_by.as_ref()
     }
+
+    pub fn send_audio_locked(&self) -> bool {
+        self.send_audio_locked
+    }
+
+    pub fn send_video_locked(&self) -> bool {
+        self.send_video_locked
+    }
+
+    pub fn audio_remb(&self) -> Option<i64> {
+        self.audio_remb
+    }
+
+    pub fn max_height(&self) -> Option<i32> {
+        self.max_height
+    }
+
+    pub fn max_width(&self) -> Option<i32> {
+        self.max_width
+    }
+
+    pub fn max_framerate(&self) -> Option<i32> {
+        self.max_framerate
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -82,27 +131,70 @@ impl ListWithRtcQuery {
 
 ////////////////////////////////////////////////////////////////////////////////
 
+#[derive(Debug)]
+pub struct FindQuery {
+    rtc_id: db::rtc::Id,
+    kind: Kind,
+}
+
+impl FindQuery {
+    pub fn new(rtc_id: db::rtc::Id, kind: Kind) -> Self {
+        Self { rtc_id, kind }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<Option<Object>, Error> {
+        use diesel::prelude::*;
+
+        rtc_writer_config::table
+            .filter(rtc_writer_config::rtc_id.eq(self.rtc_id))
+            .filter(rtc_writer_config::kind.eq(self.kind))
+            .select(ALL_COLUMNS)
+            .get_result(conn)
+            .optional()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 #[derive(Clone, Debug, Insertable, AsChangeset)]
 #[table_name = "rtc_writer_config"]
 pub struct UpsertQuery<'a> {
     rtc_id: db::rtc::Id,
+    kind: Kind,
     send_video: Option<bool>,
     send_audio: Option<bool>,
     video_remb: Option<i64>,
     send_audio_updated_by: Option<&'a AgentId>,
+    send_audio_locked: Option<bool>,
+    send_video_locked: Option<bool>,
+    audio_remb: Option<i64>,
+    max_height: Option<i32>,
+    max_width: Option<i32>,
+    max_framerate: Option<i32>,
 }
 
 impl<'a> UpsertQuery<'a> {
     pub fn new(rtc_id: db::rtc::Id) -> Self {
         Self {
             rtc_id,
+            kind: Kind::default(),
             send_audio: Default::default(),
             send_audio_updated_by: Default::default(),
             send_video: Default::default(),
             video_remb: Default::default(),
+            send_audio_locked: Default::default(),
+            send_video_locked: Default::default(),
+            audio_remb: Default::default(),
+            max_height: Default::default(),
+            max_width: Default::default(),
+            max_framerate: Default::default(),
         }
     }
 
+    pub fn kind(self, kind: Kind) -> Self {
+        Self { kind, ..self }
+    }
+
     pub fn send_video(self, send_video: bool) -> Self {
         Self {
             send_video: Some(send_video),
@@ -124,6 +216,34 @@ impl<'a> UpsertQuery<'a> {
         }
     }
 
+    pub fn audio_remb(self, audio_remb: i64) -> Self {
+        Self {
+            audio_remb: Some(audio_remb),
+            ..self
+        }
+    }
+
+    pub fn max_height(self, max_height: i32) -> Self {
+        Self {
+            max_height: Some(max_height),
+            ..self
+        }
+    }
+
+    pub fn max_width(self, max_width: i32) -> Self {
+        Self {
+            max_width: Some(max_width),
+            ..self
+        }
+    }
+
+    pub fn max_framerate(self, max_framerate: i32) -> Self {
+        Self {
+            max_framerate: Some(max_framerate),
+            ..self
+        }
+    }
+
     pub fn send_audio_updated_by(self, send_audio_updated_by: &'a AgentId) -> Self {
         Self {
             send_audio_updated_by: Some(send_audio_updated_by),
@@ -131,6 +251,29 @@ impl<'a> UpsertQuery<'a> {
         }
     }
 
+    /// Fills the fields that are not set explicitly with the room defaults.
+    pub fn defaults(self, defaults: &WriterConfigDefaults) -> Self {
+        Self {
+            send_video: self.send_video.or_else(|| defaults.send_video()),
+            send_audio: self.send_audio.or_else(|| defaults.send_audio()),
+            ..self
+        }
+    }
+
+    pub fn send_audio_locked(self, send_audio_locked: bool) -> Self {
+        Self {
+            send_audio_locked: Some(send_audio_locked),
+            ..self
+        }
+    }
+
+    pub fn send_video_locked(self, send_video_locked: bool) -> Self {
+        Self {
+            send_video_locked: Some(send_video_locked),
+            ..self
+        }
+    }
+
     pub fn execute(&self, conn: &PgConnection) -> Result<Object, Error> {
         use diesel::prelude::*;
 
@@ -144,11 +287,39 @@ impl<'a> UpsertQuery<'a> {
             insert_values.send_audio = Some(true);
         }
 
+        if insert_values.send_audio_locked.is_none() {
+            insert_values.send_audio_locked = Some(false);
+        }
+
+        if insert_values.send_video_locked.is_none() {
+            insert_values.send_video_locked = Some(false);
+        }
+
         diesel::insert_into(rtc_writer_config::table)
             .values(insert_values)
-            .on_conflict(rtc_writer_config::rtc_id)
+            .on_conflict((rtc_writer_config::rtc_id, rtc_writer_config::kind))
             .do_update()
             .set(self)
             .get_result(conn)
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub struct DeleteQuery {
+    rtc_id: db::rtc::Id,
+}
+
+impl DeleteQuery {
+    pub fn new(rtc_id: db::rtc::Id) -> Self {
+        Self { rtc_id }
+    }
+
+    pub fn execute(&self, conn: &PgConnection) -> Result<usize, Error> {
+        use diesel::prelude::*;
+
+        diesel::delete(rtc_writer_config::table.filter(rtc_writer_config::rtc_id.eq(self.rtc_id)))
+            .execute(conn)
+    }
+}
@@ -19,6 +19,12 @@ pub static LOG: Lazy<slog::Logger> = Lazy::new(|| {
 async fn main() -> Result<()> {
     let _guard = slog_envlogger::init().unwrap();
 
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    if let Some("simulate-balancer") = args.first().map(String::as_str) {
+        return simulation::run(&args[1..]);
+    }
+
     let db = {
         let url = var("DATABASE_URL").expect("DATABASE_URL must be specified");
         let size = var("DATABASE_POOL_SIZE")
@@ -86,6 +92,7 @@ mod db;
 #[allow(unused_imports)]
 mod schema;
 mod serde;
+mod simulation;
 #[cfg(test)]
 mod test_helpers;
 mod util;
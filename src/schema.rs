@@ -8,6 +8,7 @@ table! {
         room_id -> Uuid,
         created_at -> Timestamptz,
         status -> Agent_status,
+        speak_granted -> Bool,
     }
 }
 
@@ -15,11 +16,37 @@ table! {
     use diesel::sql_types::*;
     use crate::db::sql::*;
 
-    agent_connection (agent_id, rtc_id) {
+    agent_connection (agent_id, rtc_id, kind) {
         agent_id -> Uuid,
         handle_id -> Int8,
         created_at -> Timestamptz,
         rtc_id -> Uuid,
+        kind -> Rtc_stream_kind,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::db::sql::*;
+
+    agent_session (id) {
+        id -> Uuid,
+        room_id -> Uuid,
+        agent_id -> Agent_id,
+        entered_at -> Timestamptz,
+        left_at -> Nullable<Timestamptz>,
+        leave_reason -> Nullable<Agent_session_leave_reason>,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::db::sql::*;
+
+    hand_raise (room_id, agent_id) {
+        room_id -> Uuid,
+        agent_id -> Agent_id,
+        created_at -> Timestamptz,
     }
 }
 
@@ -37,6 +64,11 @@ table! {
         api_version -> Text,
         group -> Nullable<Text>,
         janus_url -> Text,
+        region -> Nullable<Text>,
+        egress_bitrate -> Nullable<Int8>,
+        cpu_usage -> Nullable<Float8>,
+        stats_updated_at -> Nullable<Timestamptz>,
+        draining -> Bool,
     }
 }
 
@@ -53,6 +85,7 @@ table! {
         sent_by -> Agent_id,
         time -> Nullable<Tstzrange>,
         created_at -> Timestamptz,
+        kind -> Rtc_stream_kind,
     }
 }
 
@@ -60,12 +93,41 @@ table! {
     use diesel::sql_types::*;
     use crate::db::sql::*;
 
-    recording (rtc_id) {
+    message (id) {
+        id -> Uuid,
+        room_id -> Uuid,
+        agent_id -> Agent_id,
+        label -> Text,
+        data -> Jsonb,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::db::sql::*;
+
+    pending_unicast (id) {
+        id -> Uuid,
+        room_id -> Uuid,
+        target -> Agent_id,
+        reqp -> Jsonb,
+        deadline -> Timestamptz,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::db::sql::*;
+
+    recording (rtc_id, kind) {
         rtc_id -> Uuid,
         started_at -> Nullable<Timestamptz>,
         segments -> Nullable<Array<Int8range>>,
         status -> Recording_status,
         mjr_dumps_uris -> Nullable<Array<Text>>,
+        kind -> Rtc_stream_kind,
     }
 }
 
@@ -84,6 +146,36 @@ table! {
         backend_id -> Nullable<Agent_id>,
         rtc_sharing_policy -> Rtc_sharing_policy,
         classroom_id -> Nullable<Uuid>,
+        lobby_enabled -> Bool,
+        default_writer_config -> Nullable<Jsonb>,
+        default_reader_config -> Nullable<Jsonb>,
+        persistent_message_labels -> Array<Text>,
+        region -> Nullable<Text>,
+        parent_room_id -> Nullable<Uuid>,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::db::sql::*;
+
+    room_ban (room_id, account_id) {
+        room_id -> Uuid,
+        account_id -> Account_id,
+        created_by -> Agent_id,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::db::sql::*;
+
+    room_role (room_id, account_id) {
+        room_id -> Uuid,
+        account_id -> Account_id,
+        role -> Agent_role,
+        created_at -> Timestamptz,
     }
 }
 
@@ -96,6 +188,7 @@ table! {
         room_id -> Uuid,
         created_at -> Timestamptz,
         created_by -> Agent_id,
+        deleted_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -115,21 +208,34 @@ table! {
     use diesel::sql_types::*;
     use crate::db::sql::*;
 
-    rtc_writer_config (rtc_id) {
+    rtc_writer_config (rtc_id, kind) {
         rtc_id -> Uuid,
         send_video -> Bool,
         send_audio -> Bool,
         video_remb -> Nullable<Int8>,
         send_audio_updated_by -> Nullable<Agent_id>,
+        kind -> Rtc_stream_kind,
+        send_audio_locked -> Bool,
+        send_video_locked -> Bool,
+        audio_remb -> Nullable<Int8>,
+        max_height -> Nullable<Int4>,
+        max_width -> Nullable<Int4>,
+        max_framerate -> Nullable<Int4>,
     }
 }
 
 joinable!(agent -> room (room_id));
 joinable!(agent_connection -> agent (agent_id));
 joinable!(agent_connection -> rtc (rtc_id));
+joinable!(agent_session -> room (room_id));
+joinable!(hand_raise -> room (room_id));
 joinable!(janus_rtc_stream -> janus_backend (backend_id));
 joinable!(janus_rtc_stream -> rtc (rtc_id));
+joinable!(message -> room (room_id));
+joinable!(pending_unicast -> room (room_id));
 joinable!(recording -> rtc (rtc_id));
+joinable!(room_ban -> room (room_id));
+joinable!(room_role -> room (room_id));
 joinable!(rtc -> room (room_id));
 joinable!(rtc_reader_config -> rtc (rtc_id));
 joinable!(rtc_writer_config -> rtc (rtc_id));
@@ -137,10 +243,16 @@ joinable!(rtc_writer_config -> rtc (rtc_id));
 allow_tables_to_appear_in_same_query!(
     agent,
     agent_connection,
+    agent_session,
+    hand_raise,
     janus_backend,
     janus_rtc_stream,
+    message,
+    pending_unicast,
     recording,
     room,
+    room_ban,
+    room_role,
     rtc,
     rtc_reader_config,
     rtc_writer_config,
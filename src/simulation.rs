@@ -0,0 +1,671 @@
+use std::{collections::HashMap, env::var, fs::File, io::BufReader};
+
+use anyhow::{anyhow, bail, Context as AnyhowContext, Result};
+use chrono::{DateTime, Utc};
+use diesel::{pg::PgConnection, Connection};
+use serde::{de::DeserializeOwned, Deserialize};
+use svc_agent::AgentId;
+use uuid::Uuid;
+
+use crate::{
+    app::{
+        balancer::{BackendLoad, BackendStats, Balancer, Selection},
+        endpoint::rtc::ConnectIntent,
+    },
+    backend::janus::JANUS_API_VERSION,
+    config::{self, BalancerConfig},
+    db,
+};
+
+const USAGE: &str =
+    "Usage: conference simulate-balancer (--snapshot <path> | --db) [--connects <path>]";
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Runs the balancer for a series of synthetic `rtc.connect` calls against a snapshot
+/// of backends and rooms and prints the resulting placement, backends' loads and rejected connects.
+/// The balancer strategies are taken from the config so its changes may be checked before deploying.
+pub fn run(args: &[String]) -> Result<()> {
+    let options = Options::parse(args)?;
+    let config = config::load().context("Failed to load config")?;
+
+    let mut snapshot = match options.source {
+        Source::Snapshot(ref path) => read_json::<Snapshot>(path)?,
+        Source::Db => load_snapshot_from_db()?,
+    };
+
+    if let Some(ref path) = options.connects {
+        snapshot.connects = read_json::<Vec<Connect>>(path)?;
+    }
+
+    let mut simulation = Simulation::new(config.balancer, config.janus_group, &snapshot);
+
+    for connect in &snapshot.connects {
+        simulation.connect(connect);
+    }
+
+    print!("{}", simulation.report());
+    Ok(())
+}
+
+enum Source {
+    Snapshot(String),
+    Db,
+}
+
+struct Options {
+    source: Source,
+    connects: Option<String>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut source = None;
+        let mut connects = None;
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--snapshot" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| anyhow!("Missing snapshot path\n\n{}", USAGE))?;
+
+                    source = Some(Source::Snapshot(path.to_owned()));
+                }
+                "--db" => source = Some(Source::Db),
+                "--connects" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| anyhow!("Missing connects path\n\n{}", USAGE))?;
+
+                    connects = Some(path.to_owned());
+                }
+                other => bail!("Unknown argument '{}'\n\n{}", other, USAGE),
+            }
+        }
+
+        let source = source
+            .ok_or_else(|| anyhow!("Either snapshot or DB must be specified\n\n{}", USAGE))?;
+
+        Ok(Self { source, connects })
+    }
+}
+
+fn read_json<T: DeserializeOwned>(path: &str) -> Result<T> {
+    let file = File::open(path).with_context(|| format!("Failed to open '{}'", path))?;
+    serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("Failed to parse '{}'", path))
+}
+
+/// Loads backends of the current API version except draining ones
+/// and currently open rooms with their actual loads.
+fn load_snapshot_from_db() -> Result<Snapshot> {
+    let url = var("DATABASE_URL").context("DATABASE_URL must be specified")?;
+    let conn = PgConnection::establish(&url).context("Failed to connect to the database")?;
+
+    let backends = db::janus_backend::ListQuery::new()
+        .execute(&conn)?
+        .into_iter()
+        .filter(|backend| backend.api_version() == JANUS_API_VERSION && !backend.is_draining())
+        .map(|backend| SnapshotBackend {
+            id: backend.id().to_owned(),
+            capacity: backend.capacity(),
+            balancer_capacity: backend.balancer_capacity(),
+            group: backend.group().map(ToOwned::to_owned),
+            region: backend.region().map(ToOwned::to_owned),
+            egress_bitrate: backend.egress_bitrate(),
+            cpu_usage: backend.cpu_usage(),
+            stats_updated_at: backend.stats_updated_at(),
+        })
+        .collect();
+
+    let rooms = db::room::open_room_loads(&conn)?
+        .into_iter()
+        .map(|row| SnapshotRoom {
+            id: row.id,
+            audience: row.audience,
+            reserve: row.reserve,
+            backend_id: row.backend_id,
+            region: row.region,
+            classroom_id: row.classroom_id,
+            taken: row.taken,
+        })
+        .collect();
+
+    Ok(Snapshot {
+        backends,
+        rooms,
+        connects: vec![],
+    })
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Backends and rooms considered open at the same time during the simulation.
+#[derive(Debug, Deserialize)]
+struct Snapshot {
+    backends: Vec<SnapshotBackend>,
+    #[serde(default)]
+    rooms: Vec<SnapshotRoom>,
+    #[serde(default)]
+    connects: Vec<Connect>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct SnapshotBackend {
+    id: AgentId,
+    capacity: Option<i32>,
+    balancer_capacity: Option<i32>,
+    group: Option<String>,
+    region: Option<String>,
+    /// Measured egress bitrate in bits per second.
+    egress_bitrate: Option<i64>,
+    /// Measured CPU usage from 0.0 to 1.0.
+    cpu_usage: Option<f64>,
+    /// Stats without the time are considered fresh.
+    stats_updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct SnapshotRoom {
+    id: db::room::Id,
+    audience: String,
+    reserve: Option<i32>,
+    backend_id: Option<AgentId>,
+    region: Option<String>,
+    classroom_id: Option<Uuid>,
+    /// Actual load of the agents already connected to the room.
+    #[serde(default)]
+    taken: f64,
+}
+
+/// Synthetic `rtc.connect` calls of a number of agents to the room.
+#[derive(Debug, Deserialize)]
+struct Connect {
+    room_id: db::room::Id,
+    intent: ConnectIntent,
+    #[serde(default = "Connect::default_count")]
+    count: usize,
+    /// Load of each agent connection; 1.0 stands for a video stream with the default bitrate.
+    #[serde(default = "Connect::default_load")]
+    load: f64,
+    region: Option<String>,
+}
+
+impl Connect {
+    fn default_count() -> usize {
+        1
+    }
+
+    fn default_load() -> f64 {
+        1.0
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RejectionReason {
+    RoomNotFound,
+    NoAvailableBackends,
+    BackendNotFound,
+    CapacityExceeded,
+}
+
+impl RejectionReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::RoomNotFound => "room_not_found",
+            Self::NoAvailableBackends => "no_available_backends",
+            Self::BackendNotFound => "backend_not_found",
+            Self::CapacityExceeded => "capacity_exceeded",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Placement {
+    room_id: db::room::Id,
+    intent: ConnectIntent,
+    backend_id: AgentId,
+    selection: &'static str,
+    count: usize,
+}
+
+#[derive(Debug)]
+struct Rejection {
+    room_id: db::room::Id,
+    intent: ConnectIntent,
+    reason: RejectionReason,
+    count: usize,
+}
+
+/// In-memory replay of the backend selection and capacity checks of `rtc.connect`.
+struct Simulation {
+    config: BalancerConfig,
+    group: Option<String>,
+    balancer: Balancer,
+    backends: Vec<SnapshotBackend>,
+    rooms: HashMap<db::room::Id, SnapshotRoom>,
+    placements: Vec<Placement>,
+    rejections: Vec<Rejection>,
+    now: DateTime<Utc>,
+}
+
+impl Simulation {
+    fn new(config: BalancerConfig, group: Option<String>, snapshot: &Snapshot) -> Self {
+        let rooms = snapshot
+            .rooms
+            .iter()
+            .map(|room| (room.id, room.to_owned()))
+            .collect();
+
+        Self {
+            config,
+            group,
+            balancer: Balancer::default(),
+            backends: snapshot.backends.to_owned(),
+            rooms,
+            placements: vec![],
+            rejections: vec![],
+            now: Utc::now(),
+        }
+    }
+
+    fn connect(&mut self, connect: &Connect) {
+        for _ in 0..connect.count {
+            if let Err(reason) = self.connect_agent(connect) {
+                match self.rejections.last_mut() {
+                    Some(last)
+                        if last.room_id == connect.room_id
+                            && last.intent == connect.intent
+                            && last.reason == reason =>
+                    {
+                        last.count += 1;
+                    }
+                    _ => self.rejections.push(Rejection {
+                        room_id: connect.room_id,
+                        intent: connect.intent,
+                        reason,
+                        count: 1,
+                    }),
+                }
+            }
+        }
+    }
+
+    fn connect_agent(&mut self, connect: &Connect) -> Result<(), RejectionReason> {
+        let room = self
+            .rooms
+            .get(&connect.room_id)
+            .ok_or(RejectionReason::RoomNotFound)?;
+
+        let room_has_backend = room.backend_id.is_some();
+
+        // Readers connecting before the first writer get a backend selected the same way
+        // as writers but it's not being assigned to the room.
+        let backend_id = match room.backend_id.to_owned() {
+            Some(backend_id) => backend_id,
+            None => {
+                let region = room.region.to_owned().or_else(|| connect.region.to_owned());
+                let (backend_id, selection) = self.select_backend(room, region.as_deref())?;
+                self.add_placement(connect, &backend_id, selection);
+
+                if connect.intent == ConnectIntent::Write {
+                    if let Some(room) = self.rooms.get_mut(&connect.room_id) {
+                        room.backend_id = Some(backend_id.to_owned());
+                        room.region = region;
+                    }
+                }
+
+                backend_id
+            }
+        };
+
+        // Readers are checked against the backend's free capacity the same way
+        // as in `rtc.connect` while writers are not checked at all. Like in `rtc.connect`
+        // there's no check for readers of a room without a backend.
+        if connect.intent == ConnectIntent::Read
+            && room_has_backend
+            && self.free_capacity(connect.room_id, &backend_id)? == 0
+        {
+            return Err(RejectionReason::CapacityExceeded);
+        }
+
+        if let Some(room) = self.rooms.get_mut(&connect.room_id) {
+            room.taken += connect.load;
+        }
+
+        Ok(())
+    }
+
+    fn select_backend(
+        &self,
+        room: &SnapshotRoom,
+        region: Option<&str>,
+    ) -> Result<(AgentId, &'static str), RejectionReason> {
+        let strategy_kind = self
+            .config
+            .strategy_kind(self.group.as_deref(), &room.audience);
+
+        let reserve = room.reserve.unwrap_or(1);
+        let backends = self.backend_loads();
+        let preferred = self.classroom_backend_ids(room);
+
+        let (backend, selection) = match self.balancer.select_preferred(
+            strategy_kind,
+            &backends,
+            reserve,
+            region,
+            &preferred,
+        ) {
+            Some(Selection::Capable(backend)) => (backend, "capable"),
+            Some(Selection::OutOfRegion(backend)) => (backend, "out_of_region"),
+            Some(Selection::Fallback(backend)) => (backend, "fallback"),
+            None => return Err(RejectionReason::NoAvailableBackends),
+        };
+
+        Ok((backend.id().to_owned(), selection))
+    }
+
+    fn add_placement(&mut self, connect: &Connect, backend_id: &AgentId, selection: &'static str) {
+        match self.placements.last_mut() {
+            Some(last)
+                if last.room_id == connect.room_id
+                    && last.intent == connect.intent
+                    && last.backend_id == *backend_id
+                    && last.selection == selection =>
+            {
+                last.count += 1;
+            }
+            _ => self.placements.push(Placement {
+                room_id: connect.room_id,
+                intent: connect.intent,
+                backend_id: backend_id.to_owned(),
+                selection,
+                count: 1,
+            }),
+        }
+    }
+
+    /// Backends hosting other rooms of the room's classroom if classroom stickiness is enabled.
+    fn classroom_backend_ids(&self, room: &SnapshotRoom) -> Vec<AgentId> {
+        let classroom_id = match room.classroom_id {
+            Some(classroom_id) if self.config.classroom_sticky => classroom_id,
+            _ => return vec![],
+        };
+
+        self.rooms
+            .values()
+            .filter(|other| other.id != room.id && other.classroom_id == Some(classroom_id))
+            .filter_map(|other| other.backend_id.to_owned())
+            .collect()
+    }
+
+    /// Loads of the backends in the group the same way as `endpoint::helpers::load_backends`.
+    /// Measured stats stay the same during the simulation.
+    fn backend_loads(&self) -> Vec<BackendLoad> {
+        self.backends
+            .iter()
+            .filter(|backend| self.group.is_none() || backend.group == self.group)
+            .map(|backend| {
+                let (load, taken, _) = self.backend_usage(&backend.id);
+
+                let capacity = backend
+                    .balancer_capacity
+                    .or(backend.capacity)
+                    .unwrap_or(i32::MAX);
+
+                let backend_load = BackendLoad::new(backend.id.to_owned(), capacity, load, taken);
+
+                let backend_load = match backend.region {
+                    Some(ref region) => backend_load.region(region),
+                    None => backend_load,
+                };
+
+                let backend_load = match (backend.egress_bitrate, backend.cpu_usage) {
+                    (None, None) => backend_load,
+                    (egress_bitrate, cpu_usage) => backend_load.stats(BackendStats {
+                        egress_bitrate,
+                        cpu_usage,
+                        reported_at: backend.stats_updated_at.unwrap_or(self.now),
+                    }),
+                };
+
+                match self.config.stats {
+                    Some(ref stats_config) => backend_load.blend_stats(stats_config, self.now),
+                    None => backend_load,
+                }
+            })
+            .collect()
+    }
+
+    /// Free capacity for the room's agents on the backend the same way as
+    /// `db::janus_backend::free_capacity`: zero if actual loads exceed the backend's capacity,
+    /// otherwise the rest of the room's reserve or the capacity not used by other rooms
+    /// but at least 1.
+    fn free_capacity(
+        &self,
+        room_id: db::room::Id,
+        backend_id: &AgentId,
+    ) -> Result<i32, RejectionReason> {
+        let room = self
+            .rooms
+            .get(&room_id)
+            .ok_or(RejectionReason::RoomNotFound)?;
+
+        let backend = self
+            .backends
+            .iter()
+            .find(|backend| &backend.id == backend_id)
+            .ok_or(RejectionReason::BackendNotFound)?;
+
+        let capacity = backend.capacity.unwrap_or(i32::MAX) as f64;
+        let (load, taken, _) = self.backend_usage(backend_id);
+
+        if capacity <= taken {
+            return Ok(0);
+        }
+
+        let reserve = room.reserve.unwrap_or(0) as f64;
+
+        let free_capacity = if reserve > room.taken {
+            (reserve - room.taken).min(capacity - taken)
+        } else {
+            (capacity - load).max(0.0)
+        };
+
+        Ok(free_capacity.max(1.0).round() as i32)
+    }
+
+    /// Returns the sum of rooms' reserves or actual loads whichever is greater,
+    /// the sum of actual loads and the number of rooms on the backend.
+    fn backend_usage(&self, backend_id: &AgentId) -> (f64, f64, usize) {
+        self.rooms
+            .values()
+            .filter(|room| room.backend_id.as_ref() == Some(backend_id))
+            .fold((0.0, 0.0, 0), |(load, taken, rooms), room| {
+                let reserve = room.reserve.unwrap_or(0) as f64;
+                (
+                    load + room.taken.max(reserve),
+                    taken + room.taken,
+                    rooms + 1,
+                )
+            })
+    }
+
+    fn report(&self) -> String {
+        let mut report = String::from("Placement:\n");
+
+        for placement in &self.placements {
+            report.push_str(&format!(
+                "  {} {} x{} -> {} ({})\n",
+                placement.room_id,
+                placement.intent,
+                placement.count,
+                placement.backend_id,
+                placement.selection
+            ));
+        }
+
+        report.push_str("\nBackends:\n");
+
+        for backend in &self.backends {
+            let (load, taken, rooms) = self.backend_usage(&backend.id);
+
+            report.push_str(&format!(
+                "  {}: capacity = {}, balancer_capacity = {}, load = {:.2}, taken = {:.2}, rooms = {}\n",
+                backend.id,
+                format_option(backend.capacity),
+                format_option(backend.balancer_capacity),
+                load,
+                taken,
+                rooms,
+            ));
+        }
+
+        report.push_str("\nRejected connects:\n");
+
+        for rejection in &self.rejections {
+            report.push_str(&format!(
+                "  {} {} x{}: {}\n",
+                rejection.room_id,
+                rejection.intent,
+                rejection.count,
+                rejection.reason.as_str()
+            ));
+        }
+
+        report
+    }
+}
+
+fn format_option(value: Option<i32>) -> String {
+    value
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| String::from("none"))
+}
+
+///////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    const BACKEND1: &str = "alpha.janus-gateway.svc.example.org";
+    const BACKEND2: &str = "beta.janus-gateway.svc.example.org";
+    const ROOM1: &str = "3a0e5f3e-7e1d-4c4b-9b5e-0f2b1d7a0001";
+    const ROOM2: &str = "3a0e5f3e-7e1d-4c4b-9b5e-0f2b1d7a0002";
+    const ROOM3: &str = "3a0e5f3e-7e1d-4c4b-9b5e-0f2b1d7a0003";
+
+    fn simulate(snapshot: serde_json::Value) -> Simulation {
+        let snapshot = serde_json::from_value::<Snapshot>(snapshot).expect("Invalid snapshot");
+        let mut simulation = Simulation::new(BalancerConfig::default(), None, &snapshot);
+
+        for connect in &snapshot.connects {
+            simulation.connect(connect);
+        }
+
+        simulation
+    }
+
+    #[test]
+    fn placement() {
+        let simulation = simulate(json!({
+            "backends": [
+                { "id": BACKEND1, "capacity": 100, "balancer_capacity": 50 },
+                { "id": BACKEND2, "capacity": 100 },
+            ],
+            "rooms": [
+                { "id": ROOM1, "audience": "example.org", "reserve": 40, "backend_id": BACKEND1 },
+                { "id": ROOM2, "audience": "example.org", "reserve": 20 },
+            ],
+            "connects": [
+                { "room_id": ROOM2, "intent": "write" },
+                { "room_id": ROOM2, "intent": "read", "count": 10 },
+            ],
+        }));
+
+        // The first backend can't host the reserve within its balancer capacity.
+        assert_eq!(simulation.placements.len(), 1);
+        assert_eq!(simulation.placements[0].backend_id.to_string(), BACKEND2);
+        assert_eq!(simulation.placements[0].selection, "capable");
+        assert_eq!(simulation.placements[0].count, 1);
+        assert!(simulation.rejections.is_empty());
+
+        let backend2 = simulation.backends[1].id.to_owned();
+        assert_eq!(simulation.backend_usage(&backend2), (20.0, 11.0, 1));
+    }
+
+    #[test]
+    fn rejections() {
+        let simulation = simulate(json!({
+            "backends": [
+                { "id": BACKEND1, "capacity": 5 },
+            ],
+            "rooms": [
+                { "id": ROOM1, "audience": "example.org", "backend_id": BACKEND1 },
+                { "id": ROOM2, "audience": "example.org" },
+            ],
+            "connects": [
+                { "room_id": ROOM1, "intent": "write" },
+                { "room_id": ROOM1, "intent": "read", "count": 10 },
+                { "room_id": ROOM2, "intent": "read" },
+                { "room_id": ROOM3, "intent": "write" },
+            ],
+        }));
+
+        let rejections = simulation
+            .rejections
+            .iter()
+            .map(|r| (r.room_id.to_string(), r.reason, r.count))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            rejections,
+            vec![
+                (ROOM1.to_owned(), RejectionReason::CapacityExceeded, 6),
+                (ROOM3.to_owned(), RejectionReason::RoomNotFound, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn reader_before_writer() {
+        let simulation = simulate(json!({
+            "backends": [
+                { "id": BACKEND1, "capacity": 100 },
+            ],
+            "rooms": [
+                { "id": ROOM1, "audience": "example.org", "reserve": 10 },
+            ],
+            "connects": [
+                { "room_id": ROOM1, "intent": "read", "count": 3 },
+                { "room_id": ROOM1, "intent": "write" },
+            ],
+        }));
+
+        // Readers get a backend but only the writer assigns it to the room.
+        let placements = simulation
+            .placements
+            .iter()
+            .map(|p| (p.intent, p.backend_id.to_string(), p.count))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            placements,
+            vec![
+                (ConnectIntent::Read, BACKEND1.to_owned(), 3),
+                (ConnectIntent::Write, BACKEND1.to_owned(), 1),
+            ]
+        );
+
+        assert!(simulation.rejections.is_empty());
+
+        let backend1 = simulation.backends[0].id.to_owned();
+        assert_eq!(simulation.backend_usage(&backend1), (10.0, 4.0, 1));
+    }
+}
@@ -9,8 +9,10 @@ use svc_authz::{cache::ConnectionPool as RedisConnectionPool, ClientMap as Authz
 
 use crate::{
     app::{
+        balancer::Balancer,
         context::{Context, GlobalContext, JanusTopics, MessageContext},
         metrics::Metrics,
+        rate_limiter::RateLimiter,
     },
     backend::janus::{client::IncomingEvent, client_pool::Clients},
     config::Config,
@@ -65,6 +67,9 @@ fn build_config() -> Config {
                 }
             }
         },
+        "sweeper": {
+            "agent_timeout": "0 seconds",
+        },
         "max_room_duration": 7,
     });
 
@@ -83,6 +88,8 @@ pub struct TestContext {
     logger: Logger,
     start_timestamp: DateTime<Utc>,
     clients: Option<Clients>,
+    rate_limiter: RateLimiter,
+    balancer: Balancer,
 }
 
 impl TestContext {
@@ -99,6 +106,8 @@ impl TestContext {
             logger: crate::LOG.new(o!()),
             start_timestamp: Utc::now(),
             clients: None,
+            rate_limiter: RateLimiter::default(),
+            balancer: Balancer::default(),
         }
     }
 
@@ -147,6 +156,14 @@ impl GlobalContext for TestContext {
         let registry = Registry::new();
         Arc::new(Metrics::new(&registry).unwrap())
     }
+
+    fn rate_limiter(&self) -> &RateLimiter {
+        &self.rate_limiter
+    }
+
+    fn balancer(&self) -> &Balancer {
+        &self.balancer
+    }
 }
 
 impl MessageContext for TestContext {
@@ -1,6 +1,7 @@
 use diesel::pg::PgConnection;
 use rand::Rng;
 use svc_agent::{AccountId, AgentId};
+use uuid::Uuid;
 
 use crate::{
     backend::janus::client::{HandleId, SessionId},
@@ -20,6 +21,13 @@ pub struct Room<'a> {
     rtc_sharing_policy: db::rtc::SharingPolicy,
     backend_id: Option<&'a AgentId>,
     reserve: Option<i32>,
+    lobby_enabled: bool,
+    default_writer_config: Option<db::room::WriterConfigDefaults>,
+    default_reader_config: Option<db::room::ReaderConfigDefaults>,
+    persistent_message_labels: Vec<String>,
+    region: Option<String>,
+    classroom_id: Option<Uuid>,
+    parent_room_id: Option<db::room::Id>,
 }
 
 impl<'a> Room<'a> {
@@ -30,6 +38,13 @@ impl<'a> Room<'a> {
             rtc_sharing_policy: db::rtc::SharingPolicy::None,
             backend_id: None,
             reserve: None,
+            lobby_enabled: false,
+            default_writer_config: None,
+            default_reader_config: None,
+            persistent_message_labels: vec![],
+            region: None,
+            classroom_id: None,
+            parent_room_id: None,
         }
     }
 
@@ -68,6 +83,55 @@ impl<'a> Room<'a> {
         }
     }
 
+    pub fn lobby_enabled(self, lobby_enabled: bool) -> Self {
+        Self {
+            lobby_enabled,
+            ..self
+        }
+    }
+
+    pub fn default_writer_config(self, defaults: db::room::WriterConfigDefaults) -> Self {
+        Self {
+            default_writer_config: Some(defaults),
+            ..self
+        }
+    }
+
+    pub fn default_reader_config(self, defaults: db::room::ReaderConfigDefaults) -> Self {
+        Self {
+            default_reader_config: Some(defaults),
+            ..self
+        }
+    }
+
+    pub fn persistent_message_labels(self, labels: &[&str]) -> Self {
+        Self {
+            persistent_message_labels: labels.iter().map(|l| l.to_string()).collect(),
+            ..self
+        }
+    }
+
+    pub fn region(self, region: &str) -> Self {
+        Self {
+            region: Some(region.to_owned()),
+            ..self
+        }
+    }
+
+    pub fn classroom_id(self, classroom_id: Uuid) -> Self {
+        Self {
+            classroom_id: Some(classroom_id),
+            ..self
+        }
+    }
+
+    pub fn parent_room_id(self, parent_room_id: db::room::Id) -> Self {
+        Self {
+            parent_room_id: Some(parent_room_id),
+            ..self
+        }
+    }
+
     pub fn insert(self, conn: &PgConnection) -> db::room::Object {
         let audience = self.audience.expect("Audience not set");
         let time = self.time.expect("Time not set");
@@ -82,6 +146,30 @@ impl<'a> Room<'a> {
             q = q.reserve(reserve);
         }
 
+        if let Some(classroom_id) = self.classroom_id {
+            q = q.classroom_id(classroom_id);
+        }
+
+        if let Some(parent_room_id) = self.parent_room_id {
+            q = q.parent_room_id(parent_room_id);
+        }
+
+        if let Some(ref defaults) = self.default_writer_config {
+            q = q.default_writer_config(defaults);
+        }
+
+        if let Some(ref defaults) = self.default_reader_config {
+            q = q.default_reader_config(defaults);
+        }
+
+        if let Some(ref region) = self.region {
+            q = q.region(region);
+        }
+
+        q = q
+            .lobby_enabled(self.lobby_enabled)
+            .persistent_message_labels(&self.persistent_message_labels);
+
         q.execute(conn).expect("Failed to insert room")
     }
 }
@@ -150,6 +238,7 @@ pub struct AgentConnection {
     agent_id: agent::Id,
     rtc_id: db::rtc::Id,
     handle_id: HandleId,
+    kind: db::janus_rtc_stream::Kind,
 }
 
 impl AgentConnection {
@@ -158,11 +247,17 @@ impl AgentConnection {
             agent_id,
             rtc_id,
             handle_id,
+            kind: db::janus_rtc_stream::Kind::default(),
         }
     }
 
+    pub fn kind(self, kind: db::janus_rtc_stream::Kind) -> Self {
+        Self { kind, ..self }
+    }
+
     pub fn insert(&self, conn: &PgConnection) -> db::agent_connection::Object {
         db::agent_connection::UpsertQuery::new(self.agent_id, self.rtc_id, self.handle_id)
+            .kind(self.kind)
             .execute(conn)
             .expect("Failed to insert agent_connection")
     }
@@ -204,6 +299,7 @@ pub struct JanusBackend {
     balancer_capacity: Option<i32>,
     group: Option<String>,
     janus_url: String,
+    region: Option<String>,
 }
 
 impl JanusBackend {
@@ -216,6 +312,7 @@ impl JanusBackend {
             balancer_capacity: None,
             group: None,
             janus_url,
+            region: None,
         }
     }
 
@@ -240,6 +337,13 @@ impl JanusBackend {
         }
     }
 
+    pub fn region(self, region: &str) -> Self {
+        Self {
+            region: Some(region.to_owned()),
+            ..self
+        }
+    }
+
     pub fn insert(&self, conn: &PgConnection) -> db::janus_backend::Object {
         let mut q = db::janus_backend::UpsertQuery::new(
             &self.id,
@@ -260,6 +364,10 @@ impl JanusBackend {
             q = q.group(group);
         }
 
+        if let Some(ref region) = self.region {
+            q = q.region(region);
+        }
+
         q.execute(conn).expect("Failed to insert janus_backend")
     }
 }
@@ -271,6 +379,7 @@ pub struct JanusRtcStream<'a> {
     backend: Option<&'a db::janus_backend::Object>,
     rtc: Option<&'a db::rtc::Object>,
     sent_by: Option<&'a AgentId>,
+    kind: db::janus_rtc_stream::Kind,
 }
 
 impl<'a> JanusRtcStream<'a> {
@@ -280,9 +389,35 @@ impl<'a> JanusRtcStream<'a> {
             backend: None,
             rtc: None,
             sent_by: None,
+            kind: db::janus_rtc_stream::Kind::default(),
+        }
+    }
+
+    pub fn rtc(self, rtc: &'a db::rtc::Object) -> Self {
+        Self {
+            rtc: Some(rtc),
+            ..self
+        }
+    }
+
+    pub fn backend(self, backend: &'a db::janus_backend::Object) -> Self {
+        Self {
+            backend: Some(backend),
+            ..self
         }
     }
 
+    pub fn sent_by(self, sent_by: &'a AgentId) -> Self {
+        Self {
+            sent_by: Some(sent_by),
+            ..self
+        }
+    }
+
+    pub fn kind(self, kind: db::janus_rtc_stream::Kind) -> Self {
+        Self { kind, ..self }
+    }
+
     pub fn insert(&self, conn: &PgConnection) -> db::janus_rtc_stream::Object {
         let default_backend;
 
@@ -322,6 +457,7 @@ impl<'a> JanusRtcStream<'a> {
             backend.id(),
             "alpha",
             sent_by,
+            self.kind,
         )
         .execute(conn)
         .expect("Failed to insert janus_rtc_stream")
@@ -333,6 +469,7 @@ impl<'a> JanusRtcStream<'a> {
 #[derive(Default)]
 pub struct Recording<'a> {
     rtc: Option<&'a db::rtc::Object>,
+    kind: db::janus_rtc_stream::Kind,
 }
 
 impl<'a> Recording<'a> {
@@ -347,6 +484,10 @@ impl<'a> Recording<'a> {
         }
     }
 
+    pub fn kind(self, kind: db::janus_rtc_stream::Kind) -> Self {
+        Self { kind, ..self }
+    }
+
     pub fn insert(&self, conn: &PgConnection) -> db::recording::Object {
         let default_rtc;
 
@@ -358,7 +499,7 @@ impl<'a> Recording<'a> {
             }
         };
 
-        db::recording::InsertQuery::new(rtc.id())
+        db::recording::InsertQuery::new(rtc.id(), self.kind)
             .execute(conn)
             .expect("Failed to insert recording")
     }
@@ -420,6 +561,8 @@ pub struct RtcWriterConfig<'a> {
     send_audio: Option<bool>,
     video_remb: Option<i64>,
     send_audio_updated_by: Option<&'a AgentId>,
+    send_audio_locked: Option<bool>,
+    kind: db::janus_rtc_stream::Kind,
 }
 
 impl<'a> RtcWriterConfig<'a> {
@@ -430,9 +573,15 @@ impl<'a> RtcWriterConfig<'a> {
             send_audio: None,
             video_remb: None,
             send_audio_updated_by: None,
+            send_audio_locked: None,
+            kind: db::janus_rtc_stream::Kind::default(),
         }
     }
 
+    pub fn kind(self, kind: db::janus_rtc_stream::Kind) -> Self {
+        Self { kind, ..self }
+    }
+
     pub fn send_video(self, send_video: bool) -> Self {
         Self {
             send_video: Some(send_video),
@@ -461,8 +610,15 @@ impl<'a> RtcWriterConfig<'a> {
         }
     }
 
+    pub fn send_audio_locked(self, send_audio_locked: bool) -> Self {
+        Self {
+            send_audio_locked: Some(send_audio_locked),
+            ..self
+        }
+    }
+
     pub fn insert(&self, conn: &PgConnection) -> db::rtc_writer_config::Object {
-        let mut q = db::rtc_writer_config::UpsertQuery::new(self.rtc.id());
+        let mut q = db::rtc_writer_config::UpsertQuery::new(self.rtc.id()).kind(self.kind);
 
         if let Some(send_video) = self.send_video {
             q = q.send_video(send_video);
@@ -480,6 +636,10 @@ impl<'a> RtcWriterConfig<'a> {
             q = q.send_audio_updated_by(send_audio_updated_by);
         }
 
+        if let Some(send_audio_locked) = self.send_audio_locked {
+            q = q.send_audio_locked(send_audio_locked);
+        }
+
         q.execute(conn).expect("Failed to insert RTC writer config")
     }
 }
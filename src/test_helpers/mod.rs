@@ -3,7 +3,10 @@ use chrono::Utc;
 use serde::de::DeserializeOwned;
 use serde_json::json;
 use svc_agent::{
-    mqtt::{IncomingEventProperties, IncomingRequestProperties, IncomingResponseProperties},
+    mqtt::{
+        IncomingEventProperties, IncomingRequestProperties, IncomingResponseProperties,
+        ResponseStatus,
+    },
     AgentId,
 };
 use uuid::Uuid;
@@ -60,7 +63,7 @@ pub async fn handle_event<H: EventHandler>(
     Ok(parse_messages(messages).await)
 }
 
-async fn parse_messages(mut messages: MessageStream) -> Vec<OutgoingEnvelope> {
+pub async fn parse_messages(mut messages: MessageStream) -> Vec<OutgoingEnvelope> {
     let mut parsed_messages = vec![];
 
     while let Some(message) = messages.next().await {
@@ -165,11 +168,18 @@ pub fn build_reqp(agent_id: &AgentId, method: &str) -> IncomingRequestProperties
 }
 
 pub fn build_respp(agent_id: &AgentId) -> IncomingResponseProperties {
+    build_respp_with_status(agent_id, ResponseStatus::OK)
+}
+
+pub fn build_respp_with_status(
+    agent_id: &AgentId,
+    status: ResponseStatus,
+) -> IncomingResponseProperties {
     let now = Utc::now().timestamp_millis().to_string();
 
     let respp_json = json!({
         "type": "response",
-        "status": "200",
+        "status": status.as_u16().to_string(),
         "correlation_data": "ignore",
         "agent_id": agent_id,
         "connection_mode": "default",
@@ -220,8 +230,9 @@ pub mod prelude {
     #[allow(unused_imports)]
     pub use super::{
         agent::TestAgent, authz::TestAuthz, build_evp, build_reqp, build_respp,
-        context::TestContext, db::TestDb, factory, find_event, find_request, find_response,
-        handle_event, handle_request, handle_response, shared_helpers, SVC_AUDIENCE, USR_AUDIENCE,
+        build_respp_with_status, context::TestContext, db::TestDb, factory, find_event,
+        find_request, find_response, handle_event, handle_request, handle_response, parse_messages,
+        shared_helpers, SVC_AUDIENCE, USR_AUDIENCE,
     };
 }
 